@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use crate::{Timestamp, TokenAmount, Transfer, TransferGraph};
+
+/// All transfers of one token from one address to another, merged
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregatedEdge<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub token_symbol: &'a str,
+    /// The merged transfers, in insertion order
+    pub transfers: Vec<&'a Transfer>,
+    /// Sum of the amounts, or `None` on overflow
+    pub total: Option<TokenAmount>,
+    pub min: TokenAmount,
+    pub max: TokenAmount,
+    /// Earliest timestamp, if any transfer is timed
+    pub first: Option<Timestamp>,
+    /// Latest timestamp, if any transfer is timed
+    pub last: Option<Timestamp>,
+}
+
+impl AggregatedEdge<'_> {
+    /// Number of merged transfers
+    pub fn count(&self) -> usize {
+        self.transfers.len()
+    }
+
+    /// The edge as a single transfer: the total amount at the first
+    /// timestamp (and its slot), with a note summarising the merged
+    /// transfers. A lone transfer is returned unchanged. `None` if the
+    /// total overflows.
+    pub fn to_transfer(&self) -> Option<Transfer> {
+        if let [only] = self.transfers.as_slice() {
+            return Some((*only).clone());
+        }
+        let mut note = format!("{} transfers, {} to {}", self.count(), self.min, self.max);
+        if let (Some(first), Some(last)) = (self.first, self.last) {
+            if first != last {
+                note.push_str(&format!(", until {}", last));
+            }
+        }
+        let earliest = self
+            .transfers
+            .iter()
+            .find(|t| t.timestamp.is_some() && t.timestamp == self.first);
+        Some(Transfer {
+            from: self.from.to_string(),
+            to: self.to.to_string(),
+            amount: self.total?,
+            token_symbol: self.token_symbol.to_string(),
+            timestamp: self.first,
+            slot: earliest.and_then(|t| t.slot),
+            note: Some(note),
+        })
+    }
+}
+
+impl TransferGraph {
+    /// Transfers merged per (sender, receiver, token)
+    ///
+    /// Edges are ordered by sender address, then by the first transfer of
+    /// each pair in the sender's outgoing list.
+    pub fn aggregated_edges(&self) -> Vec<AggregatedEdge<'_>> {
+        let mut edges: Vec<AggregatedEdge> = Vec::new();
+        for node in self.sorted_nodes() {
+            let mut index: HashMap<(&str, &str), usize> = HashMap::new();
+            for transfer in &node.outgoing {
+                let key = (transfer.to.as_str(), transfer.token_symbol.as_str());
+                match index.get(&key) {
+                    Some(&i) => {
+                        let edge = &mut edges[i];
+                        edge.transfers.push(transfer);
+                        edge.total = edge
+                            .total
+                            .and_then(|total| total.checked_add(&transfer.amount));
+                        edge.min = edge.min.min(transfer.amount);
+                        edge.max = edge.max.max(transfer.amount);
+                        if let Some(ts) = transfer.timestamp {
+                            edge.first = Some(edge.first.map_or(ts, |f| f.min(ts)));
+                            edge.last = Some(edge.last.map_or(ts, |l| l.max(ts)));
+                        }
+                    }
+                    None => {
+                        index.insert(key, edges.len());
+                        edges.push(AggregatedEdge {
+                            from: &transfer.from,
+                            to: &transfer.to,
+                            token_symbol: &transfer.token_symbol,
+                            transfers: vec![transfer],
+                            total: Some(transfer.amount),
+                            min: transfer.amount,
+                            max: transfer.amount,
+                            first: transfer.timestamp,
+                            last: transfer.timestamp,
+                        });
+                    }
+                }
+            }
+        }
+        edges
+    }
+
+    /// A copy of the graph with one transfer per aggregated edge (see
+    /// [`AggregatedEdge::to_transfer`])
+    ///
+    /// Every query and renderer works on the result, so the aggregated view
+    /// can stand in for the raw graph anywhere. Edges whose total would
+    /// overflow keep their individual transfers. Labels, origin, target and
+    /// configuration are preserved.
+    pub fn aggregated(&self) -> TransferGraph {
+        let mut graph = TransferGraph::with_config(self.render_config.clone());
+        graph.origin = self.origin.clone();
+        graph.target = self.target.clone();
+        graph.token_name = self.token_name.clone();
+        graph.token_mint = self.token_mint.clone();
+
+        for edge in self.aggregated_edges() {
+            match edge.to_transfer() {
+                Some(transfer) => graph.add_transfer(transfer),
+                None => {
+                    for transfer in edge.transfers {
+                        graph.add_transfer(transfer.clone());
+                    }
+                }
+            }
+        }
+        for node in self.nodes.values() {
+            if let Some(label) = &node.label {
+                graph.set_node_label(&node.address, label.clone());
+            }
+        }
+        graph
+    }
+
+    /// The graph drawing renderers should draw instead of `self` when
+    /// `RenderConfig::collapse_clusters` or `aggregate_edges` is set
+    pub(crate) fn render_view(&self) -> Option<TransferGraph> {
+        let cfg = &self.render_config;
+        if cfg.collapse_clusters.is_none() && !cfg.aggregate_edges {
+            return None;
+        }
+        let mut graph = match cfg.collapse_clusters {
+            Some(method) => self.collapse_clusters(&self.clusters(method)),
+            None => self.clone(),
+        };
+        if cfg.aggregate_edges {
+            graph = graph.aggregated();
+        }
+        graph.render_config.collapse_clusters = None;
+        graph.render_config.aggregate_edges = false;
+        Some(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str, amount: u64, timestamp: Option<&str>) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: timestamp.map(|ts| ts.parse().unwrap()),
+            slot: None,
+            note: None,
+        }
+    }
+
+    fn graph() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 5, Some("2024-01-03")));
+        graph.add_transfer(transfer("A", "C", 7, None));
+        graph.add_transfer(transfer("A", "B", 2, Some("2024-01-01")));
+        graph.add_transfer(transfer("A", "B", 9, None));
+        graph.add_transfer(Transfer {
+            token_symbol: "USDC".to_string(),
+            ..transfer("A", "B", 1, None)
+        });
+        graph.add_transfer(transfer("B", "C", 4, Some("2024-01-02")));
+        graph.set_node_label("B", "Hub".to_string());
+        graph
+    }
+
+    #[test]
+    fn test_aggregated_edges() {
+        let graph = graph();
+        let edges = graph.aggregated_edges();
+        let keys: Vec<(&str, &str, &str, usize)> = edges
+            .iter()
+            .map(|e| (e.from, e.to, e.token_symbol, e.count()))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                ("A", "B", "SVMAI", 3),
+                ("A", "C", "SVMAI", 1),
+                ("A", "B", "USDC", 1),
+                ("B", "C", "SVMAI", 1)
+            ]
+        );
+
+        let ab = &edges[0];
+        assert_eq!(ab.total, Some(TokenAmount::from(16)));
+        assert_eq!(
+            (ab.min, ab.max),
+            (TokenAmount::from(2), TokenAmount::from(9))
+        );
+        assert_eq!(ab.first, Some("2024-01-01".parse().unwrap()));
+        assert_eq!(ab.last, Some("2024-01-03".parse().unwrap()));
+
+        let merged = ab.to_transfer().unwrap();
+        assert_eq!(merged.amount, TokenAmount::from(16));
+        assert_eq!(merged.timestamp, ab.first);
+        assert_eq!(
+            merged.note.as_deref(),
+            Some("3 transfers, 2 to 9, until 2024-01-03T00:00:00Z")
+        );
+        assert_eq!(edges[1].to_transfer().unwrap(), *edges[1].transfers[0]);
+    }
+
+    #[test]
+    fn test_aggregated_graph() {
+        let raw = graph();
+        let graph = raw.aggregated();
+        assert_eq!(graph.nodes["A"].outgoing.len(), 3);
+        assert_eq!(graph.nodes["B"].incoming.len(), 2);
+        assert_eq!(graph.nodes["B"].label.as_deref(), Some("Hub"));
+        // One path per transfer combination: 3 via B plus the direct one,
+        // down to one via B per token
+        assert_eq!(raw.find_paths("A", "C").len(), 5);
+        assert_eq!(graph.find_paths("A", "C").len(), 3);
+
+        let mut raw = raw;
+        raw.origin = Some("A".to_string());
+        assert_eq!(raw.render_ascii().matches("────────→ B").count(), 4);
+        raw.render_config.aggregate_edges = true;
+        let output = raw.render_ascii();
+        assert_eq!(output.matches("────────→ B").count(), 2);
+        assert!(output.contains("[16.00 SVMAI]"));
+    }
+}
@@ -0,0 +1,336 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Exact token amount: an integer number of base units plus the mint decimals
+///
+/// `TokenAmount::new(1_500_000_000, 9)` is 1.5 tokens of a 9-decimal mint.
+/// Arithmetic rescales both sides to the larger number of decimals, so it
+/// never loses precision; it returns `None` instead of overflowing. Amounts
+/// are signed so net flows can be represented, although transfers themselves
+/// are expected to be non-negative.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenAmount {
+    pub raw: i128,
+    pub decimals: u8,
+}
+
+/// Error produced when a decimal string is not a valid [`TokenAmount`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmountError {
+    pub input: String,
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid amount `{}`", self.input)
+    }
+}
+
+impl std::error::Error for AmountError {}
+
+impl TokenAmount {
+    pub const ZERO: TokenAmount = TokenAmount {
+        raw: 0,
+        decimals: 0,
+    };
+
+    pub fn new(raw: i128, decimals: u8) -> Self {
+        TokenAmount { raw, decimals }
+    }
+
+    /// Amount from unsigned on-chain base units (e.g. an SPL token balance)
+    pub fn from_base_units(raw: u64, decimals: u8) -> Self {
+        TokenAmount {
+            raw: raw as i128,
+            decimals,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.raw < 0
+    }
+
+    /// Express the amount with `decimals` fractional digits, failing if that
+    /// would drop non-zero digits or overflow
+    pub fn rescale(&self, decimals: u8) -> Option<TokenAmount> {
+        match decimals.cmp(&self.decimals) {
+            Ordering::Equal => Some(*self),
+            Ordering::Greater => {
+                let factor = 10i128.checked_pow((decimals - self.decimals) as u32)?;
+                Some(TokenAmount::new(self.raw.checked_mul(factor)?, decimals))
+            }
+            Ordering::Less => {
+                let factor = 10i128.checked_pow((self.decimals - decimals) as u32)?;
+                (self.raw % factor == 0).then(|| TokenAmount::new(self.raw / factor, decimals))
+            }
+        }
+    }
+
+    pub fn checked_add(&self, other: &TokenAmount) -> Option<TokenAmount> {
+        let decimals = self.decimals.max(other.decimals);
+        let a = self.rescale(decimals)?;
+        let b = other.rescale(decimals)?;
+        Some(TokenAmount::new(a.raw.checked_add(b.raw)?, decimals))
+    }
+
+    pub fn checked_sub(&self, other: &TokenAmount) -> Option<TokenAmount> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    pub fn checked_neg(&self) -> Option<TokenAmount> {
+        Some(TokenAmount::new(self.raw.checked_neg()?, self.decimals))
+    }
+
+    pub fn abs(&self) -> TokenAmount {
+        TokenAmount::new(self.raw.saturating_abs(), self.decimals)
+    }
+
+    /// Exact sum of all amounts, or `None` on overflow
+    pub fn checked_sum<'a, I>(amounts: I) -> Option<TokenAmount>
+    where
+        I: IntoIterator<Item = &'a TokenAmount>,
+    {
+        amounts
+            .into_iter()
+            .try_fold(TokenAmount::ZERO, |acc, amount| acc.checked_add(amount))
+    }
+
+    /// Approximate value as a float, for layout and scaling only
+    pub fn to_f64(&self) -> f64 {
+        self.raw as f64 / 10f64.powi(self.decimals as i32)
+    }
+
+    /// Format with thousands separators and exactly `places` fractional
+    /// digits, rounding half away from zero when digits are dropped
+    pub fn format_grouped(&self, places: u8) -> String {
+        let scaled = if places >= self.decimals {
+            self.rescale(places).map(|a| a.raw.unsigned_abs())
+        } else {
+            let magnitude = self.raw.unsigned_abs();
+            match 10u128.checked_pow((self.decimals - places) as u32) {
+                Some(factor) => {
+                    Some(magnitude / factor + u128::from(magnitude % factor >= factor.div_ceil(2)))
+                }
+                // The divisor exceeds any representable magnitude
+                None => Some(0),
+            }
+        };
+
+        let Some(magnitude) = scaled else {
+            // Too many digits to rescale; fall back to the exact form
+            return self.to_string();
+        };
+
+        let digits = format!("{:0>width$}", magnitude, width = places as usize + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - places as usize);
+
+        let mut result = String::new();
+        if self.raw < 0 && magnitude != 0 {
+            result.push('-');
+        }
+        for (i, c) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i).is_multiple_of(3) {
+                result.push(',');
+            }
+            result.push(c);
+        }
+        if places > 0 {
+            result.push('.');
+            result.push_str(fraction);
+        }
+        result
+    }
+}
+
+impl From<u64> for TokenAmount {
+    fn from(value: u64) -> Self {
+        TokenAmount::from_base_units(value, 0)
+    }
+}
+
+impl PartialEq for TokenAmount {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TokenAmount {}
+
+impl PartialOrd for TokenAmount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TokenAmount {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let decimals = self.decimals.max(other.decimals);
+        match (self.rescale(decimals), other.rescale(decimals)) {
+            (Some(a), Some(b)) => a.raw.cmp(&b.raw),
+            // The side that overflows when rescaled has the larger magnitude
+            (None, _) => self.raw.signum().cmp(&0),
+            (_, None) => 0.cmp(&other.raw.signum()),
+        }
+    }
+}
+
+/// Exact decimal form with all `decimals` fractional digits, e.g. `-12.500`
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = format!(
+            "{:0>width$}",
+            self.raw.unsigned_abs(),
+            width = self.decimals as usize + 1
+        );
+        let (integer, fraction) = digits.split_at(digits.len() - self.decimals as usize);
+        if self.raw < 0 {
+            f.write_str("-")?;
+        }
+        f.write_str(integer)?;
+        if self.decimals > 0 {
+            write!(f, ".{}", fraction)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a plain decimal string; the number of fractional digits becomes the
+/// amount's `decimals`
+impl FromStr for TokenAmount {
+    type Err = AmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || AmountError {
+            input: s.to_string(),
+        };
+
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let all_digits = |p: &str| p.bytes().all(|b| b.is_ascii_digit());
+        if integer.is_empty() || !all_digits(integer) || !all_digits(fraction) {
+            return Err(err());
+        }
+        if unsigned.ends_with('.') {
+            return Err(err());
+        }
+
+        let decimals = u8::try_from(fraction.len()).map_err(|_| err())?;
+        let mut raw: i128 = format!("{}{}", integer, fraction)
+            .parse()
+            .map_err(|_| err())?;
+        if negative {
+            raw = -raw;
+        }
+        // Reject amounts whose scale can never be used in arithmetic
+        10i128.checked_pow(decimals as u32).ok_or_else(err)?;
+
+        Ok(TokenAmount::new(raw, decimals))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TokenAmount {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Accepts the exact decimal string written by `Serialize`, or a plain JSON
+/// number (as written by schema version 1)
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TokenAmount {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AmountVisitor;
+
+        impl serde::de::Visitor<'_> for AmountVisitor {
+            type Value = TokenAmount;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a decimal string or number")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<TokenAmount, E> {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<TokenAmount, E> {
+                Ok(TokenAmount::from(v))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<TokenAmount, E> {
+                Ok(TokenAmount::new(v as i128, 0))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<TokenAmount, E> {
+                // `Display` for f64 prints the shortest round-tripping decimal
+                v.to_string().parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(s: &str) -> TokenAmount {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(amount("31151612.000000000").raw, 31_151_612_000_000_000);
+        assert_eq!(amount("31151612.000000000").decimals, 9);
+        assert_eq!(amount("-0.05").to_string(), "-0.05");
+        assert_eq!(TokenAmount::new(5, 3).to_string(), "0.005");
+        assert_eq!(TokenAmount::from(42).to_string(), "42");
+        for bad in ["", "1.", ".5", "1,000", "1e5", "--1", "abc"] {
+            assert!(bad.parse::<TokenAmount>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let a = TokenAmount::new(1_000_000_001, 9);
+        let b = TokenAmount::from(2);
+        let sum = a.checked_add(&b).unwrap();
+        assert_eq!(sum, TokenAmount::new(3_000_000_001, 9));
+        assert_eq!(sum.to_string(), "3.000000001");
+        assert_eq!(b.checked_sub(&sum).unwrap().to_string(), "-1.000000001");
+        assert_eq!(amount("1.50"), amount("1.5"));
+        assert!(amount("1.5") < amount("1.51"));
+
+        let total = TokenAmount::checked_sum(&[amount("0.1"), amount("0.2"), amount("0.3")]);
+        assert_eq!(total.unwrap().to_string(), "0.6");
+
+        let max = TokenAmount::new(i128::MAX, 0);
+        assert_eq!(max.checked_add(&TokenAmount::from(1)), None);
+        assert_eq!(max.rescale(1), None);
+        assert_eq!(amount("1.25").rescale(1), None);
+        assert_eq!(amount("1.20").rescale(1).unwrap().raw, 12);
+    }
+
+    #[test]
+    fn test_format_grouped() {
+        assert_eq!(amount("31151612").format_grouped(2), "31,151,612.00");
+        assert_eq!(amount("1234567.895").format_grouped(2), "1,234,567.90");
+        assert_eq!(amount("-123456.004").format_grouped(2), "-123,456.00");
+        assert_eq!(amount("-999.995").format_grouped(2), "-1,000.00");
+        assert_eq!(amount("-0.001").format_grouped(2), "0.00");
+        assert_eq!(amount("0.5").format_grouped(0), "1");
+        assert_eq!(
+            TokenAmount::new(16_000_000_000_000_001, 9).format_grouped(9),
+            "16,000,000.000000001"
+        );
+    }
+}
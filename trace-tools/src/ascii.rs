@@ -0,0 +1,740 @@
+use std::io::{self, Write};
+
+use crate::output::render_to_string;
+use crate::renderer::{CyclesSummary, EdgeVisit, NodeVisit, PathsSummary, Renderer, StatsSummary};
+use crate::terminal::{self, char_width, clip, display_width, Style};
+use crate::tree::TreeNumbers;
+use crate::{
+    AsciiLayout, Charset, ColorMode, CycleLimit, LimitedWriter, MeetingPoint, OutputLimit,
+    OutputLimits, PathLimit, TokenAmount, TraceDirection, Transfer, TransferGraph,
+};
+
+/// Options only the ASCII renderer uses, see [`RenderConfig::ascii`](crate::RenderConfig::ascii)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct AsciiConfig {
+    /// Draw the graph as indented trees or as columns by hop distance
+    pub layout: AsciiLayout,
+    /// Characters per line the layered layout fits into
+    pub layout_width: usize,
+    /// Draw lines, boxes and icons with Unicode or plain ASCII
+    pub charset: Charset,
+    /// Colour the origin, target, labels and large amounts with ANSI escapes
+    pub color: ColorMode,
+    /// Amounts coloured as large; `None` picks the largest tenth of the
+    /// transfers
+    pub highlight_amount: Option<TokenAmount>,
+    /// Narrow the title box, separators and stats box to this many columns
+    pub max_width: Option<usize>,
+    /// Where the output is cut short with a notice
+    pub output_limits: OutputLimits,
+}
+
+impl Default for AsciiConfig {
+    fn default() -> Self {
+        AsciiConfig {
+            layout: AsciiLayout::default(),
+            layout_width: 120,
+            charset: Charset::default(),
+            color: ColorMode::default(),
+            highlight_amount: None,
+            max_width: None,
+            output_limits: OutputLimits::default(),
+        }
+    }
+}
+
+/// Tree depth past which lines are no longer indented further
+const MAX_INDENT: usize = 32;
+
+/// The flow diagram of [`TransferGraph::render_ascii`] as a [`Renderer`],
+/// written to `W` as it is drawn
+///
+/// Tree lines are written as the walk reaches them instead of being
+/// collected first. What the renderer holds is the node numbers, worked out
+/// by a walk ahead of each tree, and a line per open node, so memory grows
+/// with the size and depth of the tree rather than with the output.
+/// Indentation stops at 32 levels, past which lines show their depth
+/// instead. [`AsciiLayout::Layered`] is different: it lays out the whole
+/// graph first, then builds a character grid for one band of columns at a
+/// time and writes it row by row. Aggregating edges or collapsing clusters
+/// draws a copy of the graph. Once an [`OutputLimits`] limit is reached the
+/// walk or drawing stops and a notice ends the output.
+pub struct AsciiRenderer<W: Write> {
+    config: AsciiConfig,
+    /// `ColorMode` settled when the renderer was made
+    color: bool,
+    /// Amounts from this one up are coloured
+    highlight: Option<TokenAmount>,
+    out: LimitedWriter<W>,
+    direction: TraceDirection,
+    numbers: TreeNumbers,
+    /// Line each open node adds once its subtree is drawn: the blank line
+    /// after a forward subtree or the edge below a funder
+    pending: Vec<Option<String>>,
+    /// Lines of the open nodes of a backward tree, drawn below their funders
+    funded: Vec<String>,
+}
+
+impl<W: Write> AsciiRenderer<W> {
+    /// A renderer writing to `out` until `AsciiConfig::output_limits` is
+    /// reached, with `ColorMode::Auto` settled for stdout as it is now
+    pub fn new(config: AsciiConfig, out: W) -> Self {
+        AsciiRenderer {
+            color: config.color.enabled(),
+            out: LimitedWriter::new(out, config.output_limits),
+            config,
+            highlight: None,
+            direction: TraceDirection::Forward,
+            numbers: TreeNumbers::default(),
+            pending: Vec::new(),
+            funded: Vec::new(),
+        }
+    }
+
+    /// The writer, with everything rendered so far
+    pub fn into_inner(self) -> W {
+        self.out.into_inner()
+    }
+
+    pub(crate) fn config(&self) -> &AsciiConfig {
+        &self.config
+    }
+
+    /// Write `text`, in plain ASCII under `Charset::Ascii`
+    pub(crate) fn write(&mut self, text: &str) -> io::Result<()> {
+        match self.config.charset {
+            Charset::Unicode => self.out.write_all(text.as_bytes()),
+            Charset::Ascii => self.out.write_all(terminal::to_ascii(text).as_bytes()),
+        }
+    }
+
+    /// Write one tree line
+    fn line(&mut self, line: &str) -> io::Result<()> {
+        self.write(line)?;
+        self.write("\n")
+    }
+
+    /// Icon drawn before `addr`: the origin, target or node icon, without
+    /// emoji under `Charset::Ascii`
+    pub(crate) fn icon(&self, graph: &TransferGraph, addr: &str) -> String {
+        let cfg = &graph.render_config;
+        let icon = if Some(addr) == graph.origin.as_deref() {
+            &cfg.origin_icon
+        } else if Some(addr) == graph.target.as_deref() {
+            &cfg.target_icon
+        } else {
+            &cfg.node_icon
+        };
+        match self.config.charset {
+            Charset::Unicode => icon.clone(),
+            Charset::Ascii => {
+                let plain: String = icon.chars().filter(|&c| char_width(c) == 1).collect();
+                plain.trim().to_string()
+            }
+        }
+    }
+
+    /// Style of the name of `addr`, if it stands out
+    pub(crate) fn address_style(&self, graph: &TransferGraph, addr: &str) -> Option<Style> {
+        if Some(addr) == graph.origin.as_deref() {
+            Some(Style::Origin)
+        } else if Some(addr) == graph.target.as_deref() {
+            Some(Style::Target)
+        } else {
+            None
+        }
+    }
+
+    /// Style of an amount: highlighted from `AsciiConfig::highlight_amount`
+    pub(crate) fn amount_style(&self, amount: &TokenAmount) -> Option<Style> {
+        self.highlight
+            .is_some_and(|threshold| *amount >= threshold)
+            .then_some(Style::Amount)
+    }
+
+    /// `text` wrapped in the ANSI escapes of `style` when colour is on
+    pub(crate) fn paint(&self, text: &str, style: Option<Style>) -> String {
+        match style {
+            Some(style) if self.color => style.paint(text),
+            _ => text.to_string(),
+        }
+    }
+
+    /// Columns the title box, stats box and separators take up: 76, or
+    /// `AsciiConfig::max_width` when narrower
+    fn frame_width(&self) -> usize {
+        self.config
+            .max_width
+            .map_or(76, |width| width.clamp(40, 76))
+    }
+
+    /// A line of `fill` as long as `width`, cut to the frame width
+    fn rule(&self, fill: char, width: usize) -> String {
+        std::iter::repeat_n(fill, width.min(self.frame_width())).collect()
+    }
+
+    /// The separator and heading a section after the trees starts with
+    fn section(&mut self, heading: &str) -> io::Result<()> {
+        let rule = self.rule('━', 72);
+        self.write(&format!("\n{}\n\n{}\n\n", rule, heading))
+    }
+
+    /// A stats box row with `left` aligned left and `right` aligned right,
+    /// cutting `left` short when both do not fit
+    fn box_row(&self, left: &str, right: &str) -> String {
+        let inner = self.frame_width() - 5;
+        let right_width = display_width(right);
+        let mut left = left.to_string();
+        if display_width(&left) + right_width + 1 > inner && !right.is_empty() {
+            left = clip(&left, inner.saturating_sub(right_width + 1));
+        } else if display_width(&left) > inner {
+            left = clip(&left, inner);
+        }
+        let padding = inner.saturating_sub(display_width(&left) + right_width);
+        format!("│ {}{}{} │\n", left, " ".repeat(padding), right)
+    }
+
+    /// Icon, label, address and taint share of a tree node, indented for
+    /// its depth, without a line break
+    fn node_line(&self, graph: &TransferGraph, node: &NodeVisit) -> String {
+        let icon = self.paint(
+            &self.icon(graph, node.address),
+            self.address_style(graph, node.address),
+        );
+        let mut line = format!("{}{}", indent(node.depth), icon);
+
+        if let Some(label) = node.label {
+            line.push_str(&format!(" {}", self.paint(label, Some(Style::Label))));
+        }
+        let length = graph.render_config.address_truncate_length;
+        line.push_str(&format!(
+            " {}",
+            graph.truncate_address(node.address, length)
+        ));
+        if let Some(taint) = node.taint {
+            line.push_str(&format!(" [taint {:.1}%]", taint.fraction() * 100.0));
+        }
+        if node.beyond_depth > 0 {
+            line.push_str(&format!(" [+{} beyond depth]", node.beyond_depth));
+        }
+        line
+    }
+
+    /// The connector, label and receiver of a tree edge
+    fn edge_line(&self, graph: &TransferGraph, edge: &EdgeVisit, connector: &str) -> String {
+        let transfer = edge.transfer;
+        format!(
+            "{}      {} {} ────────→ {}",
+            indent(edge.depth),
+            connector,
+            self.transfer_label(graph, transfer),
+            graph.truncate_address(&transfer.to, graph.render_config.address_truncate_length)
+        )
+    }
+
+    /// Amount, time and note of a transfer as shown on tree edges
+    fn transfer_label(&self, graph: &TransferGraph, transfer: &Transfer) -> String {
+        let amount = format!(
+            "{} {}",
+            graph.format_amount(&transfer.amount),
+            transfer.token_symbol
+        );
+        let mut label = format!(
+            "[{}]",
+            self.paint(&amount, self.amount_style(&transfer.amount))
+        );
+        if let Some(time) = graph.format_time(transfer) {
+            label.push_str(&format!(" ({})", time));
+        }
+        if let Some(note) = &transfer.note {
+            label.push_str(&format!(" [{}]", note));
+        }
+        label
+    }
+
+    /// Label followed by the truncated address, or just the address
+    fn display_name(&self, graph: &TransferGraph, addr: &str) -> String {
+        let short = graph.truncate_address(addr, 8);
+        match graph.nodes.get(addr).and_then(|n| n.label.as_ref()) {
+            Some(label) => format!("{} ({})", self.paint(label, Some(Style::Label)), short),
+            None => short,
+        }
+    }
+}
+
+impl<W: Write> Renderer for AsciiRenderer<W> {
+    fn header(&mut self, graph: &TransferGraph) -> io::Result<()> {
+        if self.color {
+            self.highlight = self
+                .config
+                .highlight_amount
+                .or_else(|| Some(large_amount(graph)));
+        }
+        let mut output = String::new();
+
+        // Header with configurable title
+        if graph.render_config.show_header {
+            let inner = self.frame_width() - 2;
+            let title = clip(&graph.render_config.title, inner);
+            output.push_str(&format!("╔{}╗\n", "═".repeat(inner)));
+            output.push_str(&format!("║{}║\n", center_text(&title, inner)));
+            output.push_str(&format!("╚{}╝\n\n", "═".repeat(inner)));
+        }
+
+        if let Some(token) = &graph.token_name {
+            output.push_str(&format!("TOKEN: {}", token));
+            if let Some(mint) = &graph.token_mint {
+                output.push_str(&format!(" ({})", graph.truncate_address(mint, 8)));
+            }
+            output.push('\n');
+        }
+
+        if let Some(target) = &graph.target {
+            output.push_str(&format!("TARGET: {}\n\n", target));
+        }
+
+        output.push_str(&format!("{}\n\n", self.rule('═', 75)));
+        self.write(&output)
+    }
+
+    fn body(&mut self, graph: &TransferGraph) -> io::Result<bool> {
+        match self.config.layout {
+            AsciiLayout::Tree => Ok(true),
+            // Every address in columns instead of the trees
+            AsciiLayout::Layered => {
+                self.render_layered(graph)?;
+                Ok(false)
+            }
+        }
+    }
+
+    fn begin_tree(&mut self, graph: &TransferGraph, direction: TraceDirection) -> io::Result<()> {
+        self.direction = direction;
+        self.numbers = TreeNumbers::new(graph, direction)?;
+        if direction == TraceDirection::Backward
+            && graph.render_config.tree.direction == TraceDirection::Both
+        {
+            let rule = self.rule('━', 72);
+            self.write(&format!("\n{}\n\nFUNDING SOURCES OF TARGET:\n\n", rule))?;
+        }
+        Ok(())
+    }
+
+    fn node(&mut self, graph: &TransferGraph, node: &NodeVisit<'_>) -> io::Result<()> {
+        let line = self.node_line(graph, node);
+        match self.direction {
+            // Who funded a node is drawn above it
+            TraceDirection::Backward => {
+                self.funded.push(line);
+                Ok(())
+            }
+            _ => {
+                let suffix = self.numbers.node_suffix(node.address);
+                self.line(&(line + &suffix))
+            }
+        }
+    }
+
+    fn edge(&mut self, graph: &TransferGraph, edge: &EdgeVisit<'_>) -> io::Result<()> {
+        let transfer = edge.transfer;
+        if self.direction == TraceDirection::Backward {
+            let connector = if edge.index == 0 {
+                "┌──────→"
+            } else {
+                "├──────→"
+            };
+            let line = self.edge_line(graph, edge, connector);
+            if edge.reference {
+                let suffix = self.numbers.reference_suffix(&transfer.from);
+                return self.line(&(line + &suffix));
+            }
+            // Blank line between sibling funders
+            if edge.index > 0 {
+                self.line("")?;
+            }
+            self.pending.push(Some(line));
+        } else {
+            let connector = if edge.is_last() {
+                "└──────→"
+            } else {
+                "├──────→"
+            };
+            let line = self.edge_line(graph, edge, connector);
+            if edge.reference {
+                // Drawn elsewhere: point there instead
+                let suffix = self.numbers.reference_suffix(&transfer.to);
+                return self.line(&(line + &suffix));
+            }
+            self.line(&line)?;
+            // Blank line after each child subtree for better visual separation
+            self.pending.push((!edge.is_last()).then(String::new));
+        }
+        Ok(())
+    }
+
+    fn end_node(&mut self, _graph: &TransferGraph, node: &NodeVisit<'_>) -> io::Result<()> {
+        if self.direction == TraceDirection::Backward {
+            let line = self.funded.pop().unwrap_or_default();
+            let suffix = self.numbers.node_suffix(node.address);
+            self.line(&(line + &suffix))?;
+        }
+        if node.depth > 0 {
+            if let Some(Some(line)) = self.pending.pop() {
+                self.line(&line)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn meeting_points(
+        &mut self,
+        graph: &TransferGraph,
+        points: &[MeetingPoint<'_>],
+    ) -> io::Result<()> {
+        self.section(&format!("MEETING POINTS ({} addresses):", points.len()))?;
+        let mut output = String::new();
+        for point in points {
+            let hops = if point.from_origin == 1 {
+                "hop"
+            } else {
+                "hops"
+            };
+            output.push_str(&format!(
+                "{} {}  ({} {} from origin, {} to target)\n",
+                self.icon(graph, point.address),
+                self.display_name(graph, point.address),
+                point.from_origin,
+                hops,
+                point.to_target
+            ));
+        }
+        if points.is_empty() {
+            output.push_str("No address links the origin to the target within the trace depth.\n");
+        }
+        self.write(&output)
+    }
+
+    fn paths_summary(
+        &mut self,
+        graph: &TransferGraph,
+        summary: &PathsSummary<'_>,
+    ) -> io::Result<()> {
+        let heading = if summary.ranked {
+            format!("top {} by bottleneck", summary.paths.len())
+        } else {
+            let limit_note = match summary.limit {
+                None => "",
+                Some(PathLimit::Depth) => ", longer paths skipped",
+                Some(PathLimit::Paths) => ", stopped at path limit",
+                Some(PathLimit::Steps) => ", stopped at search budget",
+            };
+            format!("{} paths found{}", summary.paths.len(), limit_note)
+        };
+        self.section(&format!("PATHS SUMMARY ({}):", heading))?;
+
+        let mut output = String::new();
+        for (idx, path) in summary.paths.iter().enumerate() {
+            output.push_str(&format!("PATH #{}: ", idx + 1));
+            for (i, addr) in path.addresses().iter().enumerate() {
+                if i > 0 {
+                    output.push_str(" → ");
+                }
+                output.push_str(&graph.truncate_address(addr, 8));
+            }
+            if summary.ranked {
+                if let Some(narrowest) = path.hops.iter().min_by_key(|t| t.amount) {
+                    output.push_str(&format!(
+                        " [bottleneck {} {}]",
+                        graph.format_amount(&narrowest.amount),
+                        narrowest.token_symbol
+                    ));
+                }
+            }
+            output.push('\n');
+        }
+        self.write(&output)
+    }
+
+    fn cycles_summary(
+        &mut self,
+        graph: &TransferGraph,
+        summary: &CyclesSummary<'_>,
+    ) -> io::Result<()> {
+        let cycles = &summary.cycles;
+        // Still say so when the budget ran out before any cycle turned up
+        if cycles.is_empty() && summary.limit.is_none() {
+            return Ok(());
+        }
+        let limit_note = match summary.limit {
+            None => "",
+            Some(CycleLimit::Cycles) => ", stopped at cycle limit",
+            Some(CycleLimit::Steps) => ", stopped at search budget",
+        };
+        self.section(&format!(
+            "CIRCULAR FLOWS DETECTED ({} cycles{}):",
+            cycles.len(),
+            limit_note
+        ))?;
+
+        let mut output = String::new();
+        for (idx, cycle) in cycles.iter().enumerate() {
+            // Back to the first member to close the loop
+            let members: Vec<String> = cycle
+                .addresses
+                .iter()
+                .chain(cycle.addresses.first())
+                .map(|addr| graph.truncate_address(addr, 8))
+                .collect();
+            output.push_str(&format!("CYCLE #{}: {}", idx + 1, members.join(" → ")));
+            let volume = cycle
+                .volume()
+                .map_or_else(|| "overflow".to_string(), |v| graph.format_amount(&v));
+            let symbol = cycle
+                .hops
+                .first()
+                .and_then(|hop| hop.first())
+                .map_or("", |t| t.token_symbol.as_str());
+            output.push_str(&format!(
+                " [volume {} {}, wash score {:.2}]\n",
+                volume,
+                symbol,
+                cycle.wash_score(graph.render_config.cycles.time_window)
+            ));
+        }
+        self.write(&output)
+    }
+
+    fn stats(&mut self, graph: &TransferGraph, stats: &StatsSummary<'_>) -> io::Result<()> {
+        let exact = |amount: Option<TokenAmount>| {
+            amount.map_or_else(|| "overflow".to_string(), |a| graph.format_exact(&a))
+        };
+        let border = "─".repeat(self.frame_width() - 3);
+        let mut output = format!("\n┌{}┐\n", border);
+        output.push_str(&self.box_row("Total Nodes:", &stats.nodes.to_string()));
+        output.push_str(&self.box_row("Total Transfers:", &stats.transfers.to_string()));
+
+        if let Some(origin) = &stats.origin {
+            output.push_str(&self.box_row("Origin Sent:", &exact(origin.outflow)));
+        }
+        if let Some(target) = &stats.target {
+            output.push_str(&self.box_row("Target Received:", &exact(target.inflow)));
+        }
+        if graph.origin.is_some() {
+            let held = exact(stats.held_by_intermediaries);
+            output.push_str(&self.box_row("Held by Intermediaries:", &held));
+        }
+
+        let violations = &stats.violations;
+        if !violations.is_empty() {
+            let heading = format!("Conservation Violations: {}", violations.len());
+            output.push_str(&self.box_row(&heading, "sent beyond received"));
+            // The largest deficits are the likeliest gaps in the data
+            for balance in violations.iter().take(5) {
+                let name = format!("  {}", self.display_name(graph, balance.address));
+                let deficit = balance
+                    .deficit()
+                    .map_or_else(|| "overflow".to_string(), |d| graph.format_amount(&d));
+                output.push_str(&self.box_row(&name, &deficit));
+            }
+            if violations.len() > 5 {
+                let more = format!("  ... and {} more", violations.len() - 5);
+                output.push_str(&self.box_row(&more, ""));
+            }
+        }
+
+        if !stats.hubs.is_empty() {
+            let heading = format!("Top {} Hubs:", stats.hubs.len());
+            output.push_str(&self.box_row(&heading, "betweenness / PR"));
+            for (idx, hub) in stats.hubs.iter().enumerate() {
+                let rank = format!("  {}. {}", idx + 1, self.display_name(graph, hub.address));
+                let scores = format!("{:.1} / {:.3}", hub.betweenness, hub.pagerank);
+                output.push_str(&self.box_row(&rank, &scores));
+            }
+        }
+        output.push_str(&format!("└{}┘\n", border));
+        self.write(&output)
+    }
+
+    fn finish(&mut self, _graph: &TransferGraph) -> io::Result<()> {
+        if let Some(limit) = self.out.limit_reached() {
+            let notice = match limit {
+                OutputLimit::Lines => {
+                    format!("... output truncated after {} lines", self.out.written().0)
+                }
+                OutputLimit::Bytes => {
+                    format!("... output truncated after {} bytes", self.out.written().1)
+                }
+            };
+            self.out.write_notice(&notice)?;
+        }
+        self.out.flush()
+    }
+
+    fn is_truncated(&self) -> bool {
+        self.out.limit_reached().is_some()
+    }
+}
+
+impl TransferGraph {
+    /// Render the graph as ASCII art with `config` instead of
+    /// `RenderConfig::ascii`
+    pub fn render_ascii_with(&self, config: &AsciiConfig) -> String {
+        render_to_string(|out| self.write_ascii_with(config, out))
+    }
+
+    /// Stream the ASCII art of `render_ascii` to `out`, within
+    /// `AsciiConfig::output_limits`
+    ///
+    /// Lines are written as they are drawn, so wrap unbuffered writers such
+    /// as files in a `BufWriter`.
+    pub fn write_ascii<W: Write>(&self, out: W) -> io::Result<()> {
+        self.write_ascii_with(&self.render_config.ascii, out)
+    }
+
+    /// `write_ascii` with `config` instead of `RenderConfig::ascii`
+    pub fn write_ascii_with<W: Write>(&self, config: &AsciiConfig, out: W) -> io::Result<()> {
+        self.render_with(&mut AsciiRenderer::new(config.clone(), out))
+    }
+}
+
+/// Indentation of tree lines at `depth`, marked with the depth past
+/// [`MAX_INDENT`]
+fn indent(depth: usize) -> String {
+    let indent = "      ".repeat(depth.min(MAX_INDENT));
+    if depth > MAX_INDENT {
+        format!("{}… (depth {}) ", indent, depth)
+    } else {
+        indent
+    }
+}
+
+fn center_text(text: &str, width: usize) -> String {
+    let text_len = display_width(text);
+    if text_len >= width {
+        return text.to_string();
+    }
+    let padding = (width - text_len) / 2;
+    let extra = (width - text_len) % 2;
+    format!(
+        "{}{}{}",
+        " ".repeat(padding),
+        text,
+        " ".repeat(padding + extra)
+    )
+}
+
+/// The smallest amount among the largest tenth of the transfers
+/// (at least one); zero when there are none
+fn large_amount(graph: &TransferGraph) -> TokenAmount {
+    let mut amounts: Vec<TokenAmount> = graph
+        .nodes
+        .values()
+        .flat_map(|node| node.outgoing.iter().map(|t| t.amount))
+        .collect();
+    amounts.sort_unstable_by(|a, b| b.cmp(a));
+    let top = amounts.len().div_ceil(10);
+    top.checked_sub(1).map_or(TokenAmount::ZERO, |i| amounts[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "T".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    /// O pays 100 addresses, which all pay T
+    fn fan() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("O".to_string());
+        graph.target = Some("T".to_string());
+        for i in 0..100 {
+            let hop = format!("H{:03}", i);
+            graph.add_transfer(transfer("O", &hop, 2));
+            graph.add_transfer(transfer(&hop, "T", 1));
+        }
+        graph
+    }
+
+    /// Counts writes, failing the test once more than `max` bytes arrive
+    struct Capped {
+        written: usize,
+        max: usize,
+    }
+
+    impl Write for Capped {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written += buf.len();
+            assert!(self.written <= self.max, "{} bytes written", self.written);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_ascii() {
+        let graph = fan();
+        let mut output = Vec::new();
+        graph.write_ascii(&mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), graph.render_ascii());
+    }
+
+    #[test]
+    fn test_truncation_notice() {
+        let mut graph = fan();
+        graph.render_config.ascii.output_limits.max_lines = Some(20);
+        let output = graph.render_ascii();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 21);
+        assert_eq!(lines[20], "... output truncated after 20 lines");
+        assert!(!output.contains("PATHS SUMMARY"));
+
+        graph.render_config.ascii.output_limits = OutputLimits {
+            max_bytes: Some(2000),
+            ..OutputLimits::unbounded()
+        };
+        let output = graph.render_ascii();
+        let (kept, notice) = output.rsplit_once("... output truncated after ").unwrap();
+        assert!(kept.len() <= 2000 && kept.ends_with('\n'));
+        assert_eq!(notice, format!("{} bytes\n", kept.len()));
+
+        // Past the limit nothing more reaches the writer
+        graph.render_config.ascii.output_limits.max_bytes = Some(500);
+        let capped = Capped {
+            written: 0,
+            max: 600,
+        };
+        graph.write_ascii(capped).unwrap();
+    }
+
+    #[test]
+    fn test_indentation_stops_at_max_indent() {
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("C0".to_string());
+        for i in 0..40 {
+            graph.add_transfer(transfer(&format!("C{}", i), &format!("C{}", i + 1), 1));
+        }
+        let output = graph.render_ascii();
+        let indent = " ".repeat(6 * MAX_INDENT);
+        assert!(output.contains(&format!("\n{}○ C32\n", indent)));
+        assert!(output.contains(&format!("\n{}… (depth 40) ○ C40\n", indent)));
+        assert!(output.contains(&format!("\n{}… (depth 39)       └──────→ ", indent)));
+    }
+}
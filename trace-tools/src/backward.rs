@@ -0,0 +1,236 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::TransferGraph;
+
+/// Which way the ASCII tree walks the graph
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TraceDirection {
+    /// Follow outgoing transfers from the origin
+    #[default]
+    Forward,
+    /// Follow incoming transfers back from the target and draw the tree
+    /// upside down, with the target at the bottom
+    Backward,
+    /// Both trees, followed by the addresses where they meet
+    Both,
+}
+
+/// An address funds from the origin reach and that funds the target, see
+/// [`TransferGraph::meeting_points`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeetingPoint<'a> {
+    pub address: &'a str,
+    /// Fewest hops from the origin to here
+    pub from_origin: usize,
+    /// Fewest hops from here to the target
+    pub to_target: usize,
+}
+
+impl TransferGraph {
+    /// Every address `addr` sent funds to, directly or through others, with
+    /// the fewest hops it takes; `addr` itself is at 0
+    ///
+    /// Only addresses within `max_depth` hops are listed. Sorted by hops,
+    /// then address.
+    pub fn downstream(&self, addr: &str, max_depth: Option<usize>) -> Vec<(&str, usize)> {
+        sorted_by_hops(self.hop_distances(addr, max_depth, false))
+    }
+
+    /// Every address that funded `addr`, directly or through others, with
+    /// the fewest hops it takes; `addr` itself is at 0
+    ///
+    /// Only addresses within `max_depth` hops are listed. Sorted by hops,
+    /// then address.
+    pub fn upstream(&self, addr: &str, max_depth: Option<usize>) -> Vec<(&str, usize)> {
+        sorted_by_hops(self.hop_distances(addr, max_depth, true))
+    }
+
+    /// Addresses where a forward search from `origin` and a backward search
+    /// from `target` meet, each side walking at most `max_depth` hops
+    ///
+    /// These are the intermediaries funds could have passed through on the
+    /// way from the origin to the target. Sorted by the length of the
+    /// shortest route through them, then address; `origin` and `target`
+    /// themselves are left out.
+    pub fn meeting_points(
+        &self,
+        origin: &str,
+        target: &str,
+        max_depth: Option<usize>,
+    ) -> Vec<MeetingPoint<'_>> {
+        let forward = self.hop_distances(origin, max_depth, false);
+        let backward = self.hop_distances(target, max_depth, true);
+        let mut points: Vec<MeetingPoint> = forward
+            .iter()
+            .filter(|(address, _)| **address != origin && **address != target)
+            .filter_map(|(address, &from_origin)| {
+                backward.get(address).map(|&to_target| MeetingPoint {
+                    address,
+                    from_origin,
+                    to_target,
+                })
+            })
+            .collect();
+        points.sort_by_key(|p| (p.from_origin + p.to_target, p.address));
+        points
+    }
+
+    /// Breadth-first hop counts from `addr` along outgoing transfers, or
+    /// incoming ones when `backward`
+    fn hop_distances(
+        &self,
+        addr: &str,
+        max_depth: Option<usize>,
+        backward: bool,
+    ) -> HashMap<&str, usize> {
+        let mut distances: HashMap<&str, usize> = HashMap::new();
+        let Some((start, _)) = self.nodes.get_key_value(addr) else {
+            return distances;
+        };
+        distances.insert(start, 0);
+        let mut queue = VecDeque::from([start.as_str()]);
+        while let Some(current) = queue.pop_front() {
+            let hops = distances[current];
+            if max_depth.is_some_and(|max| hops >= max) {
+                continue;
+            }
+            let node = &self.nodes[current];
+            let neighbours = if backward {
+                node.incoming
+                    .iter()
+                    .map(|t| t.from.as_str())
+                    .collect::<Vec<_>>()
+            } else {
+                node.outgoing.iter().map(|t| t.to.as_str()).collect()
+            };
+            for next in neighbours {
+                if !distances.contains_key(next) && self.nodes.contains_key(next) {
+                    distances.insert(next, hops + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+}
+
+fn sorted_by_hops(distances: HashMap<&str, usize>) -> Vec<(&str, usize)> {
+    let mut sorted: Vec<(&str, usize)> = distances.into_iter().collect();
+    sorted.sort_by_key(|&(address, hops)| (hops, address));
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TokenAmount, Transfer};
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    /// O -> A -> T and O -> B -> C -> T, with F funding C from outside and
+    /// A also paying D
+    fn graph() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("O".to_string());
+        graph.target = Some("T".to_string());
+        graph.add_transfer(transfer("O", "A", 10));
+        graph.add_transfer(transfer("O", "B", 20));
+        graph.add_transfer(transfer("A", "T", 10));
+        graph.add_transfer(transfer("A", "D", 1));
+        graph.add_transfer(transfer("B", "C", 20));
+        graph.add_transfer(transfer("F", "C", 5));
+        graph.add_transfer(transfer("C", "T", 25));
+        graph
+    }
+
+    #[test]
+    fn test_upstream_and_downstream() {
+        let graph = graph();
+        assert_eq!(
+            graph.upstream("T", None),
+            [("T", 0), ("A", 1), ("C", 1), ("B", 2), ("F", 2), ("O", 2)]
+        );
+        assert_eq!(graph.upstream("T", Some(1)), [("T", 0), ("A", 1), ("C", 1)]);
+        assert_eq!(graph.downstream("A", None), [("A", 0), ("D", 1), ("T", 1)]);
+        assert!(graph.upstream("nobody", None).is_empty());
+    }
+
+    #[test]
+    fn test_meeting_points() {
+        let graph = graph();
+        let points = graph.meeting_points("O", "T", None);
+        let summary: Vec<(&str, usize, usize)> = points
+            .iter()
+            .map(|p| (p.address, p.from_origin, p.to_target))
+            .collect();
+        assert_eq!(summary, [("A", 1, 1), ("B", 1, 2), ("C", 2, 1)]);
+
+        let near: Vec<&str> = graph
+            .meeting_points("O", "T", Some(1))
+            .iter()
+            .map(|p| p.address)
+            .collect();
+        assert_eq!(near, ["A"]);
+    }
+
+    #[test]
+    fn test_render_backward() {
+        let mut graph = graph();
+        graph.render_config.show_header = false;
+        graph.render_config.show_paths_summary = false;
+        graph.render_config.show_stats_summary = false;
+        graph.render_config.tree.direction = TraceDirection::Backward;
+        let output = graph.render_ascii();
+        assert!(!output.contains("\n🏦 ORIGIN O\n"));
+        let tree = output.split_once("═\n\n").unwrap().1;
+        let expected = "            🏦 ORIGIN O #1
+            ┌──────→ [10.00 SVMAI] ────────→ A
+      ○ A
+      ┌──────→ [10.00 SVMAI] ────────→ T
+
+                  ┌──────→ [20.00 SVMAI] ────────→ B  ↺ see #1 above
+            ○ B
+            ┌──────→ [20.00 SVMAI] ────────→ C
+
+            ○ F
+            ├──────→ [5.00 SVMAI] ────────→ C
+      ○ C
+      ├──────→ [25.00 SVMAI] ────────→ T
+🎯 TARGET T
+";
+        assert!(tree.starts_with(expected), "{}", tree);
+
+        graph.render_config.tree.depth = Some(1);
+        let output = graph.render_ascii();
+        assert!(output.contains("      ○ C [+2 beyond depth]\n"));
+        assert!(!output.contains("○ F"));
+    }
+
+    #[test]
+    fn test_render_meet_in_the_middle() {
+        let mut graph = graph();
+        graph.render_config.tree.direction = TraceDirection::Both;
+        let output = graph.render_ascii();
+        assert!(output.contains("🏦 ORIGIN O\n"));
+        assert!(output.contains("FUNDING SOURCES OF TARGET:"));
+        assert!(output.contains("\n🎯 TARGET T\n"));
+        assert!(output.contains("MEETING POINTS (3 addresses):"));
+        assert!(output.contains("○ A  (1 hop from origin, 1 to target)"));
+        assert!(output.contains("○ C  (2 hops from origin, 1 to target)"));
+    }
+}
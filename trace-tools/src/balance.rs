@@ -0,0 +1,146 @@
+use std::cmp::Reverse;
+
+use crate::{total_amount, TokenAmount, TransferGraph};
+
+/// Funds moving through one address, see [`TransferGraph::balances`]
+///
+/// Amounts of every token are added up together, like the totals in the
+/// stats summary and exports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeBalance<'a> {
+    pub address: &'a str,
+    /// Total received, or `None` on overflow
+    pub inflow: Option<TokenAmount>,
+    /// Total sent, or `None` on overflow
+    pub outflow: Option<TokenAmount>,
+}
+
+impl NodeBalance<'_> {
+    /// Received minus sent; negative when the address sent more than the
+    /// graph shows it receiving
+    pub fn net_flow(&self) -> Option<TokenAmount> {
+        self.inflow?.checked_sub(&self.outflow?)
+    }
+
+    /// Funds received and not sent on again, i.e. what the address still
+    /// holds as far as the graph can tell
+    pub fn residual(&self) -> Option<TokenAmount> {
+        self.net_flow().map(|net| net.max(TokenAmount::ZERO))
+    }
+
+    /// Amount sent beyond what was received
+    pub fn deficit(&self) -> Option<TokenAmount> {
+        self.outflow?
+            .checked_sub(&self.inflow?)
+            .map(|excess| excess.max(TokenAmount::ZERO))
+    }
+
+    /// Whether the address sent no more than it received
+    pub fn is_conserved(&self) -> bool {
+        self.deficit().is_some_and(|deficit| deficit.is_zero())
+    }
+}
+
+impl TransferGraph {
+    /// Inflow and outflow of every address, sorted by address
+    pub fn balances(&self) -> Vec<NodeBalance<'_>> {
+        self.sorted_nodes()
+            .into_iter()
+            .map(|node| NodeBalance {
+                address: &node.address,
+                inflow: total_amount(&node.incoming),
+                outflow: total_amount(&node.outgoing),
+            })
+            .collect()
+    }
+
+    /// Addresses that sent more than they received within the graph,
+    /// largest deficit first
+    ///
+    /// Outside the origin, which is expected to fund the trace, every such
+    /// address points at missing transfers or a source the trace does not
+    /// cover. Addresses whose totals overflow are not checked.
+    pub fn conservation_violations(&self) -> Vec<NodeBalance<'_>> {
+        let mut violations: Vec<NodeBalance> = self
+            .balances()
+            .into_iter()
+            .filter(|b| Some(b.address) != self.origin.as_deref())
+            .filter(|b| b.deficit().is_some_and(|deficit| !deficit.is_zero()))
+            .collect();
+        violations.sort_by_key(|b| Reverse(b.deficit()));
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Transfer;
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    /// O funds A, which keeps some; B forwards more than A sent it
+    fn graph() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("O".to_string());
+        graph.add_transfer(transfer("O", "A", 100));
+        graph.add_transfer(transfer("A", "B", 30));
+        graph.add_transfer(transfer("A", "C", 20));
+        graph.add_transfer(transfer("B", "C", 45));
+        graph.add_transfer(transfer("X", "C", 5));
+        graph
+    }
+
+    #[test]
+    fn test_balances() {
+        let graph = graph();
+        let balances = graph.balances();
+        let addresses: Vec<&str> = balances.iter().map(|b| b.address).collect();
+        assert_eq!(addresses, ["A", "B", "C", "O", "X"]);
+
+        let a = &balances[0];
+        assert_eq!(a.inflow, Some(TokenAmount::from(100)));
+        assert_eq!(a.outflow, Some(TokenAmount::from(50)));
+        assert_eq!(a.net_flow(), Some(TokenAmount::from(50)));
+        assert_eq!(a.residual(), Some(TokenAmount::from(50)));
+        assert!(a.is_conserved());
+
+        let b = &balances[1];
+        assert_eq!(b.net_flow(), Some(TokenAmount::new(-15, 0)));
+        assert_eq!(b.residual(), Some(TokenAmount::ZERO));
+        assert_eq!(b.deficit(), Some(TokenAmount::from(15)));
+        assert!(!b.is_conserved());
+
+        assert_eq!(balances[2].residual(), Some(TokenAmount::from(70)));
+    }
+
+    #[test]
+    fn test_conservation_violations() {
+        let graph = graph();
+        let flagged: Vec<&str> = graph
+            .conservation_violations()
+            .iter()
+            .map(|b| b.address)
+            .collect();
+        assert_eq!(flagged, ["B", "X"]);
+
+        let output = graph.render_ascii();
+        assert!(output.contains("│ Conservation Violations: 2"));
+        assert!(output.contains(&format!("│ {:<47}{:>24} │", "  B", "15.00")));
+        assert!(output.contains(&format!("│ Held by Intermediaries: {:>47} │", "120.00")));
+
+        let mut graph = graph;
+        graph.origin = None;
+        assert_eq!(graph.conservation_violations()[0].address, "O");
+    }
+}
@@ -0,0 +1,379 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+use crate::TransferGraph;
+
+/// How transfers count towards centrality scores
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Weighting {
+    /// Every transfer counts once
+    Count,
+    /// Transfers count by amount
+    #[default]
+    Amount,
+}
+
+/// Hubs listed in the stats summary, see
+/// [`RenderConfig::hubs`](crate::RenderConfig::hubs)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct HubConfig {
+    /// Intermediaries listed, ranked by betweenness; 0 (the default) hides
+    /// the list and skips computing it
+    pub count: usize,
+    /// How transfers count when ranking them
+    pub weighting: Weighting,
+}
+
+/// Centrality scores of one address, see [`TransferGraph::centrality`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeCentrality<'a> {
+    pub address: &'a str,
+    /// Transfers received, or the amount received
+    pub in_degree: f64,
+    /// Transfers sent, or the amount sent
+    pub out_degree: f64,
+    /// Share of time a random walk following transfers spends here; the
+    /// scores of all addresses add up to 1
+    pub pagerank: f64,
+    /// Number of shortest routes between other addresses that pass through
+    /// here (fractions when several routes are equally short)
+    pub betweenness: f64,
+}
+
+/// Probability that the PageRank walk follows a transfer rather than
+/// jumping to a random address
+const DAMPING: f64 = 0.85;
+
+impl TransferGraph {
+    /// Degree, PageRank and betweenness of every address, sorted by address
+    ///
+    /// With [`Weighting::Amount`] degrees are amounts, the PageRank walk
+    /// follows each transfer in proportion to its amount and betweenness
+    /// measures route length as the sum of `1 / amount` over the hops, so
+    /// routes carrying more are shorter. With [`Weighting::Count`] every
+    /// transfer weighs the same and routes are measured in hops. Parallel
+    /// transfers between a pair are combined into one edge.
+    pub fn centrality(&self, weighting: Weighting) -> Vec<NodeCentrality<'_>> {
+        let edges = Edges::new(self, weighting);
+        let pagerank = edges.pagerank();
+        let betweenness = edges.betweenness();
+
+        edges
+            .addresses
+            .iter()
+            .enumerate()
+            .map(|(i, address)| NodeCentrality {
+                address,
+                in_degree: edges.incoming[i].iter().map(|&(_, w)| w).sum(),
+                out_degree: edges.outgoing[i].iter().map(|&(_, w)| w).sum(),
+                pagerank: pagerank[i],
+                betweenness: betweenness[i],
+            })
+            .collect()
+    }
+
+    /// The `n` addresses with the highest betweenness, i.e. the
+    /// intermediaries most flows pass through, ties broken by PageRank.
+    /// Addresses no route passes through are never hubs.
+    ///
+    /// Betweenness runs a shortest-route search from every address, so
+    /// this is slow on large graphs; nothing is computed when `n` is 0.
+    pub fn hubs(&self, n: usize, weighting: Weighting) -> Vec<NodeCentrality<'_>> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let mut hubs: Vec<NodeCentrality> = self
+            .centrality(weighting)
+            .into_iter()
+            .filter(|c| c.betweenness > 0.0)
+            .collect();
+        hubs.sort_by(|a, b| {
+            b.betweenness
+                .total_cmp(&a.betweenness)
+                .then(b.pagerank.total_cmp(&a.pagerank))
+        });
+        hubs.truncate(n);
+        hubs
+    }
+}
+
+/// Combined edges between distinct addresses, indexed by sorted address
+struct Edges<'a> {
+    addresses: Vec<&'a str>,
+    /// Receivers of each address with the edge weight
+    outgoing: Vec<Vec<(usize, f64)>>,
+    /// Senders of each address with the edge weight
+    incoming: Vec<Vec<(usize, f64)>>,
+    weighting: Weighting,
+}
+
+impl<'a> Edges<'a> {
+    fn new(graph: &'a TransferGraph, weighting: Weighting) -> Self {
+        let nodes = graph.sorted_nodes();
+        let addresses: Vec<&str> = nodes.iter().map(|n| n.address.as_str()).collect();
+        let index: HashMap<&str, usize> =
+            addresses.iter().enumerate().map(|(i, a)| (*a, i)).collect();
+
+        let mut outgoing: Vec<Vec<(usize, f64)>> = vec![Vec::new(); addresses.len()];
+        let mut incoming: Vec<Vec<(usize, f64)>> = vec![Vec::new(); addresses.len()];
+        for (from, node) in nodes.iter().enumerate() {
+            let mut weights: BTreeMap<usize, f64> = BTreeMap::new();
+            for transfer in &node.outgoing {
+                let Some(&to) = index.get(transfer.to.as_str()) else {
+                    continue;
+                };
+                let weight = match weighting {
+                    Weighting::Count => 1.0,
+                    Weighting::Amount => transfer.amount.to_f64().max(0.0),
+                };
+                *weights.entry(to).or_insert(0.0) += weight;
+            }
+            for (&to, &weight) in &weights {
+                incoming[to].push((from, weight));
+            }
+            outgoing[from] = weights.into_iter().collect();
+        }
+
+        Edges {
+            addresses,
+            outgoing,
+            incoming,
+            weighting,
+        }
+    }
+
+    /// Power iteration; addresses that send nothing spread their score
+    /// evenly over all addresses
+    fn pagerank(&self) -> Vec<f64> {
+        let count = self.addresses.len();
+        if count == 0 {
+            return Vec::new();
+        }
+        let totals: Vec<f64> = self
+            .outgoing
+            .iter()
+            .map(|edges| edges.iter().map(|&(_, w)| w).sum())
+            .collect();
+        let mut rank = vec![1.0 / count as f64; count];
+
+        for _ in 0..100 {
+            let dangling: f64 = (0..count)
+                .filter(|&i| totals[i] <= 0.0)
+                .map(|i| rank[i])
+                .sum();
+            let base = (1.0 - DAMPING + DAMPING * dangling) / count as f64;
+            let mut next = vec![base; count];
+            for (from, edges) in self.outgoing.iter().enumerate() {
+                if totals[from] <= 0.0 {
+                    continue;
+                }
+                for &(to, weight) in edges {
+                    next[to] += DAMPING * rank[from] * weight / totals[from];
+                }
+            }
+            let change: f64 = next.iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+            rank = next;
+            if change < 1e-12 {
+                break;
+            }
+        }
+        rank
+    }
+
+    /// Length of an edge with the given weight; zero-amount edges are unusable
+    fn length(&self, weight: f64) -> Option<f64> {
+        match self.weighting {
+            Weighting::Count => Some(1.0),
+            Weighting::Amount if weight > 0.0 => Some(1.0 / weight),
+            Weighting::Amount => None,
+        }
+    }
+
+    /// Brandes' algorithm with Dijkstra searches
+    fn betweenness(&self) -> Vec<f64> {
+        let count = self.addresses.len();
+        let mut betweenness = vec![0.0; count];
+
+        for source in 0..count {
+            let mut distance: Vec<Option<f64>> = vec![None; count];
+            let mut routes = vec![0.0; count];
+            let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); count];
+            let mut settled: Vec<usize> = Vec::new();
+            let mut done = vec![false; count];
+            let mut queue = BinaryHeap::from([Pending(0.0, source)]);
+            distance[source] = Some(0.0);
+            routes[source] = 1.0;
+
+            while let Some(Pending(dist, node)) = queue.pop() {
+                if done[node] {
+                    continue;
+                }
+                done[node] = true;
+                settled.push(node);
+                for &(next, weight) in &self.outgoing[node] {
+                    let Some(length) = self.length(weight) else {
+                        continue;
+                    };
+                    if next == node {
+                        continue;
+                    }
+                    let candidate = dist + length;
+                    match distance[next].map(|d| candidate.total_cmp(&d)) {
+                        None | Some(Ordering::Less) => {
+                            distance[next] = Some(candidate);
+                            routes[next] = routes[node];
+                            predecessors[next] = vec![node];
+                            queue.push(Pending(candidate, next));
+                        }
+                        Some(Ordering::Equal) => {
+                            routes[next] += routes[node];
+                            predecessors[next].push(node);
+                        }
+                        Some(Ordering::Greater) => {}
+                    }
+                }
+            }
+
+            // Accumulate dependencies from the farthest address back
+            let mut dependency = vec![0.0; count];
+            for &node in settled.iter().rev() {
+                for &previous in &predecessors[node] {
+                    dependency[previous] +=
+                        routes[previous] / routes[node] * (1.0 + dependency[node]);
+                }
+                if node != source {
+                    betweenness[node] += dependency[node];
+                }
+            }
+        }
+        betweenness
+    }
+}
+
+/// Dijkstra queue entry, ordered so the nearest address pops first
+struct Pending(f64, usize);
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0).then(other.1.cmp(&self.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TokenAmount, Transfer};
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    /// M fans out to hubs H1 and H2; H1 forwards to three wallets, H2 to one
+    fn graph() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("M", "H1", 10));
+        graph.add_transfer(transfer("M", "H2", 90));
+        for wallet in ["W1", "W2", "W3"] {
+            graph.add_transfer(transfer("H1", wallet, 3));
+        }
+        graph.add_transfer(transfer("H2", "W4", 40));
+        graph.add_transfer(transfer("H2", "W4", 50));
+        graph
+    }
+
+    fn scores<'a>(
+        graph: &'a TransferGraph,
+        weighting: Weighting,
+    ) -> HashMap<&'a str, NodeCentrality<'a>> {
+        graph
+            .centrality(weighting)
+            .into_iter()
+            .map(|c| (c.address, c))
+            .collect()
+    }
+
+    #[test]
+    fn test_degrees_and_betweenness() {
+        let graph = graph();
+        let count = scores(&graph, Weighting::Count);
+        assert_eq!(count["H2"].in_degree, 1.0);
+        assert_eq!(count["H2"].out_degree, 2.0);
+        assert_eq!(count["H1"].betweenness, 3.0);
+        assert_eq!(count["H2"].betweenness, 1.0);
+        assert_eq!(count["M"].betweenness, 0.0);
+
+        let amount = scores(&graph, Weighting::Amount);
+        assert_eq!(amount["H2"].in_degree, 90.0);
+        assert_eq!(amount["H2"].out_degree, 90.0);
+        assert_eq!(amount["H1"].betweenness, 3.0);
+
+        let hubs: Vec<&str> = graph
+            .hubs(5, Weighting::Count)
+            .iter()
+            .map(|h| h.address)
+            .collect();
+        assert_eq!(hubs, ["H1", "H2"]);
+        assert_eq!(graph.hubs(1, Weighting::Count).len(), 1);
+    }
+
+    #[test]
+    fn test_weighted_betweenness_prefers_large_flows() {
+        // A reaches D directly with a dust transfer or through B with real
+        // volume
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "D", 1));
+        graph.add_transfer(transfer("A", "B", 100));
+        graph.add_transfer(transfer("B", "D", 100));
+
+        assert_eq!(scores(&graph, Weighting::Count)["B"].betweenness, 0.0);
+        assert_eq!(scores(&graph, Weighting::Amount)["B"].betweenness, 1.0);
+    }
+
+    #[test]
+    fn test_pagerank() {
+        let graph = graph();
+        let count = scores(&graph, Weighting::Count);
+        let amount = scores(&graph, Weighting::Amount);
+
+        let total: f64 = count.values().map(|c| c.pagerank).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        // By count M splits its rank evenly; by amount H2 gets most of it
+        assert!((count["H1"].pagerank - count["H2"].pagerank).abs() < 1e-12);
+        assert!(amount["H2"].pagerank > amount["H1"].pagerank);
+        assert!(amount["W4"].pagerank > amount["W1"].pagerank);
+
+        assert!(TransferGraph::new().centrality(Weighting::Count).is_empty());
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{GraphNode, TokenAmount, Transfer, TransferGraph};
+
+/// How addresses are grouped, see [`TransferGraph::clusters`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ClusterMethod {
+    /// Addresses connected by any chain of transfers, in either direction
+    Components,
+    /// Communities that maximise modularity (Louvain), each split into
+    /// connected parts as in Leiden
+    #[default]
+    Louvain,
+}
+
+/// A group of addresses treated as one actor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    pub id: usize,
+    /// Sorted member addresses
+    pub members: Vec<String>,
+    /// Amount received from addresses outside the cluster
+    pub inflow: TokenAmount,
+    /// Amount sent to addresses outside the cluster
+    pub outflow: TokenAmount,
+    /// Amount moved between members
+    pub internal: TokenAmount,
+}
+
+/// Result of [`TransferGraph::clusters`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clustering {
+    /// Clusters ordered by their first member; `id` is the position
+    pub clusters: Vec<Cluster>,
+    ids: HashMap<String, usize>,
+}
+
+impl Clustering {
+    /// Id of the cluster `address` belongs to
+    pub fn cluster_of(&self, address: &str) -> Option<usize> {
+        self.ids.get(address).copied()
+    }
+
+    /// Address of a cluster's super-node in
+    /// [`collapse_clusters`](TransferGraph::collapse_clusters)
+    pub fn node_address(id: usize) -> String {
+        format!("cluster-{}", id)
+    }
+}
+
+impl TransferGraph {
+    /// Group addresses into clusters over the undirected, amount-weighted
+    /// transfer graph
+    ///
+    /// Every address belongs to exactly one cluster. Louvain is
+    /// deterministic: addresses are visited in sorted order. Flows are
+    /// summed exactly in base units, saturating on overflow.
+    pub fn clusters(&self, method: ClusterMethod) -> Clustering {
+        let graph = Undirected::new(self);
+        let community = match method {
+            ClusterMethod::Components => graph.components(&vec![0; graph.len()]),
+            ClusterMethod::Louvain => graph.components(&graph.louvain()),
+        };
+
+        // Number clusters by their smallest (first sorted) member
+        let mut ids: Vec<Option<usize>> = vec![None; graph.len()];
+        let mut members: Vec<Vec<String>> = Vec::new();
+        let mut cluster_of = vec![0; graph.len()];
+        for (node, &c) in community.iter().enumerate() {
+            let id = *ids[c].get_or_insert_with(|| {
+                members.push(Vec::new());
+                members.len() - 1
+            });
+            members[id].push(graph.addresses[node].to_string());
+            cluster_of[node] = id;
+        }
+
+        let decimals = self
+            .nodes
+            .values()
+            .flat_map(|n| n.outgoing.iter())
+            .map(|t| t.amount.decimals)
+            .max()
+            .unwrap_or(0);
+        let mut flows = vec![(0i128, 0i128, 0i128); members.len()];
+        for (from, node) in graph.nodes.iter().enumerate() {
+            for transfer in &node.outgoing {
+                let to = graph.index[transfer.to.as_str()];
+                let amount = transfer.amount.rescale(decimals).map_or(0, |a| a.raw);
+                let (a, b) = (cluster_of[from], cluster_of[to]);
+                if a == b {
+                    flows[a].2 = flows[a].2.saturating_add(amount);
+                } else {
+                    flows[a].1 = flows[a].1.saturating_add(amount);
+                    flows[b].0 = flows[b].0.saturating_add(amount);
+                }
+            }
+        }
+
+        let clusters = members
+            .into_iter()
+            .zip(flows)
+            .enumerate()
+            .map(|(id, (members, (inflow, outflow, internal)))| Cluster {
+                id,
+                members,
+                inflow: TokenAmount::new(inflow, decimals),
+                outflow: TokenAmount::new(outflow, decimals),
+                internal: TokenAmount::new(internal, decimals),
+            })
+            .collect();
+        let ids = graph
+            .addresses
+            .iter()
+            .zip(cluster_of)
+            .map(|(address, id)| (address.to_string(), id))
+            .collect();
+        Clustering { clusters, ids }
+    }
+
+    /// A copy of the graph with each cluster replaced by one super-node
+    ///
+    /// Super-nodes are addressed by [`Clustering::node_address`] and
+    /// labelled with their size. Transfers between members disappear;
+    /// every other transfer is kept, redirected between super-nodes, so
+    /// any renderer can draw the collapsed graph. The origin and target
+    /// become the super-nodes that contain them.
+    pub fn collapse_clusters(&self, clustering: &Clustering) -> TransferGraph {
+        let mut graph = TransferGraph::with_config(self.render_config.clone());
+        graph.token_name = self.token_name.clone();
+        graph.token_mint = self.token_mint.clone();
+        let super_node = |address: &str| {
+            clustering
+                .cluster_of(address)
+                .map_or_else(|| address.to_string(), Clustering::node_address)
+        };
+        graph.origin = self.origin.as_deref().map(super_node);
+        graph.target = self.target.as_deref().map(super_node);
+
+        for cluster in &clustering.clusters {
+            let address = Clustering::node_address(cluster.id);
+            let label = match cluster.members.as_slice() {
+                [only] => self
+                    .nodes
+                    .get(only)
+                    .and_then(|n| n.label.clone())
+                    .unwrap_or_else(|| only.clone()),
+                members => format!("Cluster {} ({} wallets)", cluster.id, members.len()),
+            };
+            graph.nodes.insert(
+                address.clone(),
+                GraphNode {
+                    address,
+                    label: Some(label),
+                    incoming: Vec::new(),
+                    outgoing: Vec::new(),
+                },
+            );
+        }
+        for transfer in self.sorted_nodes().iter().flat_map(|n| n.outgoing.iter()) {
+            let (from, to) = (super_node(&transfer.from), super_node(&transfer.to));
+            if from != to {
+                graph.add_transfer(Transfer {
+                    from,
+                    to,
+                    ..transfer.clone()
+                });
+            }
+        }
+        graph
+    }
+}
+
+/// Undirected weighted view of the graph, indexed by sorted address
+struct Undirected<'a> {
+    addresses: Vec<&'a str>,
+    nodes: Vec<&'a GraphNode>,
+    index: HashMap<&'a str, usize>,
+    /// Neighbours other than the node itself, with the amount moved either
+    /// way; a neighbour reached by zero-amount transfers has weight 0
+    adjacent: Vec<BTreeMap<usize, f64>>,
+    /// Amount each address sent to itself
+    loops: Vec<f64>,
+}
+
+impl<'a> Undirected<'a> {
+    fn new(graph: &'a TransferGraph) -> Self {
+        let nodes = graph.sorted_nodes();
+        let addresses: Vec<&str> = nodes.iter().map(|n| n.address.as_str()).collect();
+        let index: HashMap<&str, usize> =
+            addresses.iter().enumerate().map(|(i, a)| (*a, i)).collect();
+        let mut adjacent = vec![BTreeMap::new(); nodes.len()];
+        let mut loops = vec![0.0; nodes.len()];
+        for (from, node) in nodes.iter().enumerate() {
+            for transfer in &node.outgoing {
+                let to = index[transfer.to.as_str()];
+                let weight = transfer.amount.to_f64().max(0.0);
+                if from == to {
+                    loops[from] += weight;
+                } else {
+                    *adjacent[from].entry(to).or_insert(0.0) += weight;
+                    *adjacent[to].entry(from).or_insert(0.0) += weight;
+                }
+            }
+        }
+        Undirected {
+            addresses,
+            nodes,
+            index,
+            adjacent,
+            loops,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    /// Split each group of `community` into its connected parts; returns
+    /// a part number per node
+    fn components(&self, community: &[usize]) -> Vec<usize> {
+        let mut part = vec![usize::MAX; self.len()];
+        for start in 0..self.len() {
+            if part[start] != usize::MAX {
+                continue;
+            }
+            part[start] = start;
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                for &next in self.adjacent[node].keys() {
+                    if part[next] == usize::MAX && community[next] == community[start] {
+                        part[next] = start;
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+        part
+    }
+
+    /// Louvain modularity optimisation; returns a community number per
+    /// node
+    fn louvain(&self) -> Vec<usize> {
+        let mut level = Level {
+            adjacent: self
+                .adjacent
+                .iter()
+                .map(|edges| edges.iter().map(|(&n, &w)| (n, w)).collect())
+                .collect(),
+            loops: self.loops.clone(),
+        };
+        let mut community: Vec<usize> = (0..self.len()).collect();
+
+        loop {
+            let moved = level.local_moves();
+            let count = moved.iter().max().map_or(0, |&c| c + 1);
+            if count == level.adjacent.len() {
+                return community;
+            }
+            for c in community.iter_mut() {
+                *c = moved[*c];
+            }
+            level = level.aggregate(&moved, count);
+        }
+    }
+}
+
+/// One level of the Louvain hierarchy: each node is a community of the
+/// level below
+struct Level {
+    adjacent: Vec<Vec<(usize, f64)>>,
+    loops: Vec<f64>,
+}
+
+impl Level {
+    /// Move nodes between communities while modularity improves; returns
+    /// communities numbered from 0 in order of first appearance
+    fn local_moves(&self) -> Vec<usize> {
+        let count = self.adjacent.len();
+        let degree: Vec<f64> = (0..count)
+            .map(|i| self.adjacent[i].iter().map(|&(_, w)| w).sum::<f64>() + 2.0 * self.loops[i])
+            .collect();
+        let total: f64 = degree.iter().sum();
+        let mut community: Vec<usize> = (0..count).collect();
+        if total <= 0.0 {
+            return community;
+        }
+        let mut community_degree = degree.clone();
+
+        let mut moved = true;
+        while moved {
+            moved = false;
+            for node in 0..count {
+                let current = community[node];
+                let mut links: BTreeMap<usize, f64> = BTreeMap::new();
+                for &(next, weight) in &self.adjacent[node] {
+                    *links.entry(community[next]).or_insert(0.0) += weight;
+                }
+                community_degree[current] -= degree[node];
+
+                let gain =
+                    |c: usize, links: f64| links - community_degree[c] * degree[node] / total;
+                let mut best = current;
+                let mut best_gain = gain(current, links.get(&current).copied().unwrap_or(0.0));
+                for (&c, &weight) in &links {
+                    let candidate = gain(c, weight);
+                    if candidate > best_gain + 1e-12 {
+                        best = c;
+                        best_gain = candidate;
+                    }
+                }
+
+                community_degree[best] += degree[node];
+                if best != current {
+                    community[node] = best;
+                    moved = true;
+                }
+            }
+        }
+
+        let mut renumber: HashMap<usize, usize> = HashMap::new();
+        community
+            .iter()
+            .map(|c| {
+                let next = renumber.len();
+                *renumber.entry(*c).or_insert(next)
+            })
+            .collect()
+    }
+
+    /// Merge each community into a single node
+    fn aggregate(&self, community: &[usize], count: usize) -> Level {
+        let mut edges: Vec<BTreeMap<usize, f64>> = vec![BTreeMap::new(); count];
+        let mut loops = vec![0.0; count];
+        for (node, neighbours) in self.adjacent.iter().enumerate() {
+            let c = community[node];
+            loops[c] += self.loops[node];
+            for &(next, weight) in neighbours {
+                if community[next] == c {
+                    // Each internal edge is listed from both ends
+                    loops[c] += weight / 2.0;
+                } else {
+                    *edges[c].entry(community[next]).or_insert(0.0) += weight;
+                }
+            }
+        }
+        Level {
+            adjacent: edges.into_iter().map(|e| e.into_iter().collect()).collect(),
+            loops,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    /// Two tightly knit triangles joined by one small transfer, plus a
+    /// separate pair
+    fn graph() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        for (a, b) in [("A1", "A2"), ("A2", "A3"), ("A3", "A1")] {
+            graph.add_transfer(transfer(a, b, 100));
+        }
+        for (a, b) in [("B1", "B2"), ("B2", "B3"), ("B3", "B1")] {
+            graph.add_transfer(transfer(a, b, 100));
+        }
+        graph.add_transfer(transfer("A3", "B1", 5));
+        graph.add_transfer(transfer("X", "Y", 1));
+        graph
+    }
+
+    fn members(clustering: &Clustering) -> Vec<Vec<&str>> {
+        clustering
+            .clusters
+            .iter()
+            .map(|c| c.members.iter().map(|m| m.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let clustering = graph().clusters(ClusterMethod::Components);
+        assert_eq!(
+            members(&clustering),
+            [vec!["A1", "A2", "A3", "B1", "B2", "B3"], vec!["X", "Y"]]
+        );
+        assert_eq!(clustering.cluster_of("B2"), Some(0));
+        assert_eq!(clustering.cluster_of("Y"), Some(1));
+        assert_eq!(clustering.cluster_of("Z"), None);
+        assert_eq!(clustering.clusters[0].internal, TokenAmount::from(605));
+        assert_eq!(clustering.clusters[0].inflow, TokenAmount::ZERO);
+    }
+
+    #[test]
+    fn test_louvain() {
+        let clustering = graph().clusters(ClusterMethod::Louvain);
+        assert_eq!(
+            members(&clustering),
+            [
+                vec!["A1", "A2", "A3"],
+                vec!["B1", "B2", "B3"],
+                vec!["X", "Y"]
+            ]
+        );
+        let a = &clustering.clusters[0];
+        assert_eq!(a.outflow, TokenAmount::from(5));
+        assert_eq!(a.internal, TokenAmount::from(300));
+        assert_eq!(clustering.clusters[1].inflow, TokenAmount::from(5));
+
+        assert!(TransferGraph::new()
+            .clusters(ClusterMethod::Louvain)
+            .clusters
+            .is_empty());
+    }
+
+    #[test]
+    fn test_collapse_clusters() {
+        let mut graph = graph();
+        graph.origin = Some("A1".to_string());
+        graph.target = Some("B3".to_string());
+        graph.set_node_label("X", "Exchange".to_string());
+        let clustering = graph.clusters(ClusterMethod::Louvain);
+        let collapsed = graph.collapse_clusters(&clustering);
+
+        assert_eq!(collapsed.origin.as_deref(), Some("cluster-0"));
+        assert_eq!(collapsed.target.as_deref(), Some("cluster-1"));
+        assert_eq!(collapsed.nodes.len(), 3);
+        let a = &collapsed.nodes["cluster-0"];
+        assert_eq!(a.label.as_deref(), Some("Cluster 0 (3 wallets)"));
+        assert_eq!(a.outgoing.len(), 1);
+        assert_eq!(a.outgoing[0].to, "cluster-1");
+        assert_eq!(a.outgoing[0].amount, TokenAmount::from(5));
+
+        let output = collapsed.render_dot();
+        assert!(output.contains("Cluster 1 (3 wallets)"));
+        assert!(!output.contains("A2"));
+
+        graph.render_config.collapse_clusters = Some(ClusterMethod::Louvain);
+        assert_eq!(graph.render_dot(), output);
+        let ascii = graph.render_ascii();
+        assert!(ascii.contains("🏦 ORIGIN Cluster 0 (3 wallets) cluster-0"));
+        assert!(ascii.contains("🎯 TARGET Cluster 1 (3 wallets) cluster-1"));
+    }
+}
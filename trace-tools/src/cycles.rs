@@ -0,0 +1,494 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{TokenAmount, Transfer, TransferGraph};
+
+/// Bounds and scoring for [`TransferGraph::iter_cycles`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct CycleConfig {
+    /// Most addresses in a cycle
+    pub max_length: usize,
+    /// Stop after this many cycles
+    pub max_cycles: Option<usize>,
+    /// Stop after following this many links between addresses
+    pub max_steps: Option<usize>,
+    /// Time spread at which a cycle's time proximity drops to one half
+    pub time_window: Duration,
+}
+
+impl Default for CycleConfig {
+    fn default() -> Self {
+        CycleConfig {
+            max_length: 6,
+            max_cycles: Some(100),
+            max_steps: Some(100_000),
+            time_window: Duration::from_secs(3600),
+        }
+    }
+}
+
+/// Which limit cut a cycle search short
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CycleLimit {
+    /// `max_cycles` cycles were returned and at least one more exists
+    Cycles,
+    /// The `max_steps` budget ran out
+    Steps,
+}
+
+/// Funds going round a loop of addresses back to where they started
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cycle<'a> {
+    /// Members in order, starting with the smallest address; the last one
+    /// sends back to the first
+    pub addresses: Vec<&'a str>,
+    /// All transfers from each member to the next
+    pub hops: Vec<Vec<&'a Transfer>>,
+}
+
+impl<'a> Cycle<'a> {
+    /// Number of members (and hops)
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// Total amount moved along the cycle's hops, or `None` on overflow
+    pub fn volume(&self) -> Option<TokenAmount> {
+        TokenAmount::checked_sum(self.hops.iter().flatten().map(|t| &t.amount))
+    }
+
+    /// Likelihood in `0.0..=1.0` that the cycle is wash trading
+    ///
+    /// Each hop is represented by its largest transfer. The score is the
+    /// ratio of the smallest to the largest of those amounts (funds coming
+    /// back almost untouched), times a time proximity of
+    /// `1 / (1 + spread / time_window)` where `spread` is the time between
+    /// the earliest and the latest of them. Cycles with an untimed hop are
+    /// scored on amounts alone.
+    pub fn wash_score(&self, time_window: Duration) -> f64 {
+        let representatives: Vec<&Transfer> = self
+            .hops
+            .iter()
+            .filter_map(|hop| hop.iter().copied().max_by_key(|t| t.amount))
+            .collect();
+        let (Some(smallest), Some(largest)) = (
+            representatives.iter().map(|t| t.amount).min(),
+            representatives.iter().map(|t| t.amount).max(),
+        ) else {
+            return 0.0;
+        };
+        let similarity = if largest.to_f64() > 0.0 {
+            (smallest.to_f64() / largest.to_f64()).max(0.0)
+        } else {
+            0.0
+        };
+
+        let times: Option<Vec<i64>> = representatives
+            .iter()
+            .map(|t| t.timestamp.map(|ts| ts.unix()))
+            .collect();
+        let proximity = match times {
+            Some(times) if !time_window.is_zero() => {
+                let spread = times.iter().max().unwrap_or(&0) - times.iter().min().unwrap_or(&0);
+                1.0 / (1.0 + spread as f64 / time_window.as_secs_f64())
+            }
+            _ => 1.0,
+        };
+        similarity * proximity
+    }
+}
+
+impl TransferGraph {
+    /// Strongly connected components (Tarjan's algorithm)
+    ///
+    /// Every address belongs to exactly one component; funds can circulate
+    /// among the members of a component with more than one address. Members
+    /// are sorted and components are ordered by their first member.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<&str>> {
+        let adjacency = self.adjacency();
+        let count = adjacency.addresses.len();
+
+        let mut index = vec![usize::MAX; count];
+        let mut low = vec![0; count];
+        let mut on_stack = vec![false; count];
+        let mut stack: Vec<usize> = Vec::new();
+        let mut next_index = 0;
+        let mut components: Vec<Vec<&str>> = Vec::new();
+
+        for root in 0..count {
+            if index[root] != usize::MAX {
+                continue;
+            }
+            // Explicit call stack of (node, next neighbour to visit)
+            let mut calls: Vec<(usize, usize)> = vec![(root, 0)];
+            index[root] = next_index;
+            low[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some((node, next)) = calls.last_mut() {
+                let node = *node;
+                if let Some(&neighbour) = adjacency.edges[node].get(*next) {
+                    *next += 1;
+                    if index[neighbour] == usize::MAX {
+                        index[neighbour] = next_index;
+                        low[neighbour] = next_index;
+                        next_index += 1;
+                        stack.push(neighbour);
+                        on_stack[neighbour] = true;
+                        calls.push((neighbour, 0));
+                    } else if on_stack[neighbour] {
+                        low[node] = low[node].min(index[neighbour]);
+                    }
+                    continue;
+                }
+
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    low[parent] = low[parent].min(low[node]);
+                }
+                if low[node] == index[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(adjacency.addresses[member]);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    component.sort_unstable();
+                    components.push(component);
+                }
+            }
+        }
+
+        components.sort_unstable();
+        components
+    }
+
+    /// Collect the simple cycles found within the bounds of `config`
+    ///
+    /// Use [`iter_cycles`](TransferGraph::iter_cycles) to learn whether a
+    /// bound cut the results short.
+    pub fn find_cycles(&self, config: &CycleConfig) -> Vec<Cycle<'_>> {
+        self.iter_cycles(config).collect()
+    }
+
+    /// Lazily enumerate simple cycles within the bounds of `config`
+    ///
+    /// Each cycle is reported once, starting at its smallest address.
+    /// Cycles are found per strongly connected component, so the search
+    /// never explores addresses that cannot lead back. A self-transfer is a
+    /// cycle of length one. Check [`CycleIter::limit_reached`] afterwards to
+    /// learn whether the results are complete.
+    pub fn iter_cycles(&self, config: &CycleConfig) -> CycleIter<'_> {
+        let adjacency = self.adjacency();
+        let mut component = vec![0; adjacency.addresses.len()];
+        for (id, members) in self.strongly_connected_components().iter().enumerate() {
+            for member in members {
+                component[adjacency.index[member]] = id;
+            }
+        }
+        CycleIter {
+            graph: self,
+            config: config.clone(),
+            on_path: vec![false; adjacency.addresses.len()],
+            adjacency,
+            component,
+            next_start: 0,
+            stack: Vec::new(),
+            steps: 0,
+            found: 0,
+            limit: None,
+        }
+    }
+
+    /// Addresses in sorted order with the distinct receivers of each
+    fn adjacency(&self) -> Adjacency<'_> {
+        let nodes = self.sorted_nodes();
+        let addresses: Vec<&str> = nodes.iter().map(|n| n.address.as_str()).collect();
+        let index: HashMap<&str, usize> =
+            addresses.iter().enumerate().map(|(i, a)| (*a, i)).collect();
+        let edges = nodes
+            .iter()
+            .map(|node| {
+                let mut receivers: Vec<usize> = node
+                    .outgoing
+                    .iter()
+                    .filter_map(|t| index.get(t.to.as_str()).copied())
+                    .collect();
+                receivers.sort_unstable();
+                receivers.dedup();
+                receivers
+            })
+            .collect();
+        Adjacency {
+            addresses,
+            index,
+            edges,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Adjacency<'a> {
+    addresses: Vec<&'a str>,
+    index: HashMap<&'a str, usize>,
+    edges: Vec<Vec<usize>>,
+}
+
+/// Lazy depth-first search for cycles, see [`TransferGraph::iter_cycles`]
+///
+/// Cycles through each start address whose other members all sort after it
+/// are searched in turn, which finds every cycle exactly once.
+#[derive(Debug)]
+pub struct CycleIter<'a> {
+    graph: &'a TransferGraph,
+    config: CycleConfig,
+    adjacency: Adjacency<'a>,
+    /// Strongly connected component of each address
+    component: Vec<usize>,
+    next_start: usize,
+    /// Addresses on the current path, starting with the start address, and
+    /// the index of the next receiver of each to try
+    stack: Vec<(usize, usize)>,
+    on_path: Vec<bool>,
+    steps: usize,
+    found: usize,
+    limit: Option<CycleLimit>,
+}
+
+impl<'a> CycleIter<'a> {
+    /// The limit that cut the search short, if any. Only final once the
+    /// iterator has returned `None`.
+    pub fn limit_reached(&self) -> Option<CycleLimit> {
+        self.limit
+    }
+
+    /// Links between addresses followed so far
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    fn stop(&mut self, limit: CycleLimit) -> Option<Vec<usize>> {
+        self.limit = Some(limit);
+        self.stack.clear();
+        None
+    }
+
+    /// Members of the next cycle, or `None` once the search is over
+    fn advance(&mut self) -> Option<Vec<usize>> {
+        loop {
+            let Some((node, next)) = self.stack.last_mut() else {
+                let start = self.next_start;
+                if start >= self.adjacency.addresses.len() {
+                    return None;
+                }
+                self.next_start += 1;
+                self.stack.push((start, 0));
+                self.on_path[start] = true;
+                continue;
+            };
+            let node = *node;
+            let Some(&receiver) = self.adjacency.edges[node].get(*next) else {
+                self.stack.pop();
+                self.on_path[node] = false;
+                continue;
+            };
+            *next += 1;
+
+            self.steps += 1;
+            if self.config.max_steps.is_some_and(|max| self.steps > max) {
+                self.steps -= 1;
+                return self.stop(CycleLimit::Steps);
+            }
+            let start = self.stack[0].0;
+            if receiver == start {
+                return Some(self.stack.iter().map(|&(member, _)| member).collect());
+            }
+            if receiver < start
+                || self.on_path[receiver]
+                || self.component[receiver] != self.component[start]
+                || self.stack.len() >= self.config.max_length
+            {
+                continue;
+            }
+            self.stack.push((receiver, 0));
+            self.on_path[receiver] = true;
+        }
+    }
+}
+
+impl<'a> Iterator for CycleIter<'a> {
+    type Item = Cycle<'a>;
+
+    fn next(&mut self) -> Option<Cycle<'a>> {
+        if self.limit.is_some() {
+            return None;
+        }
+        let members = self.advance()?;
+        // Only report the cycle limit once another cycle proves it mattered
+        if self.config.max_cycles.is_some_and(|max| self.found >= max) {
+            self.stop(CycleLimit::Cycles);
+            return None;
+        }
+        self.found += 1;
+
+        let addresses: Vec<&'a str> = members
+            .iter()
+            .map(|&m| self.adjacency.addresses[m])
+            .collect();
+        let hops = (0..addresses.len())
+            .map(|i| {
+                let (from, to) = (addresses[i], addresses[(i + 1) % addresses.len()]);
+                self.graph.nodes[from]
+                    .outgoing
+                    .iter()
+                    .filter(|t| t.to == to)
+                    .collect()
+            })
+            .collect();
+        Some(Cycle { addresses, hops })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str, amount: u64, timestamp: &str) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: Some(timestamp.parse().unwrap()),
+            slot: None,
+            note: None,
+        }
+    }
+
+    /// A -> B -> C -> A and B -> D -> B, with C -> E leaving the loop
+    fn graph() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 100, "2024-01-01 00:00"));
+        graph.add_transfer(transfer("B", "C", 99, "2024-01-01 00:10"));
+        graph.add_transfer(transfer("C", "A", 98, "2024-01-01 00:20"));
+        graph.add_transfer(transfer("C", "A", 1, "2024-01-01 00:30"));
+        graph.add_transfer(transfer("B", "D", 10, "2024-01-01 00:00"));
+        graph.add_transfer(transfer("D", "B", 5, "2024-01-03 00:00"));
+        graph.add_transfer(transfer("C", "E", 7, "2024-01-01 00:00"));
+        graph
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let graph = graph();
+        let components = graph.strongly_connected_components();
+        assert_eq!(components, [vec!["A", "B", "C", "D"], vec!["E"]]);
+
+        let mut chain = TransferGraph::new();
+        chain.add_transfer(transfer("X", "Y", 1, "2024-01-01"));
+        assert_eq!(
+            chain.strongly_connected_components(),
+            [vec!["X"], vec!["Y"]]
+        );
+    }
+
+    #[test]
+    fn test_find_cycles() {
+        let graph = graph();
+        let cycles = graph.find_cycles(&CycleConfig::default());
+        let members: Vec<Vec<&str>> = cycles.iter().map(|c| c.addresses.clone()).collect();
+        assert_eq!(members, [vec!["A", "B", "C"], vec!["B", "D"]]);
+        assert_eq!(cycles[0].hops[2].len(), 2);
+        assert_eq!(cycles[0].volume(), Some(TokenAmount::from(298)));
+
+        let short = CycleConfig {
+            max_length: 2,
+            ..CycleConfig::default()
+        };
+        assert_eq!(graph.find_cycles(&short).len(), 1);
+        let capped = CycleConfig {
+            max_cycles: Some(1),
+            ..CycleConfig::default()
+        };
+        let mut search = graph.iter_cycles(&capped);
+        assert_eq!(search.by_ref().count(), 1);
+        assert_eq!(search.limit_reached(), Some(CycleLimit::Cycles));
+        // Exactly as many cycles as the cap finishes the search
+        let exact = CycleConfig {
+            max_cycles: Some(2),
+            ..CycleConfig::default()
+        };
+        let mut search = graph.iter_cycles(&exact);
+        assert_eq!(search.by_ref().count(), 2);
+        assert_eq!(search.limit_reached(), None);
+
+        let mut selfie = TransferGraph::new();
+        selfie.add_transfer(transfer("S", "S", 1, "2024-01-01"));
+        assert_eq!(
+            selfie.find_cycles(&CycleConfig::default())[0].addresses,
+            ["S"]
+        );
+    }
+
+    #[test]
+    fn test_cycle_search_budget() {
+        // Eight fully connected layers of ten addresses, the last paying
+        // back into the first: one component whose cycles all have eight
+        // members, so a search for shorter ones never finds any
+        let mut graph = TransferGraph::new();
+        for layer in 0..8 {
+            for a in 0..10 {
+                for b in 0..10 {
+                    let from = format!("L{}-{}", layer, a);
+                    let to = format!("L{}-{}", (layer + 1) % 8, b);
+                    graph.add_transfer(transfer(&from, &to, 1, "2024-01-01"));
+                }
+            }
+        }
+        assert_eq!(graph.strongly_connected_components().len(), 1);
+        let config = CycleConfig {
+            max_length: 6,
+            max_steps: Some(10_000),
+            ..CycleConfig::default()
+        };
+        let mut search = graph.iter_cycles(&config);
+        assert_eq!(search.by_ref().count(), 0);
+        assert_eq!(search.steps(), 10_000);
+        assert_eq!(search.limit_reached(), Some(CycleLimit::Steps));
+    }
+
+    #[test]
+    fn test_wash_score() {
+        let graph = graph();
+        let cycles = graph.find_cycles(&CycleConfig::default());
+        let hour = Duration::from_secs(3600);
+
+        // 100 -> 99 -> 98 within 20 minutes
+        let tight = cycles[0].wash_score(hour);
+        assert!((tight - 0.98 * 0.75).abs() < 1e-9, "{}", tight);
+        // 10 out, 5 back two days later
+        let loose = cycles[1].wash_score(hour);
+        assert!(loose < 0.02, "{}", loose);
+
+        let mut untimed = cycles[0].clone();
+        let hop = Transfer {
+            timestamp: None,
+            ..untimed.hops[0][0].clone()
+        };
+        untimed.hops[0] = vec![&hop];
+        assert!((untimed.wash_score(hour) - 0.98).abs() < 1e-9);
+    }
+}
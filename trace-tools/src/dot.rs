@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::output::render_to_string;
+use crate::{GraphNode, Transfer, TransferGraph};
+
+/// Configuration for Graphviz DOT output, see
+/// [`RenderConfig::dot`](crate::RenderConfig::dot)
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct DotConfig {
+    /// Graph layout direction (`LR`, `TB`, ...)
+    pub rankdir: String,
+    pub show_amounts: bool,
+    pub show_timestamps: bool,
+    pub show_notes: bool,
+    /// Edge pen width used for the smallest transfer
+    pub min_pen_width: f64,
+    /// Edge pen width used for the largest transfer
+    pub max_pen_width: f64,
+    /// Group nodes sharing a label into a `cluster_*` subgraph
+    pub cluster_by_label: bool,
+    pub origin_color: String,
+    pub target_color: String,
+    pub labelled_color: String,
+}
+
+impl Default for DotConfig {
+    fn default() -> Self {
+        DotConfig {
+            rankdir: "LR".to_string(),
+            show_amounts: true,
+            show_timestamps: true,
+            show_notes: true,
+            min_pen_width: 1.0,
+            max_pen_width: 8.0,
+            cluster_by_label: false,
+            origin_color: "#a7f3d0".to_string(),
+            target_color: "#fecaca".to_string(),
+            labelled_color: "#e0e7ff".to_string(),
+        }
+    }
+}
+
+impl TransferGraph {
+    /// Render the graph as a Graphviz DOT document using
+    /// `RenderConfig::dot`
+    pub fn render_dot(&self) -> String {
+        self.render_dot_with(&self.render_config.dot)
+    }
+
+    /// Render the graph as a Graphviz DOT document with `dot` instead of
+    /// `RenderConfig::dot`
+    pub fn render_dot_with(&self, dot: &DotConfig) -> String {
+        render_to_string(|out| self.write_dot_with(dot, out))
+    }
+
+    /// Stream the DOT document of `render_dot` to `out`
+    ///
+    /// Wrap unbuffered writers such as files in a `BufWriter`.
+    pub fn write_dot<W: Write>(&self, out: W) -> io::Result<()> {
+        self.write_dot_with(&self.render_config.dot, out)
+    }
+
+    /// `write_dot` with `dot` instead of `RenderConfig::dot`
+    pub fn write_dot_with<W: Write>(&self, dot: &DotConfig, mut out: W) -> io::Result<()> {
+        if let Some(view) = self.render_view() {
+            return view.write_dot_with(dot, out);
+        }
+        let cfg = &self.render_config;
+
+        out.write_all(b"digraph transfers {\n")?;
+        writeln!(out, "    rankdir={};", dot.rankdir)?;
+        if cfg.show_header {
+            let mut title = cfg.title.clone();
+            if let Some(token) = &self.token_name {
+                title.push_str(&format!("\n{}", token));
+            }
+            writeln!(
+                out,
+                "    label=\"{}\";\n    labelloc=t;",
+                escape_dot(&title)
+            )?;
+        }
+        out.write_all(b"    node [shape=box, style=\"rounded,filled\", fillcolor=\"#ffffff\", fontname=\"Helvetica\"];\n")?;
+        out.write_all(b"    edge [fontname=\"Helvetica\", fontsize=10];\n\n")?;
+
+        let nodes = self.sorted_nodes();
+
+        if dot.cluster_by_label {
+            let mut clusters: BTreeMap<&str, Vec<&GraphNode>> = BTreeMap::new();
+            for node in &nodes {
+                match &node.label {
+                    Some(label) => clusters.entry(label).or_default().push(node),
+                    None => self.write_dot_node(&mut out, node, dot, "    ")?,
+                }
+            }
+            for (idx, (label, members)) in clusters.iter().enumerate() {
+                writeln!(out, "\n    subgraph cluster_{} {{", idx)?;
+                writeln!(
+                    out,
+                    "        label=\"{}\";\n        style=\"rounded,dashed\";",
+                    escape_dot(label)
+                )?;
+                for node in members {
+                    self.write_dot_node(&mut out, node, dot, "        ")?;
+                }
+                out.write_all(b"    }\n")?;
+            }
+        } else {
+            for node in &nodes {
+                self.write_dot_node(&mut out, node, dot, "    ")?;
+            }
+        }
+
+        out.write_all(b"\n")?;
+        let max_amount = nodes
+            .iter()
+            .flat_map(|n| n.outgoing.iter())
+            .map(|t| t.amount.to_f64())
+            .fold(0.0_f64, f64::max);
+        for node in &nodes {
+            for transfer in &node.outgoing {
+                self.write_dot_edge(&mut out, transfer, dot, max_amount)?;
+            }
+        }
+
+        out.write_all(b"}\n")
+    }
+
+    fn write_dot_node<W: Write>(
+        &self,
+        out: &mut W,
+        node: &GraphNode,
+        dot: &DotConfig,
+        indent: &str,
+    ) -> io::Result<()> {
+        let cfg = &self.render_config;
+        let addr = node.address.as_str();
+        let short = self.truncate_address(addr, cfg.address_truncate_length);
+
+        let mut label = String::new();
+        let mut attrs = Vec::new();
+        if Some(addr) == self.origin.as_deref() {
+            label.push_str(&format!("{}\n", cfg.origin_icon));
+            attrs.push(format!("fillcolor=\"{}\"", dot.origin_color));
+            attrs.push("penwidth=2".to_string());
+        } else if Some(addr) == self.target.as_deref() {
+            label.push_str(&format!("{}\n", cfg.target_icon));
+            attrs.push(format!("fillcolor=\"{}\"", dot.target_color));
+            attrs.push("penwidth=2".to_string());
+        } else if node.label.is_some() {
+            attrs.push(format!("fillcolor=\"{}\"", dot.labelled_color));
+        }
+        if let Some(node_label) = &node.label {
+            label.push_str(&format!("{}\n", node_label));
+        }
+        label.push_str(&short);
+
+        write!(
+            out,
+            "{}\"{}\" [label=\"{}\", tooltip=\"{}\"",
+            indent,
+            escape_dot(addr),
+            escape_dot(&label),
+            escape_dot(addr)
+        )?;
+        for attr in attrs {
+            write!(out, ", {}", attr)?;
+        }
+        out.write_all(b"];\n")
+    }
+
+    fn write_dot_edge<W: Write>(
+        &self,
+        out: &mut W,
+        transfer: &Transfer,
+        dot: &DotConfig,
+        max_amount: f64,
+    ) -> io::Result<()> {
+        let mut lines = Vec::new();
+        if dot.show_amounts {
+            lines.push(format!(
+                "{} {}",
+                self.format_amount(&transfer.amount),
+                transfer.token_symbol
+            ));
+        }
+        if dot.show_timestamps {
+            if let Some(time) = self.format_time(transfer) {
+                lines.push(time);
+            }
+        }
+        if dot.show_notes {
+            if let Some(note) = &transfer.note {
+                lines.push(note.clone());
+            }
+        }
+
+        writeln!(
+            out,
+            "    \"{}\" -> \"{}\" [label=\"{}\", penwidth={:.2}];",
+            escape_dot(&transfer.from),
+            escape_dot(&transfer.to),
+            escape_dot(&lines.join("\n")),
+            pen_width(transfer.amount.to_f64(), max_amount, dot)
+        )
+    }
+}
+
+/// Scale edge width logarithmically so a single huge transfer does not
+/// flatten every other edge to the minimum width
+fn pen_width(amount: f64, max_amount: f64, dot: &DotConfig) -> f64 {
+    if max_amount <= 0.0 || amount <= 0.0 {
+        return dot.min_pen_width;
+    }
+    let ratio = (1.0 + amount).ln() / (1.0 + max_amount).ln();
+    dot.min_pen_width + (dot.max_pen_width - dot.min_pen_width) * ratio.clamp(0.0, 1.0)
+}
+
+/// Escape a string for use inside a double-quoted DOT identifier
+fn escape_dot(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TokenAmount;
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_render_dot() {
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("Origin123".to_string());
+        graph.target = Some("Target789".to_string());
+        graph.add_transfer(Transfer {
+            timestamp: Some("2024-01-01".parse().unwrap()),
+            note: Some("Say \"hi\"".to_string()),
+            ..transfer("Origin123", "Middle456", 1000)
+        });
+        graph.add_transfer(transfer("Middle456", "Target789", 10));
+        graph.set_node_label("Middle456", "Hub".to_string());
+
+        let dot = graph.render_dot();
+        assert!(dot.starts_with("digraph transfers {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains("\"Origin123\" [label=\"🏦 ORIGIN\\nOrigin123\", tooltip=\"Origin123\", fillcolor=\"#a7f3d0\", penwidth=2];"));
+        assert!(dot.contains("\"Middle456\" [label=\"Hub\\nMiddle456\""));
+        assert!(dot.contains(
+            "\"Origin123\" -> \"Middle456\" [label=\"1,000.00 SVMAI\\nJan 1, 2024 00:00:00 UTC\\nSay \\\"hi\\\"\", penwidth=8.00];"
+        ));
+        assert!(
+            dot.contains("\"Middle456\" -> \"Target789\" [label=\"10.00 SVMAI\", penwidth=3.43];")
+        );
+        assert_eq!(dot, graph.render_dot());
+
+        let mut written = Vec::new();
+        graph.write_dot(&mut written).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), dot);
+    }
+
+    #[test]
+    fn test_render_dot_clusters() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 1));
+        graph.add_transfer(transfer("A", "C", 1));
+        graph.set_node_label("B", "Exchange".to_string());
+        graph.set_node_label("C", "Exchange".to_string());
+
+        let dot = graph.render_dot_with(&DotConfig {
+            cluster_by_label: true,
+            ..DotConfig::default()
+        });
+        assert_eq!(dot.matches("subgraph cluster_").count(), 1);
+        assert!(dot.contains("subgraph cluster_0 {\n        label=\"Exchange\";"));
+        assert!(dot.contains("        \"B\" [label=\"Exchange\\nB\""));
+        assert!(dot.contains("        \"C\" [label=\"Exchange\\nC\""));
+
+        graph.render_config.dot.cluster_by_label = true;
+        assert_eq!(graph.render_dot(), dot);
+    }
+}
@@ -0,0 +1,509 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::time::chronological;
+use crate::{TokenAmount, Transfer, TransferGraph};
+
+/// Flow assigned to the transfers from one address to another
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeFlow<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    /// Sum of the transfers between the pair that the search could use
+    pub capacity: TokenAmount,
+    pub flow: TokenAmount,
+}
+
+/// Result of [`TransferGraph::max_flow`] or
+/// [`TransferGraph::temporal_max_flow`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaxFlow<'a> {
+    /// Most that can have moved from the source to the sink
+    pub value: TokenAmount,
+    /// Every address pair with transfers, sorted by sender then receiver,
+    /// with its flow
+    pub edges: Vec<EdgeFlow<'a>>,
+    /// Saturated edges separating the source side from the sink side; their
+    /// capacities add up to `value`
+    pub min_cut: Vec<EdgeFlow<'a>>,
+    /// Transfers a temporal flow could not place in time, in chronological
+    /// order; always empty for [`TransferGraph::max_flow`]
+    pub skipped: Vec<&'a Transfer>,
+}
+
+impl<'a> MaxFlow<'a> {
+    /// Edges that carry flow
+    pub fn used_edges(&self) -> impl Iterator<Item = &EdgeFlow<'a>> {
+        self.edges.iter().filter(|e| !e.flow.is_zero())
+    }
+
+    /// Addresses on the min cut other than the source and sink, in order of
+    /// appearance: the wallets every route to the sink passes through or
+    /// out of
+    pub fn chokepoints(&self, source: &str, sink: &str) -> Vec<&'a str> {
+        let mut wallets: Vec<&'a str> = Vec::new();
+        for edge in &self.min_cut {
+            for addr in [edge.from, edge.to] {
+                if addr != source && addr != sink && !wallets.contains(&addr) {
+                    wallets.push(addr);
+                }
+            }
+        }
+        wallets
+    }
+}
+
+impl TransferGraph {
+    /// Maximum flow from `from` to `to`, treating the summed amounts of
+    /// parallel transfers as edge capacities
+    ///
+    /// This bounds how much of `from`'s tokens could have reached `to` when
+    /// timing is ignored. Transfers with non-positive amounts carry nothing.
+    /// `from` and `to` must differ; otherwise the flow is zero. Returns
+    /// `None` if the amounts overflow when brought to common decimals.
+    pub fn max_flow(&self, from: &str, to: &str) -> Option<MaxFlow<'_>> {
+        let transfers: Vec<&Transfer> = self
+            .sorted_nodes()
+            .into_iter()
+            .flat_map(|n| n.outgoing.iter())
+            .collect();
+        let decimals = common_decimals(&transfers);
+
+        let mut network = Network::default();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        let mut node =
+            |network: &mut Network, addr| *index.entry(addr).or_insert_with(|| network.add_node());
+        let source = node(&mut network, from);
+        let sink = node(&mut network, to);
+
+        let mut pairs = Pairs::default();
+        for transfer in &transfers {
+            let pair = pairs.get(transfer);
+            let capacity = capacity(transfer, decimals)?;
+            pairs.capacity[pair] = pairs.capacity[pair].checked_add(capacity)?;
+        }
+        for pair in 0..pairs.keys.len() {
+            let (a, b) = pairs.keys[pair];
+            let (a, b) = (node(&mut network, a), node(&mut network, b));
+            let arc = network.add_arc(a, b, pairs.capacity[pair]);
+            pairs.arcs[pair].push(arc);
+        }
+
+        Some(pairs.solve(network, source, sink, from == to, decimals))
+    }
+
+    /// Maximum flow from `from` to `to` over time-respecting routes
+    ///
+    /// Funds may only leave an address at or after they arrived there
+    /// (same-second transfers are ordered by slot), so this is at most
+    /// [`max_flow`](TransferGraph::max_flow). If any transfer has a slot but
+    /// no timestamp, every transfer is ordered by slot instead. Transfers
+    /// without the timestamp or slot the order needs are left out and listed
+    /// in [`MaxFlow::skipped`]; each pair's capacity only counts the
+    /// transfers used. `from` is assumed to hold its tokens from the start.
+    pub fn temporal_max_flow(&self, from: &str, to: &str) -> Option<MaxFlow<'_>> {
+        let candidates: Vec<&Transfer> = self
+            .transfers_by_time()
+            .into_iter()
+            .filter(|t| t.from != to)
+            .collect();
+        let by_slot = candidates
+            .iter()
+            .any(|t| t.timestamp.is_none() && t.slot.is_some());
+        let (mut transfers, skipped): (Vec<&Transfer>, Vec<&Transfer>) =
+            candidates.into_iter().partition(|t| {
+                if by_slot {
+                    t.slot.is_some()
+                } else {
+                    t.timestamp.is_some()
+                }
+            });
+        // Stable, so transfers in one slot stay in timestamp order
+        if by_slot {
+            transfers.sort_by_key(|t| t.slot);
+        }
+        let simultaneous = |a: &Transfer, b: &Transfer| {
+            if by_slot {
+                a.slot == b.slot
+            } else {
+                chronological(a, b).is_eq()
+            }
+        };
+        let decimals = common_decimals(&transfers);
+
+        // A time-expanded network: one node per address and distinct moment
+        // it sends or receives, linked in time order by unbounded "funds
+        // wait here" arcs. Transfers sharing a moment share the node, so
+        // funds received can be forwarded in the same moment.
+        let mut network = Network::default();
+        let source = network.add_node();
+        let sink = network.add_node();
+        let mut latest: HashMap<&str, (&Transfer, usize)> = HashMap::new();
+        let mut moment = |network: &mut Network, addr, transfer| {
+            if let Some(&(last, node)) = latest.get(addr) {
+                if simultaneous(last, transfer) {
+                    return node;
+                }
+                let next = network.add_node();
+                network.add_arc(node, next, UNBOUNDED);
+                latest.insert(addr, (transfer, next));
+                return next;
+            }
+            let node = network.add_node();
+            latest.insert(addr, (transfer, node));
+            node
+        };
+
+        let mut pairs = Pairs::default();
+        let mut holding = false;
+        for transfer in &transfers {
+            let pair = pairs.get(transfer);
+            let capacity = capacity(transfer, decimals)?;
+            pairs.capacity[pair] = pairs.capacity[pair].checked_add(capacity)?;
+
+            let sender = moment(&mut network, &transfer.from, transfer);
+            // Later moments of `from` are reached through the wait arcs
+            if transfer.from == from && !holding {
+                network.add_arc(source, sender, UNBOUNDED);
+                holding = true;
+            }
+            let receiver = moment(&mut network, &transfer.to, transfer);
+            if transfer.to == to {
+                network.add_arc(receiver, sink, UNBOUNDED);
+            }
+            let arc = network.add_arc(sender, receiver, capacity);
+            pairs.arcs[pair].push(arc);
+        }
+
+        let mut flow = pairs.solve(network, source, sink, from == to, decimals);
+        flow.skipped = skipped;
+        Some(flow)
+    }
+}
+
+/// Capacity of the arcs that stand for "funds wait here"
+const UNBOUNDED: i128 = i128::MAX;
+
+/// Address pairs in order of first appearance, with their summed capacity
+/// and the network arcs carrying their transfers
+#[derive(Default)]
+struct Pairs<'a> {
+    keys: Vec<(&'a str, &'a str)>,
+    index: HashMap<(&'a str, &'a str), usize>,
+    capacity: Vec<i128>,
+    arcs: Vec<Vec<usize>>,
+}
+
+impl<'a> Pairs<'a> {
+    fn get(&mut self, transfer: &'a Transfer) -> usize {
+        let key = (transfer.from.as_str(), transfer.to.as_str());
+        *self.index.entry(key).or_insert_with(|| {
+            self.keys.push(key);
+            self.capacity.push(0);
+            self.arcs.push(Vec::new());
+            self.keys.len() - 1
+        })
+    }
+
+    fn solve(
+        self,
+        mut network: Network,
+        source: usize,
+        sink: usize,
+        same: bool,
+        decimals: u8,
+    ) -> MaxFlow<'a> {
+        let value = if same {
+            0
+        } else {
+            network.max_flow(source, sink)
+        };
+        let reachable = network.reachable(source);
+        let amount = |raw| TokenAmount::new(raw, decimals);
+
+        let mut edges = Vec::new();
+        let mut min_cut = Vec::new();
+        let mut order: Vec<usize> = (0..self.keys.len()).collect();
+        order.sort_by_key(|&pair| self.keys[pair]);
+        for pair in order {
+            let (from, to) = self.keys[pair];
+            let arcs = &self.arcs[pair];
+            let flow = arcs.iter().map(|&a| network.arcs[a].flow).sum();
+            edges.push(EdgeFlow {
+                from,
+                to,
+                capacity: amount(self.capacity[pair]),
+                flow: amount(flow),
+            });
+            let cut: i128 = arcs
+                .iter()
+                .filter(|&&a| network.crosses(a, &reachable))
+                .map(|&a| network.arcs[a].capacity)
+                .sum();
+            if cut > 0 {
+                min_cut.push(EdgeFlow {
+                    from,
+                    to,
+                    capacity: amount(cut),
+                    flow: amount(cut),
+                });
+            }
+        }
+        MaxFlow {
+            value: amount(value),
+            edges,
+            min_cut,
+            skipped: Vec::new(),
+        }
+    }
+}
+
+fn common_decimals(transfers: &[&Transfer]) -> u8 {
+    transfers
+        .iter()
+        .map(|t| t.amount.decimals)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Transfer amount in base units at `decimals`; negative amounts carry
+/// nothing
+fn capacity(transfer: &Transfer, decimals: u8) -> Option<i128> {
+    Some(transfer.amount.rescale(decimals)?.raw.max(0))
+}
+
+struct Arc {
+    to: usize,
+    capacity: i128,
+    flow: i128,
+}
+
+/// Flow network with residual arcs stored in pairs: arc `i ^ 1` is the
+/// reverse of arc `i`
+#[derive(Default)]
+struct Network {
+    arcs: Vec<Arc>,
+    adjacent: Vec<Vec<usize>>,
+}
+
+impl Network {
+    fn add_node(&mut self) -> usize {
+        self.adjacent.push(Vec::new());
+        self.adjacent.len() - 1
+    }
+
+    fn add_arc(&mut self, from: usize, to: usize, capacity: i128) -> usize {
+        let id = self.arcs.len();
+        self.arcs.push(Arc {
+            to,
+            capacity,
+            flow: 0,
+        });
+        self.arcs.push(Arc {
+            to: from,
+            capacity: 0,
+            flow: 0,
+        });
+        self.adjacent[from].push(id);
+        self.adjacent[to].push(id + 1);
+        id
+    }
+
+    fn residual(&self, arc: usize) -> i128 {
+        self.arcs[arc].capacity - self.arcs[arc].flow
+    }
+
+    /// Edmonds-Karp: augment along shortest residual paths until none is
+    /// left
+    fn max_flow(&mut self, source: usize, sink: usize) -> i128 {
+        let mut total: i128 = 0;
+        loop {
+            let mut via: Vec<Option<usize>> = vec![None; self.adjacent.len()];
+            let mut queue = VecDeque::from([source]);
+            while let Some(node) = queue.pop_front() {
+                for &arc in &self.adjacent[node] {
+                    let next = self.arcs[arc].to;
+                    if next != source && via[next].is_none() && self.residual(arc) > 0 {
+                        via[next] = Some(arc);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            if via[sink].is_none() {
+                return total;
+            }
+
+            let mut path = Vec::new();
+            let mut node = sink;
+            while let Some(arc) = via[node] {
+                path.push(arc);
+                node = self.arcs[arc ^ 1].to;
+            }
+            let push = path.iter().map(|&a| self.residual(a)).min().unwrap_or(0);
+            for arc in path {
+                self.arcs[arc].flow += push;
+                self.arcs[arc ^ 1].flow -= push;
+            }
+            total = total.saturating_add(push);
+        }
+    }
+
+    /// Nodes reachable from `source` in the residual network
+    fn reachable(&self, source: usize) -> Vec<bool> {
+        let mut seen = vec![false; self.adjacent.len()];
+        seen[source] = true;
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            for &arc in &self.adjacent[node] {
+                let next = self.arcs[arc].to;
+                if !seen[next] && self.residual(arc) > 0 {
+                    seen[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Whether `arc` leads from the source side to the sink side of the cut
+    fn crosses(&self, arc: usize, reachable: &[bool]) -> bool {
+        let from = self.arcs[arc ^ 1].to;
+        reachable[from] && !reachable[self.arcs[arc].to]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str, amount: u64, timestamp: &str) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: Some(timestamp.parse().unwrap()),
+            slot: None,
+            note: None,
+        }
+    }
+
+    fn cut(flow: &MaxFlow) -> Vec<(String, u64)> {
+        flow.min_cut
+            .iter()
+            .map(|e| (format!("{} -> {}", e.from, e.to), e.capacity.raw as u64))
+            .collect()
+    }
+
+    #[test]
+    fn test_max_flow_and_min_cut() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 6, "2024-01-01"));
+        graph.add_transfer(transfer("A", "B", 4, "2024-01-01"));
+        graph.add_transfer(transfer("A", "C", 5, "2024-01-01"));
+        graph.add_transfer(transfer("B", "D", 7, "2024-01-01"));
+        graph.add_transfer(transfer("C", "D", 20, "2024-01-01"));
+        graph.add_transfer(transfer("B", "C", 2, "2024-01-01"));
+        graph.add_transfer(transfer("D", "E", 3, "2024-01-01"));
+
+        let flow = graph.max_flow("A", "D").unwrap();
+        assert_eq!(flow.value, TokenAmount::from(14));
+        assert_eq!(
+            cut(&flow),
+            [
+                ("A -> C".to_string(), 5),
+                ("B -> C".to_string(), 2),
+                ("B -> D".to_string(), 7)
+            ]
+        );
+        assert_eq!(flow.chokepoints("A", "D"), ["C", "B"]);
+
+        let ab = &flow.edges[0];
+        assert_eq!((ab.from, ab.to), ("A", "B"));
+        assert_eq!(ab.capacity, TokenAmount::from(10));
+        assert_eq!(ab.flow, TokenAmount::from(9));
+        assert_eq!(flow.used_edges().count(), 5);
+
+        assert_eq!(graph.max_flow("D", "A").unwrap().value, TokenAmount::ZERO);
+        assert_eq!(graph.max_flow("A", "A").unwrap().value, TokenAmount::ZERO);
+    }
+
+    #[test]
+    fn test_max_flow_mixed_decimals() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(Transfer {
+            amount: "1.5".parse().unwrap(),
+            ..transfer("A", "B", 0, "2024-01-01")
+        });
+        graph.add_transfer(Transfer {
+            amount: "0.25".parse().unwrap(),
+            ..transfer("B", "C", 0, "2024-01-01")
+        });
+        graph.add_transfer(transfer("A", "C", 1, "2024-01-01"));
+
+        let flow = graph.max_flow("A", "C").unwrap();
+        assert_eq!(flow.value, "1.25".parse().unwrap());
+        assert_eq!(flow.value.to_string(), "1.25");
+    }
+
+    #[test]
+    fn test_temporal_max_flow() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 100, "2024-01-01 10:00"));
+        // Left B before the funds arrived
+        graph.add_transfer(transfer("B", "C", 60, "2024-01-01 09:00"));
+        graph.add_transfer(transfer("B", "C", 30, "2024-01-01 11:00"));
+        // Forwarded in the same second it arrived
+        graph.add_transfer(transfer("A", "D", 10, "2024-01-01 12:00"));
+        graph.add_transfer(transfer("D", "C", 10, "2024-01-01 12:00"));
+        graph.add_transfer(Transfer {
+            timestamp: None,
+            ..transfer("A", "C", 50, "2024-01-01")
+        });
+
+        assert_eq!(
+            graph.max_flow("A", "C").unwrap().value,
+            TokenAmount::from(150)
+        );
+
+        let flow = graph.temporal_max_flow("A", "C").unwrap();
+        assert_eq!(flow.value, TokenAmount::from(40));
+        let bc = flow.edges.iter().find(|e| e.from == "B").unwrap();
+        assert_eq!(
+            (bc.capacity, bc.flow),
+            (TokenAmount::from(90), TokenAmount::from(30))
+        );
+        assert_eq!(
+            cut(&flow),
+            [("A -> D".to_string(), 10), ("B -> C".to_string(), 30)]
+        );
+        assert!(flow.edges.iter().all(|e| e.from != "A" || e.to != "C"));
+        assert_eq!(flow.skipped.len(), 1);
+    }
+
+    #[test]
+    fn test_temporal_max_flow_by_slot() {
+        let slotted = |from, to, amount, slot| Transfer {
+            timestamp: None,
+            slot: Some(slot),
+            ..transfer(from, to, amount, "2024-01-01")
+        };
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(slotted("A", "B", 100, 10));
+        graph.add_transfer(slotted("B", "C", 60, 9));
+        graph.add_transfer(Transfer {
+            slot: Some(12),
+            ..transfer("B", "C", 30, "2024-01-01")
+        });
+        // Forwarded in the slot it arrived
+        graph.add_transfer(slotted("A", "D", 10, 15));
+        graph.add_transfer(slotted("D", "C", 10, 15));
+        // Nothing to place it by
+        graph.add_transfer(transfer("A", "C", 50, "2024-01-01"));
+
+        let flow = graph.temporal_max_flow("A", "C").unwrap();
+        assert_eq!(flow.value, TokenAmount::from(40));
+        assert_eq!(flow.skipped.len(), 1);
+        assert_eq!(flow.skipped[0].amount, TokenAmount::from(50));
+        assert!(graph.max_flow("A", "C").unwrap().skipped.is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+use std::io::{self, Write};
+
+use crate::output::render_to_string;
+use crate::xml::escape_xml;
+use crate::{total_amount, GraphNode, Timestamp, TransferGraph};
+
+/// GEXF attribute declarations: (id, title, type)
+const NODE_ATTRIBUTES: [(&str, &str, &str); 8] = [
+    ("address", "address", "string"),
+    ("label", "label", "string"),
+    ("in_degree", "in_degree", "integer"),
+    ("out_degree", "out_degree", "integer"),
+    ("total_in", "total_in", "double"),
+    ("total_out", "total_out", "double"),
+    ("net_flow", "net_flow", "double"),
+    ("conserved", "conserved", "boolean"),
+];
+
+const EDGE_ATTRIBUTES: [(&str, &str, &str); 5] = [
+    ("amount", "amount", "double"),
+    ("token_symbol", "token_symbol", "string"),
+    ("timestamp", "timestamp", "string"),
+    ("slot", "slot", "long"),
+    ("note", "note", "string"),
+];
+
+impl TransferGraph {
+    /// Export the graph as GEXF 1.3 with typed node and edge attributes
+    ///
+    /// When any transfer has a timestamp the graph is written in dynamic
+    /// mode: each edge starts at its transfer time and each node at its
+    /// earliest transfer, so Gephi's timeline can play the flow back.
+    pub fn render_gexf(&self) -> String {
+        render_to_string(|out| self.write_gexf(out))
+    }
+
+    /// Stream the GEXF document of `render_gexf` to `out`
+    ///
+    /// Wrap unbuffered writers such as files in a `BufWriter`.
+    pub fn write_gexf<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let nodes = self.sorted_nodes();
+        let dynamic = nodes
+            .iter()
+            .flat_map(|n| n.outgoing.iter())
+            .any(|t| t.timestamp.is_some());
+
+        out.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+        out.write_all(b"<gexf xmlns=\"http://gexf.net/1.3\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://gexf.net/1.3 http://gexf.net/1.3/gexf.xsd\" version=\"1.3\">\n")?;
+        writeln!(
+            out,
+            "  <meta>\n    <creator>trace-flow</creator>\n    <description>{}</description>\n  </meta>",
+            escape_xml(&self.render_config.title)
+        )?;
+        if dynamic {
+            out.write_all(
+                b"  <graph defaultedgetype=\"directed\" mode=\"dynamic\" timeformat=\"datetime\">\n",
+            )?;
+        } else {
+            out.write_all(b"  <graph defaultedgetype=\"directed\" mode=\"static\">\n")?;
+        }
+
+        write_attributes(&mut out, "node", &NODE_ATTRIBUTES)?;
+        write_attributes(&mut out, "edge", &EDGE_ATTRIBUTES)?;
+
+        out.write_all(b"    <nodes>\n")?;
+        for (node, balance) in nodes.iter().zip(self.balances()) {
+            write!(
+                out,
+                "      <node id=\"{}\" label=\"{}\"",
+                escape_xml(&node.address),
+                escape_xml(node.label.as_deref().unwrap_or(&node.address))
+            )?;
+            if let Some(start) = node_start(node) {
+                write!(out, " start=\"{}\"", start)?;
+            }
+            out.write_all(b">\n        <attvalues>\n")?;
+            write_attvalue(&mut out, "address", &node.address)?;
+            if let Some(label) = &node.label {
+                write_attvalue(&mut out, "label", label)?;
+            }
+            write_attvalue(&mut out, "in_degree", &node.incoming.len().to_string())?;
+            write_attvalue(&mut out, "out_degree", &node.outgoing.len().to_string())?;
+            if let Some(total_in) = total_amount(&node.incoming) {
+                write_attvalue(&mut out, "total_in", &total_in.to_string())?;
+            }
+            if let Some(total_out) = total_amount(&node.outgoing) {
+                write_attvalue(&mut out, "total_out", &total_out.to_string())?;
+            }
+            if let Some(net_flow) = balance.net_flow() {
+                write_attvalue(&mut out, "net_flow", &net_flow.to_string())?;
+                write_attvalue(&mut out, "conserved", &balance.is_conserved().to_string())?;
+            }
+            out.write_all(b"        </attvalues>\n      </node>\n")?;
+        }
+        out.write_all(b"    </nodes>\n")?;
+
+        out.write_all(b"    <edges>\n")?;
+        let transfers = nodes.iter().flat_map(|n| n.outgoing.iter());
+        for (idx, transfer) in transfers.enumerate() {
+            write!(
+                out,
+                "      <edge id=\"{}\" source=\"{}\" target=\"{}\" weight=\"{}\"",
+                idx,
+                escape_xml(&transfer.from),
+                escape_xml(&transfer.to),
+                transfer.amount
+            )?;
+            if let Some(start) = transfer.timestamp {
+                write!(out, " start=\"{}\"", start)?;
+            }
+            out.write_all(b">\n        <attvalues>\n")?;
+            write_attvalue(&mut out, "amount", &transfer.amount.to_string())?;
+            write_attvalue(&mut out, "token_symbol", &transfer.token_symbol)?;
+            if let Some(ts) = transfer.timestamp {
+                write_attvalue(&mut out, "timestamp", &ts.to_string())?;
+            }
+            if let Some(slot) = transfer.slot {
+                write_attvalue(&mut out, "slot", &slot.to_string())?;
+            }
+            if let Some(note) = &transfer.note {
+                write_attvalue(&mut out, "note", note)?;
+            }
+            out.write_all(b"        </attvalues>\n      </edge>\n")?;
+        }
+        out.write_all(b"    </edges>\n")?;
+
+        out.write_all(b"  </graph>\n</gexf>\n")
+    }
+}
+
+fn write_attributes<W: Write>(
+    out: &mut W,
+    class: &str,
+    attributes: &[(&str, &str, &str)],
+) -> io::Result<()> {
+    writeln!(out, "    <attributes class=\"{}\">", class)?;
+    for (id, title, kind) in attributes {
+        writeln!(
+            out,
+            "      <attribute id=\"{}\" title=\"{}\" type=\"{}\"/>",
+            id, title, kind
+        )?;
+    }
+    out.write_all(b"    </attributes>\n")
+}
+
+fn write_attvalue<W: Write>(out: &mut W, attribute: &str, value: &str) -> io::Result<()> {
+    writeln!(
+        out,
+        "          <attvalue for=\"{}\" value=\"{}\"/>",
+        attribute,
+        escape_xml(value)
+    )
+}
+
+/// Earliest timestamp among the transfers touching a node
+fn node_start(node: &GraphNode) -> Option<Timestamp> {
+    node.incoming
+        .iter()
+        .chain(node.outgoing.iter())
+        .filter_map(|t| t.timestamp)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TokenAmount, Transfer};
+
+    fn transfer(from: &str, to: &str, amount: u64, timestamp: Option<&str>) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: timestamp.map(|ts| ts.parse().unwrap()),
+            slot: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_render_gexf_dynamic() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 100, Some("2024-01-02 10:00:00")));
+        graph.add_transfer(transfer("B", "C", 40, Some("2024-01-01")));
+        graph.add_transfer(transfer("B", "C", 10, Some("Dec 26, 2024 10:51:22 UTC")));
+        graph.set_node_label("B", "Hub".to_string());
+
+        let xml = graph.render_gexf();
+        assert!(xml.contains("mode=\"dynamic\" timeformat=\"datetime\""));
+        assert!(xml.contains("<attribute id=\"total_out\" title=\"total_out\" type=\"double\"/>"));
+        assert!(xml.contains("<node id=\"B\" label=\"Hub\" start=\"2024-01-01T00:00:00Z\">"));
+        assert!(xml.contains("<node id=\"A\" label=\"A\" start=\"2024-01-02T10:00:00Z\">"));
+        assert!(xml.contains(
+            "<edge id=\"0\" source=\"A\" target=\"B\" weight=\"100\" start=\"2024-01-02T10:00:00Z\">"
+        ));
+        assert!(xml.contains(
+            "<edge id=\"2\" source=\"B\" target=\"C\" weight=\"10\" start=\"2024-12-26T10:51:22Z\">"
+        ));
+        assert!(xml.contains("<attvalue for=\"timestamp\" value=\"2024-12-26T10:51:22Z\"/>"));
+        assert!(xml.contains("<attvalue for=\"total_out\" value=\"50\"/>"));
+        assert!(xml.contains("<attvalue for=\"conserved\" value=\"false\"/>"));
+    }
+
+    #[test]
+    fn test_render_gexf_static() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 1, None));
+
+        let xml = graph.render_gexf();
+        assert!(xml.contains("<graph defaultedgetype=\"directed\" mode=\"static\">"));
+        assert!(!xml.contains("start="));
+        assert!(xml.ends_with("  </graph>\n</gexf>\n"));
+    }
+}
@@ -0,0 +1,153 @@
+use std::io::{self, Write};
+
+use crate::output::render_to_string;
+use crate::xml::escape_xml;
+use crate::{total_amount, TransferGraph};
+
+/// GraphML `<key>` declarations: (id, domain, name, type)
+const GRAPHML_KEYS: [(&str, &str, &str, &str); 13] = [
+    ("n_address", "node", "address", "string"),
+    ("n_label", "node", "label", "string"),
+    ("n_in_degree", "node", "in_degree", "int"),
+    ("n_out_degree", "node", "out_degree", "int"),
+    ("n_total_in", "node", "total_in", "double"),
+    ("n_total_out", "node", "total_out", "double"),
+    ("n_net_flow", "node", "net_flow", "double"),
+    ("n_conserved", "node", "conserved", "boolean"),
+    ("e_amount", "edge", "amount", "double"),
+    ("e_token_symbol", "edge", "token_symbol", "string"),
+    ("e_timestamp", "edge", "timestamp", "string"),
+    ("e_slot", "edge", "slot", "long"),
+    ("e_note", "edge", "note", "string"),
+];
+
+impl TransferGraph {
+    /// Export the graph as GraphML with typed node and edge attributes
+    ///
+    /// Nodes carry address, label, in/out degree, total in/out amount, net
+    /// flow and whether they sent no more than they received; every transfer
+    /// becomes an edge carrying amount, token symbol, ISO-8601 timestamp,
+    /// slot and note. Absent optional values are omitted.
+    pub fn render_graphml(&self) -> String {
+        render_to_string(|out| self.write_graphml(out))
+    }
+
+    /// Stream the GraphML document of `render_graphml` to `out`
+    ///
+    /// Wrap unbuffered writers such as files in a `BufWriter`.
+    pub fn write_graphml<W: Write>(&self, mut out: W) -> io::Result<()> {
+        out.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+        out.write_all(b"<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n")?;
+        for (id, domain, name, kind) in GRAPHML_KEYS {
+            writeln!(
+                out,
+                "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                id, domain, name, kind
+            )?;
+        }
+        out.write_all(b"  <graph id=\"transfers\" edgedefault=\"directed\">\n")?;
+
+        let nodes = self.sorted_nodes();
+        for (node, balance) in nodes.iter().zip(self.balances()) {
+            writeln!(out, "    <node id=\"{}\">", escape_xml(&node.address))?;
+            write_data(&mut out, "n_address", &node.address)?;
+            if let Some(label) = &node.label {
+                write_data(&mut out, "n_label", label)?;
+            }
+            write_data(&mut out, "n_in_degree", &node.incoming.len().to_string())?;
+            write_data(&mut out, "n_out_degree", &node.outgoing.len().to_string())?;
+            if let Some(total_in) = total_amount(&node.incoming) {
+                write_data(&mut out, "n_total_in", &total_in.to_string())?;
+            }
+            if let Some(total_out) = total_amount(&node.outgoing) {
+                write_data(&mut out, "n_total_out", &total_out.to_string())?;
+            }
+            if let Some(net_flow) = balance.net_flow() {
+                write_data(&mut out, "n_net_flow", &net_flow.to_string())?;
+                write_data(&mut out, "n_conserved", &balance.is_conserved().to_string())?;
+            }
+            out.write_all(b"    </node>\n")?;
+        }
+
+        let transfers = nodes.iter().flat_map(|n| n.outgoing.iter());
+        for (idx, transfer) in transfers.enumerate() {
+            writeln!(
+                out,
+                "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">",
+                idx,
+                escape_xml(&transfer.from),
+                escape_xml(&transfer.to)
+            )?;
+            write_data(&mut out, "e_amount", &transfer.amount.to_string())?;
+            write_data(&mut out, "e_token_symbol", &transfer.token_symbol)?;
+            if let Some(ts) = transfer.timestamp {
+                write_data(&mut out, "e_timestamp", &ts.to_string())?;
+            }
+            if let Some(slot) = transfer.slot {
+                write_data(&mut out, "e_slot", &slot.to_string())?;
+            }
+            if let Some(note) = &transfer.note {
+                write_data(&mut out, "e_note", note)?;
+            }
+            out.write_all(b"    </edge>\n")?;
+        }
+
+        out.write_all(b"  </graph>\n</graphml>\n")
+    }
+}
+
+fn write_data<W: Write>(out: &mut W, key: &str, value: &str) -> io::Result<()> {
+    writeln!(
+        out,
+        "      <data key=\"{}\">{}</data>",
+        key,
+        escape_xml(value)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TokenAmount, Transfer};
+
+    #[test]
+    fn test_render_graphml() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(Transfer {
+            from: "A".to_string(),
+            to: "B".to_string(),
+            amount: TokenAmount::new(10005, 1),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: Some("2024-01-01".parse().unwrap()),
+            slot: Some(311_245_117),
+            note: Some("Fees & <dust>".to_string()),
+        });
+        graph.add_transfer(Transfer {
+            from: "A".to_string(),
+            to: "B".to_string(),
+            amount: TokenAmount::from(2),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        });
+        graph.set_node_label("B", "Hub".to_string());
+
+        let xml = graph.render_graphml();
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<graphml "));
+        assert!(xml.contains(
+            "<key id=\"n_total_in\" for=\"node\" attr.name=\"total_in\" attr.type=\"double\"/>"
+        ));
+        assert!(xml.contains(
+            "    <node id=\"B\">\n      <data key=\"n_address\">B</data>\n      <data key=\"n_label\">Hub</data>\n      <data key=\"n_in_degree\">2</data>\n      <data key=\"n_out_degree\">0</data>\n      <data key=\"n_total_in\">1002.5</data>\n      <data key=\"n_total_out\">0</data>\n      <data key=\"n_net_flow\">1002.5</data>\n      <data key=\"n_conserved\">true</data>\n"
+        ));
+        assert!(xml.contains("<edge id=\"e0\" source=\"A\" target=\"B\">"));
+        assert!(xml.contains("<data key=\"n_net_flow\">-1002.5</data>"));
+        assert!(xml.contains("<data key=\"e_note\">Fees &amp; &lt;dust&gt;</data>"));
+        assert_eq!(xml.matches("<edge ").count(), 2);
+        assert_eq!(xml.matches("e_timestamp\">").count(), 1);
+        assert!(xml.contains("<data key=\"e_timestamp\">2024-01-01T00:00:00Z</data>"));
+        assert!(xml.contains("<data key=\"e_slot\">311245117</data>"));
+        assert!(xml.ends_with("  </graph>\n</graphml>\n"));
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+
+use crate::{Timestamp, TokenAmount, Transfer};
+
+/// Error produced when a line of delimited transfer data cannot be parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse tab-separated transfer records.
+///
+/// Each non-empty line that does not start with `#` is read as
+/// `from<TAB>to<TAB>amount<TAB>token_symbol[<TAB>timestamp[<TAB>note[<TAB>slot]]]`.
+/// Tabs are used rather than commas because timestamps such as
+/// `Dec 26, 2024 10:51:22 UTC` contain commas. Amounts must be positive and
+/// are parsed exactly (thousands separators are ignored) with as many
+/// decimals as they are written with; timestamps accept any format
+/// understood by [`Timestamp`].
+/// Empty optional fields are treated as absent.
+pub fn parse_transfers(input: &str) -> Result<Vec<Transfer>, ParseError> {
+    let mut transfers = Vec::new();
+
+    for (idx, raw) in input.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = raw.trim_end_matches('\r');
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+        if fields.len() < 4 {
+            return Err(ParseError {
+                line: line_no,
+                message: format!(
+                    "expected at least 4 tab-separated fields, found {}",
+                    fields.len()
+                ),
+            });
+        }
+        if fields.len() > 7 {
+            return Err(ParseError {
+                line: line_no,
+                message: format!(
+                    "expected at most 7 tab-separated fields, found {}",
+                    fields.len()
+                ),
+            });
+        }
+
+        for (name, value) in [
+            ("from", fields[0]),
+            ("to", fields[1]),
+            ("token_symbol", fields[3]),
+        ] {
+            if value.is_empty() {
+                return Err(ParseError {
+                    line: line_no,
+                    message: format!("`{}` must not be empty", name),
+                });
+            }
+        }
+
+        let amount = fields[2]
+            .replace(',', "")
+            .parse::<TokenAmount>()
+            .ok()
+            .filter(|amount| !amount.is_zero() && !amount.is_negative())
+            .ok_or_else(|| ParseError {
+                line: line_no,
+                message: format!("invalid amount `{}`", fields[2]),
+            })?;
+
+        let optional = |i: usize| fields.get(i).copied().filter(|s| !s.is_empty());
+
+        let timestamp = optional(4)
+            .map(|s| s.parse::<Timestamp>())
+            .transpose()
+            .map_err(|e| ParseError {
+                line: line_no,
+                message: e.to_string(),
+            })?;
+        let slot = optional(6)
+            .map(|s| s.parse::<u64>())
+            .transpose()
+            .map_err(|_| ParseError {
+                line: line_no,
+                message: format!("invalid slot `{}`", fields[6]),
+            })?;
+
+        transfers.push(Transfer {
+            from: fields[0].to_string(),
+            to: fields[1].to_string(),
+            amount,
+            token_symbol: fields[3].to_string(),
+            timestamp,
+            slot,
+            note: optional(5).map(str::to_string),
+        });
+    }
+
+    Ok(transfers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_transfers() {
+        let input = "# from\tto\tamount\tsymbol\n\
+                     A\tB\t1,000.5\tSVMAI\tDec 26, 2024 10:51:22 UTC\tInitial Distribution\n\
+                     \n\
+                     B\tC\t50\tSVMAI\t\tSplit\t311245117\n\
+                     C\tD\t25\tSVMAI\n";
+
+        let transfers = parse_transfers(input).unwrap();
+        assert_eq!(transfers.len(), 3);
+        assert_eq!(transfers[0].amount, TokenAmount::new(10005, 1));
+        assert_eq!(transfers[0].timestamp, Some(Timestamp(1_735_210_282)));
+        assert_eq!(transfers[0].note.as_deref(), Some("Initial Distribution"));
+        assert_eq!(transfers[1].timestamp, None);
+        assert_eq!(transfers[1].slot, Some(311_245_117));
+        assert_eq!(transfers[1].note.as_deref(), Some("Split"));
+        assert_eq!(transfers[2].to, "D");
+    }
+
+    #[test]
+    fn test_parse_transfers_errors() {
+        let err = parse_transfers("A\tB\t1\tX\nA\tB\n").unwrap_err();
+        assert_eq!(err.line, 2);
+
+        let err = parse_transfers("A\tB\tlots\tX\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: invalid amount `lots`");
+
+        let err = parse_transfers("A\tB\t-5\tX\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: invalid amount `-5`");
+
+        let err = parse_transfers("A\tB\t0.00\tX\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: invalid amount `0.00`");
+
+        let err = parse_transfers("A\tB\t1\tX\tsoon\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: invalid timestamp `soon`");
+
+        let err = parse_transfers("A\tB\t1\tX\t\t\t-5\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: invalid slot `-5`");
+
+        assert!(parse_transfers("\tB\t1\tX\n").is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! JSON document format for [`TransferGraph`].
+//!
+//! A graph is stored as a single object:
+//!
+//! ```json
+//! {
+//!   "version": 3,
+//!   "token_name": "SVMAI",
+//!   "token_mint": "Cpzvdx6pppc9TNArsGsqgShCsKC9NCCjA2gtzHvUpump",
+//!   "origin": "Origin123",
+//!   "target": "Target789",
+//!   "nodes": [{
+//!     "address": "Origin123", "label": "MINT",
+//!     "inflow": "0", "outflow": "1000.500000000", "net_flow": "-1000.500000000"
+//!   }],
+//!   "transfers": [{
+//!     "from": "Origin123", "to": "Target789", "amount": "1000.500000000",
+//!     "token_symbol": "SVMAI", "timestamp": "2024-01-01T00:00:00Z",
+//!     "slot": 311245117, "note": null
+//!   }],
+//!   "render_config": { "title": "TOKEN DISTRIBUTION TRACE" }
+//! }
+//! ```
+//!
+//! Amounts are exact decimal strings whose fractional digits give the mint
+//! decimals. Timestamps are written as ISO-8601 UTC strings and read from any
+//! format understood by [`Timestamp`](crate::Timestamp) or from Unix seconds.
+//! Version 1 documents, which stored amounts as JSON numbers, and version 2
+//! documents, which had free-form timestamps and no slots, are still accepted
+//! as long as their timestamps parse.
+//!
+//! Every transfer is listed exactly once; node `incoming`/`outgoing` lists are
+//! rebuilt on import. Node `inflow`, `outflow` and `net_flow` (see
+//! [`NodeBalance`](crate::NodeBalance), `null` on overflow) are written for
+//! consumers of the document and ignored on import. Nodes and transfers are
+//! written in address order so the output is stable. Missing optional fields
+//! and `render_config` keys fall back to their defaults.
+
+use std::io::Write;
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{GraphNode, RenderConfig, TokenAmount, Transfer, TransferGraph};
+
+/// Version of the JSON document format written by [`TransferGraph::to_json`]
+pub const JSON_SCHEMA_VERSION: u32 = 3;
+
+#[derive(Serialize)]
+struct NodeRecordRef<'a> {
+    address: &'a str,
+    label: &'a Option<String>,
+    inflow: Option<TokenAmount>,
+    outflow: Option<TokenAmount>,
+    net_flow: Option<TokenAmount>,
+}
+
+#[derive(Serialize)]
+struct GraphDocumentRef<'a> {
+    version: u32,
+    token_name: &'a Option<String>,
+    token_mint: &'a Option<String>,
+    origin: &'a Option<String>,
+    target: &'a Option<String>,
+    nodes: Vec<NodeRecordRef<'a>>,
+    transfers: Vec<&'a Transfer>,
+    render_config: &'a RenderConfig,
+}
+
+#[derive(Deserialize)]
+struct NodeRecord {
+    address: String,
+    label: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GraphDocument {
+    version: u32,
+    token_name: Option<String>,
+    token_mint: Option<String>,
+    origin: Option<String>,
+    target: Option<String>,
+    #[serde(default)]
+    nodes: Vec<NodeRecord>,
+    #[serde(default)]
+    transfers: Vec<Transfer>,
+    #[serde(default)]
+    render_config: RenderConfig,
+}
+
+impl Serialize for TransferGraph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let nodes = self.sorted_nodes();
+        let balances = self.balances();
+
+        GraphDocumentRef {
+            version: JSON_SCHEMA_VERSION,
+            token_name: &self.token_name,
+            token_mint: &self.token_mint,
+            origin: &self.origin,
+            target: &self.target,
+            nodes: nodes
+                .iter()
+                .zip(&balances)
+                .map(|(n, b)| NodeRecordRef {
+                    address: &n.address,
+                    label: &n.label,
+                    inflow: b.inflow,
+                    outflow: b.outflow,
+                    net_flow: b.net_flow(),
+                })
+                .collect(),
+            transfers: nodes.iter().flat_map(|n| n.outgoing.iter()).collect(),
+            render_config: &self.render_config,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TransferGraph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let doc = GraphDocument::deserialize(deserializer)?;
+        if doc.version == 0 || doc.version > JSON_SCHEMA_VERSION {
+            return Err(D::Error::custom(format!(
+                "unsupported trace graph schema version {} (expected at most {})",
+                doc.version, JSON_SCHEMA_VERSION
+            )));
+        }
+
+        let mut graph = TransferGraph::with_config(doc.render_config);
+        graph.token_name = doc.token_name;
+        graph.token_mint = doc.token_mint;
+        graph.origin = doc.origin;
+        graph.target = doc.target;
+
+        for transfer in doc.transfers {
+            // Same rule as `parse_transfers`: only positive amounts move funds
+            if transfer.amount.is_zero() || transfer.amount.is_negative() {
+                return Err(D::Error::custom(format!(
+                    "invalid amount `{}`",
+                    transfer.amount
+                )));
+            }
+            graph.add_transfer(transfer);
+        }
+        for record in doc.nodes {
+            graph
+                .nodes
+                .entry(record.address.clone())
+                .or_insert_with(|| GraphNode {
+                    address: record.address,
+                    label: None,
+                    incoming: Vec::new(),
+                    outgoing: Vec::new(),
+                })
+                .label = record.label;
+        }
+
+        Ok(graph)
+    }
+}
+
+impl TransferGraph {
+    /// Serialize the graph to a compact JSON document
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Serialize the graph to an indented JSON document
+    pub fn to_json_pretty(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Stream the compact JSON document of `to_json` to `out`
+    pub fn write_json<W: Write>(&self, out: W) -> serde_json::Result<()> {
+        serde_json::to_writer(out, self)
+    }
+
+    /// Stream the indented JSON document of `to_json_pretty` to `out`
+    pub fn write_json_pretty<W: Write>(&self, out: W) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(out, self)
+    }
+
+    /// Load a graph from a JSON document produced by [`TransferGraph::to_json`]
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TokenAmount;
+
+    fn sample_graph() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.token_name = Some("SVMAI".to_string());
+        graph.token_mint = Some("Cpzvdx6pppc9TNArsGsqgShCsKC9NCCjA2gtzHvUpump".to_string());
+        graph.origin = Some("Origin123".to_string());
+        graph.target = Some("Target789".to_string());
+        graph.render_config.title = "CUSTOM TRACE".to_string();
+        graph.render_config.tree.depth = Some(2);
+        graph.render_config.svg.show_legend = false;
+
+        graph.add_transfer(Transfer {
+            from: "Origin123".to_string(),
+            to: "Middle456".to_string(),
+            amount: TokenAmount::new(1_000_125_000_000, 9),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: Some("2024-01-01".parse().unwrap()),
+            slot: None,
+            note: None,
+        });
+        graph.add_transfer(Transfer {
+            from: "Middle456".to_string(),
+            to: "Target789".to_string(),
+            amount: TokenAmount::from(500),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: Some("Final transfer".to_string()),
+        });
+        graph.set_node_label("Middle456", "Hub".to_string());
+        graph
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let graph = sample_graph();
+        let json = graph.to_json_pretty().unwrap();
+        let restored = TransferGraph::from_json(&json).unwrap();
+
+        assert_eq!(restored.to_json_pretty().unwrap(), json);
+        assert_eq!(restored.render_ascii(), graph.render_ascii());
+        assert_eq!(restored.nodes.len(), 3);
+        assert_eq!(restored.nodes["Middle456"].label.as_deref(), Some("Hub"));
+        assert_eq!(
+            restored.nodes["Origin123"].outgoing[0].amount,
+            TokenAmount::new(1_000_125_000_000, 9)
+        );
+        assert!(json.contains("\"amount\": \"1000.125000000\""));
+        assert!(json.contains("\"timestamp\": \"2024-01-01T00:00:00Z\""));
+        assert!(json.contains("\"net_flow\": \"500.125000000\""));
+        assert_eq!(restored.render_config.title, "CUSTOM TRACE");
+        assert_eq!(restored.render_config.tree.depth, Some(2));
+        assert!(!restored.render_config.svg.show_legend);
+    }
+
+    #[test]
+    fn test_json_defaults_and_version() {
+        let graph = TransferGraph::from_json(
+            r#"{"version":1,"transfers":[{"from":"A","to":"B","amount":1,"token_symbol":"T"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.nodes["A"].outgoing[0].amount, TokenAmount::from(1));
+        assert_eq!(graph.origin, None);
+        assert_eq!(graph.render_config.title, RenderConfig::default().title);
+
+        let graph = TransferGraph::from_json(
+            r#"{"version":2,"transfers":[{"from":"A","to":"B","amount":"1","token_symbol":"T",
+                "timestamp":"Dec 26, 2024 10:51:22 UTC"},{"from":"B","to":"C","amount":"1",
+                "token_symbol":"T","timestamp":1735210282,"slot":5}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            graph.nodes["B"].incoming[0].timestamp,
+            graph.nodes["B"].outgoing[0].timestamp
+        );
+        assert_eq!(graph.nodes["B"].outgoing[0].slot, Some(5));
+        assert!(TransferGraph::from_json(
+            r#"{"version":2,"transfers":[{"from":"A","to":"B","amount":"1","token_symbol":"T",
+                "timestamp":"last tuesday"}]}"#
+        )
+        .is_err());
+
+        for amount in ["-5", "0"] {
+            let json = format!(
+                r#"{{"version":3,"transfers":[{{"from":"A","to":"B","amount":"{}",
+                    "token_symbol":"T"}}]}}"#,
+                amount
+            );
+            let err = TransferGraph::from_json(&json).unwrap_err();
+            assert!(err
+                .to_string()
+                .contains(&format!("invalid amount `{}`", amount)));
+        }
+
+        let err = TransferGraph::from_json(r#"{"version":99}"#).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unsupported trace graph schema version 99"));
+        assert!(TransferGraph::from_json(r#"{"transfers":[]}"#).is_err());
+    }
+}
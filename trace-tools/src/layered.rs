@@ -0,0 +1,948 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::io::{self, Write};
+
+use crate::renderer::Renderer;
+use crate::terminal::{char_width, clip, display_width, Style};
+use crate::{AggregatedEdge, AsciiRenderer, TransferGraph};
+
+/// How the ASCII renderer draws the graph itself
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum AsciiLayout {
+    /// Indented trees, one level per hop (see `TreeConfig::direction`)
+    #[default]
+    Tree,
+    /// Every address in a column by hop distance, connected by box-drawing
+    /// lines and fitted to `AsciiConfig::layout_width`
+    Layered,
+}
+
+/// Barycenter sweeps tried when ordering the columns
+const SWEEPS: usize = 8;
+
+/// A place in a column: an address, or a dummy carrying a longer edge
+/// across the column
+struct Slot<'a> {
+    address: Option<&'a str>,
+    layer: usize,
+}
+
+/// Part of an edge between adjacent columns; only the first part of each
+/// edge carries its label
+struct Segment {
+    from: usize,
+    to: usize,
+    /// Index of the edge in `TransferGraph::aggregated_edges`
+    edge: usize,
+    label: Option<String>,
+    /// Style of the label, when it shows a large amount
+    style: Option<Style>,
+}
+
+/// Column assignment and order, see [`AsciiRenderer::render_layered`]
+struct Layers<'a> {
+    slots: Vec<Slot<'a>>,
+    /// Slots of each column, top to bottom
+    layers: Vec<Vec<usize>>,
+    segments: Vec<Segment>,
+}
+
+impl Layers<'_> {
+    fn positions(&self) -> Vec<usize> {
+        let mut position = vec![0; self.slots.len()];
+        for layer in &self.layers {
+            for (i, &slot) in layer.iter().enumerate() {
+                position[slot] = i;
+            }
+        }
+        position
+    }
+
+    /// Pairs of segments that cross between adjacent columns
+    fn crossings(&self) -> usize {
+        let position = self.positions();
+        let mut gaps: Vec<Vec<(usize, usize)>> = vec![Vec::new(); self.layers.len()];
+        for segment in &self.segments {
+            gaps[self.slots[segment.from].layer]
+                .push((position[segment.from], position[segment.to]));
+        }
+        gaps.iter_mut()
+            .map(|ends| {
+                ends.sort_unstable();
+                inversions(ends)
+            })
+            .sum()
+    }
+
+    /// Drop `edges` from the drawing, along with the dummies carrying them
+    fn hide(&mut self, edges: &BTreeSet<usize>) {
+        let mut dropped = vec![false; self.slots.len()];
+        self.segments.retain(|segment| {
+            if !edges.contains(&segment.edge) {
+                return true;
+            }
+            for slot in [segment.from, segment.to] {
+                if self.slots[slot].address.is_none() {
+                    dropped[slot] = true;
+                }
+            }
+            false
+        });
+        for layer in &mut self.layers {
+            layer.retain(|&slot| !dropped[slot]);
+        }
+    }
+
+    /// Reorder the columns by the average position of each slot's
+    /// neighbours, alternately left to right and right to left, and keep
+    /// the order with the fewest crossings
+    fn reduce_crossings(&mut self) {
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); self.slots.len()];
+        let mut successors: Vec<Vec<usize>> = vec![Vec::new(); self.slots.len()];
+        for segment in &self.segments {
+            predecessors[segment.to].push(segment.from);
+            successors[segment.from].push(segment.to);
+        }
+
+        let mut best = self.layers.clone();
+        let mut fewest = self.crossings();
+        for sweep in 0..SWEEPS {
+            if fewest == 0 {
+                break;
+            }
+            let mut position = self.positions();
+            let (order, neighbours): (Vec<usize>, _) = if sweep % 2 == 0 {
+                ((1..self.layers.len()).collect(), &predecessors)
+            } else {
+                (
+                    (0..self.layers.len().saturating_sub(1)).rev().collect(),
+                    &successors,
+                )
+            };
+            for l in order {
+                let barycenter = |slot: usize| {
+                    let around = &neighbours[slot];
+                    if around.is_empty() {
+                        position[slot] as f64
+                    } else {
+                        around.iter().map(|&n| position[n] as f64).sum::<f64>()
+                            / around.len() as f64
+                    }
+                };
+                let mut keyed: Vec<(f64, usize, usize)> = self.layers[l]
+                    .iter()
+                    .map(|&slot| (barycenter(slot), position[slot], slot))
+                    .collect();
+                keyed.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+                self.layers[l] = keyed.into_iter().map(|(_, _, slot)| slot).collect();
+                for (i, &slot) in self.layers[l].iter().enumerate() {
+                    position[slot] = i;
+                }
+            }
+            let crossings = self.crossings();
+            if crossings < fewest {
+                fewest = crossings;
+                best = self.layers.clone();
+            }
+        }
+        self.layers = best;
+    }
+}
+
+/// Rows and connector positions of the whole drawing
+struct Geometry {
+    /// Row of each slot
+    row: Vec<usize>,
+    /// Rows used by each slot (one per connector on its busier side)
+    height: Vec<usize>,
+    /// Row each segment leaves its source and reaches its target on
+    ports: Vec<(usize, usize)>,
+    /// Track (counted from the left of the gap) of each segment that has
+    /// to change rows
+    track: Vec<Option<usize>>,
+    column_width: Vec<usize>,
+    /// Per gap: label width and number of tracks
+    gaps: Vec<(usize, usize)>,
+    /// Edges with a segment that found no track within the layout width
+    crowded: BTreeSet<usize>,
+}
+
+impl Geometry {
+    /// Width of the gap after column `l`
+    fn gap_width(&self, l: usize) -> usize {
+        let (label, tracks) = self.gaps[l];
+        label_region(label) + 2 * tracks + 7
+    }
+}
+
+/// Characters between the source stub and the first track
+fn label_region(label: usize) -> usize {
+    if label > 0 {
+        label + 4
+    } else {
+        2
+    }
+}
+
+impl TransferGraph {
+    /// Hop distance of every address from the origin (or from every address
+    /// without incoming transfers when no origin is set); addresses that are
+    /// not reached start their own breadth-first pass
+    pub(crate) fn hop_columns(&self) -> HashMap<&str, usize> {
+        let nodes = self.sorted_nodes();
+        let mut depth: HashMap<&str, usize> = HashMap::new();
+
+        let roots: Vec<&str> = match self.origin.as_deref() {
+            Some(origin) if self.nodes.contains_key(origin) => vec![origin],
+            _ => nodes
+                .iter()
+                .filter(|n| n.incoming.is_empty())
+                .map(|n| n.address.as_str())
+                .collect(),
+        };
+        let mut pending: Vec<&str> = roots;
+        pending.extend(nodes.iter().map(|n| n.address.as_str()));
+
+        for root in pending {
+            if depth.contains_key(root) {
+                continue;
+            }
+            depth.insert(root, 0);
+            let mut queue = VecDeque::from([root]);
+            while let Some(addr) = queue.pop_front() {
+                let d = depth[addr];
+                for transfer in &self.nodes[addr].outgoing {
+                    if !depth.contains_key(transfer.to.as_str()) {
+                        depth.insert(transfer.to.as_str(), d + 1);
+                        queue.push_back(transfer.to.as_str());
+                    }
+                }
+            }
+        }
+        depth
+    }
+}
+
+impl<W: Write> AsciiRenderer<W> {
+    /// Draw every address in columns by hop distance, with one connector
+    /// per aggregated edge
+    ///
+    /// Edges spanning several columns pass through the columns in between;
+    /// edges back to the same or an earlier column are listed below the
+    /// drawing. Columns are ordered to reduce crossings. When the drawing
+    /// is wider than `AsciiConfig::layout_width` it is split into bands of
+    /// columns, each starting with the last column of the previous band.
+    /// Connectors that change rows take a track each, so a gap only holds
+    /// as many as fit the width next to its two columns; the edges of the
+    /// rest are listed below the drawing too.
+    pub(crate) fn render_layered(&mut self, graph: &TransferGraph) -> io::Result<()> {
+        let layout_width = self.config().layout_width;
+        let text_limit = (layout_width / 4).max(12);
+        let mut layers = graph.layered(text_limit, self);
+        layers.reduce_crossings();
+
+        let texts: Vec<String> = layers
+            .slots
+            .iter()
+            .map(|slot| match slot.address {
+                Some(addr) => clip(&self.layer_text(graph, addr), text_limit),
+                None => String::new(),
+            })
+            .collect();
+        let styles: Vec<Option<Style>> = layers
+            .slots
+            .iter()
+            .map(|slot| slot.address.and_then(|addr| self.text_style(graph, addr)))
+            .collect();
+        let mut hidden = BTreeSet::new();
+        let geometry = loop {
+            let geometry = layout(&layers, &texts, layout_width);
+            if geometry.crowded.is_empty() {
+                break geometry;
+            }
+            layers.hide(&geometry.crowded);
+            hidden.extend(geometry.crowded);
+        };
+
+        let count = layers.layers.len();
+        let mut start = 0;
+        while start < count {
+            let mut end = start;
+            let mut width = geometry.column_width[start];
+            while end + 1 < count {
+                let extra = geometry.gap_width(end) + geometry.column_width[end + 1];
+                if width + extra > layout_width && end > start {
+                    break;
+                }
+                width += extra;
+                end += 1;
+            }
+            if start > 0 || end + 1 < count {
+                self.write(&format!(
+                    "Columns {}–{} of {}:\n\n",
+                    start + 1,
+                    end + 1,
+                    count
+                ))?;
+            }
+            let band = draw_band(&layers, &geometry, (&texts, &styles), start, end);
+            band.write_to(self)?;
+            if self.is_truncated() {
+                return Ok(());
+            }
+            if end + 1 >= count {
+                break;
+            }
+            self.write("\n")?;
+            start = end;
+        }
+
+        let columns = graph.hop_columns();
+        let edges = graph.aggregated_edges();
+        let back = edges
+            .iter()
+            .filter(|edge| columns[edge.to] <= columns[edge.from]);
+        let heading = "Edges back to the same or an earlier column:";
+        self.write_edge_list(graph, heading, "↺", back, text_limit)?;
+        let crowded = hidden.iter().map(|&e| &edges[e]);
+        let heading = "Edges left out of the drawing to fit the width:";
+        self.write_edge_list(graph, heading, "…", crowded, text_limit)
+    }
+
+    /// Edges left out of the drawing, one per line under `heading`
+    fn write_edge_list<'e>(
+        &mut self,
+        graph: &TransferGraph,
+        heading: &str,
+        marker: &str,
+        edges: impl Iterator<Item = &'e AggregatedEdge<'e>>,
+        text_limit: usize,
+    ) -> io::Result<()> {
+        let mut edges = edges.peekable();
+        if edges.peek().is_some() {
+            self.write(&format!("\n{}\n", heading))?;
+        }
+        for edge in edges {
+            if self.is_truncated() {
+                break;
+            }
+            self.write(&format!(
+                "  {} {} → {}  [{}]\n",
+                marker,
+                clip(&graph.layer_text_plain(edge.from), text_limit),
+                clip(&graph.layer_text_plain(edge.to), text_limit),
+                edge_label(graph, edge)
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Icon and label (or address) of a column entry
+    fn layer_text(&self, graph: &TransferGraph, addr: &str) -> String {
+        format!(
+            "{} {}",
+            self.icon(graph, addr),
+            graph.layer_text_plain(addr)
+        )
+    }
+
+    /// Colour of a column entry: the origin and target stand out, and
+    /// labelled addresses show as labels
+    fn text_style(&self, graph: &TransferGraph, addr: &str) -> Option<Style> {
+        let labelled = graph.nodes.get(addr).is_some_and(|n| n.label.is_some());
+        self.address_style(graph, addr)
+            .or(labelled.then_some(Style::Label))
+    }
+}
+
+impl TransferGraph {
+    /// Slots and segments with one column per hop distance, in address
+    /// order before crossings are reduced
+    fn layered<W: Write>(&self, label_limit: usize, ascii: &AsciiRenderer<W>) -> Layers<'_> {
+        let columns = self.hop_columns();
+        let mut slots: Vec<Slot> = Vec::new();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for node in self.sorted_nodes() {
+            index.insert(&node.address, slots.len());
+            slots.push(Slot {
+                address: Some(&node.address),
+                layer: columns[node.address.as_str()],
+            });
+        }
+
+        let mut segments: Vec<Segment> = Vec::new();
+        for (e, edge) in self.aggregated_edges().iter().enumerate() {
+            let (first, last) = (columns[edge.from], columns[edge.to]);
+            if last <= first {
+                continue;
+            }
+            let mut label = Some(clip(&edge_label(self, edge), label_limit));
+            let style = edge.total.and_then(|total| ascii.amount_style(&total));
+            let mut from = index[edge.from];
+            for layer in first + 1..last {
+                slots.push(Slot {
+                    address: None,
+                    layer,
+                });
+                segments.push(Segment {
+                    from,
+                    to: slots.len() - 1,
+                    edge: e,
+                    label: label.take(),
+                    style,
+                });
+                from = slots.len() - 1;
+            }
+            segments.push(Segment {
+                from,
+                to: index[edge.to],
+                edge: e,
+                label,
+                style,
+            });
+        }
+
+        let count = slots.iter().map(|s| s.layer + 1).max().unwrap_or(0);
+        let mut layers = vec![Vec::new(); count];
+        for (i, slot) in slots.iter().enumerate() {
+            layers[slot.layer].push(i);
+        }
+        Layers {
+            slots,
+            layers,
+            segments,
+        }
+    }
+
+    fn layer_text_plain(&self, addr: &str) -> String {
+        match self.nodes.get(addr).and_then(|n| n.label.as_ref()) {
+            Some(label) => label.clone(),
+            None => self.truncate_address(addr, self.render_config.address_truncate_length),
+        }
+    }
+}
+
+fn edge_label(graph: &TransferGraph, edge: &AggregatedEdge) -> String {
+    let total = edge
+        .total
+        .map_or_else(|| "overflow".to_string(), |t| graph.format_amount(&t));
+    let mut label = format!("{} {}", total, edge.token_symbol);
+    if edge.count() > 1 {
+        label.push_str(&format!(" ×{}", edge.count()));
+    }
+    label
+}
+
+/// Rows, ports, tracks and widths for the ordered layers, with as many
+/// tracks in each gap as fit `layout_width` next to its two columns
+fn layout(layers: &Layers, texts: &[String], layout_width: usize) -> Geometry {
+    let slots = layers.slots.len();
+    let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); slots];
+    let mut incoming: Vec<Vec<usize>> = vec![Vec::new(); slots];
+    for (i, segment) in layers.segments.iter().enumerate() {
+        outgoing[segment.from].push(i);
+        incoming[segment.to].push(i);
+    }
+
+    let height: Vec<usize> = (0..slots)
+        .map(|s| outgoing[s].len().max(incoming[s].len()).max(1))
+        .collect();
+    let mut row = vec![0; slots];
+    for layer in &layers.layers {
+        let mut y = 0;
+        for &slot in layer {
+            row[slot] = y;
+            y += height[slot] + 1;
+        }
+    }
+
+    // Connectors leave and arrive in the order of the rows at the other end
+    let mut ports = vec![(0, 0); layers.segments.len()];
+    for slot in 0..slots {
+        let mut out = outgoing[slot].clone();
+        out.sort_by_key(|&i| (row[layers.segments[i].to], i));
+        for (k, &i) in out.iter().enumerate() {
+            ports[i].0 = row[slot] + k;
+        }
+    }
+    for slot in 0..slots {
+        let mut arriving = incoming[slot].clone();
+        arriving.sort_by_key(|&i| ports[i].0);
+        for (k, &i) in arriving.iter().enumerate() {
+            ports[i].1 = row[slot] + k;
+        }
+    }
+
+    let column_width: Vec<usize> = layers
+        .layers
+        .iter()
+        .map(|layer| {
+            layer
+                .iter()
+                .map(|&s| display_width(&texts[s]))
+                .max()
+                .unwrap_or(0)
+                .max(1)
+        })
+        .collect();
+
+    let mut track = vec![None; layers.segments.len()];
+    let mut crowded = BTreeSet::new();
+    let mut gaps = vec![(0, 0); layers.layers.len().saturating_sub(1)];
+    let mut by_gap: Vec<Vec<usize>> = vec![Vec::new(); gaps.len()];
+    for (i, segment) in layers.segments.iter().enumerate() {
+        by_gap[layers.slots[segment.from].layer].push(i);
+    }
+    for (l, (gap, in_gap)) in gaps.iter_mut().zip(by_gap).enumerate() {
+        let label = in_gap
+            .iter()
+            .filter_map(|&i| layers.segments[i].label.as_ref())
+            .map(|label| display_width(label))
+            .max()
+            .unwrap_or(0);
+        let jogs: Vec<usize> = in_gap
+            .iter()
+            .copied()
+            .filter(|&i| ports[i].0 != ports[i].1)
+            .collect();
+        let fixed = column_width[l] + column_width[l + 1] + label_region(label) + 7;
+        let room = layout_width.saturating_sub(fixed) / 2;
+        for (k, i) in order_tracks(&jogs, &ports).into_iter().enumerate() {
+            if k < room {
+                track[i] = Some(k);
+            } else {
+                crowded.insert(layers.segments[i].edge);
+            }
+        }
+        *gap = (label, jogs.len().min(room));
+    }
+
+    Geometry {
+        row,
+        height,
+        ports,
+        track,
+        column_width,
+        gaps,
+        crowded,
+    }
+}
+
+/// Left-to-right order of the tracks in a gap
+///
+/// A segment whose source row is another's target row must turn off that
+/// row before the other joins it, so its track comes first; otherwise
+/// tracks follow the source rows. Segments swapping rows with each other
+/// cannot both be satisfied and share the row briefly.
+fn order_tracks(jogs: &[usize], ports: &[(usize, usize)]) -> Vec<usize> {
+    let mut sorted: Vec<usize> = jogs.to_vec();
+    sorted.sort_by_key(|&i| (ports[i].0, i));
+    // Segments leave and reach distinct rows, so each one waits for at most
+    // one other: the segment leaving the row it reaches
+    let leaving: HashMap<usize, usize> = sorted
+        .iter()
+        .enumerate()
+        .map(|(k, &i)| (ports[i].0, k))
+        .collect();
+    let mut waiting = vec![None; sorted.len()];
+    let mut ready = BTreeSet::new();
+    for (k, &i) in sorted.iter().enumerate() {
+        match leaving.get(&ports[i].1) {
+            Some(&first) if first != k => waiting[first] = Some(k),
+            _ => {
+                ready.insert(k);
+            }
+        }
+    }
+
+    let mut remaining: BTreeSet<usize> = (0..sorted.len()).collect();
+    let mut order = Vec::with_capacity(sorted.len());
+    while let Some(&first) = remaining.first() {
+        // Segments swapping rows wait for each other; the first goes anyway
+        let next = ready.pop_first().unwrap_or(first);
+        remaining.remove(&next);
+        order.push(sorted[next]);
+        if let Some(k) = waiting[next].filter(|k| remaining.contains(k)) {
+            ready.insert(k);
+        }
+    }
+    order
+}
+
+/// Pairs in `ends`, sorted by source position, whose target positions are
+/// the other way round, counted with a Fenwick tree over target positions
+fn inversions(ends: &[(usize, usize)]) -> usize {
+    let size = ends.iter().map(|&(_, to)| to + 1).max().unwrap_or(0);
+    let mut tree = vec![0; size + 1];
+    let mut inversions = 0;
+    for (seen, &(_, to)) in ends.iter().enumerate() {
+        // Earlier segments reaching `to` or a position above it
+        let mut above = 0;
+        let mut i = to + 1;
+        while i > 0 {
+            above += tree[i];
+            i &= i - 1;
+        }
+        inversions += seen - above;
+        let mut i = to + 1;
+        while i <= size {
+            tree[i] += 1;
+            i += i & i.wrapping_neg();
+        }
+    }
+    inversions
+}
+
+/// Connector directions of a box-drawing cell
+const UP: u8 = 1;
+const RIGHT: u8 = 2;
+const DOWN: u8 = 4;
+const LEFT: u8 = 8;
+
+#[derive(Clone)]
+enum Cell {
+    Lines(u8),
+    /// A character with any zero-width characters following it
+    Text(String, Option<Style>),
+    /// Second column of a wide character
+    Wide,
+}
+
+/// Character grid where crossing and joining lines merge into the right
+/// box-drawing character
+struct Canvas {
+    cells: Vec<Vec<Cell>>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Canvas {
+            cells: vec![vec![Cell::Lines(0); width]; height],
+        }
+    }
+
+    fn connect(&mut self, x: usize, y: usize, directions: u8) {
+        if let Some(Cell::Lines(bits)) = self.cells.get_mut(y).and_then(|row| row.get_mut(x)) {
+            *bits |= directions;
+        }
+    }
+
+    fn horizontal(&mut self, y: usize, x1: usize, x2: usize) {
+        let (left, right) = (x1.min(x2), x1.max(x2));
+        for x in left..=right {
+            let mut bits = 0;
+            if x > left {
+                bits |= LEFT;
+            }
+            if x < right {
+                bits |= RIGHT;
+            }
+            self.connect(x, y, bits);
+        }
+    }
+
+    fn vertical(&mut self, x: usize, y1: usize, y2: usize) {
+        let (top, bottom) = (y1.min(y2), y1.max(y2));
+        for y in top..=bottom {
+            let mut bits = 0;
+            if y > top {
+                bits |= UP;
+            }
+            if y < bottom {
+                bits |= DOWN;
+            }
+            self.connect(x, y, bits);
+        }
+    }
+
+    fn text(&mut self, x: usize, y: usize, text: &str, style: Option<Style>) {
+        let Some(row) = self.cells.get_mut(y) else {
+            return;
+        };
+        let mut column = x;
+        for c in text.chars() {
+            match char_width(c) {
+                0 => {
+                    if let Some(Cell::Text(previous, _)) =
+                        column.checked_sub(1).and_then(|before| row.get_mut(before))
+                    {
+                        previous.push(c);
+                    }
+                }
+                width => {
+                    if column + width > row.len() {
+                        break;
+                    }
+                    row[column] = Cell::Text(c.to_string(), style);
+                    if width == 2 {
+                        row[column + 1] = Cell::Wide;
+                    }
+                    column += width;
+                }
+            }
+        }
+    }
+
+    /// Write the rows through `ascii`, each styled run painted, until its
+    /// output limits are reached
+    fn write_to<W: Write>(&self, ascii: &mut AsciiRenderer<W>) -> io::Result<()> {
+        for row in &self.cells {
+            if ascii.is_truncated() {
+                break;
+            }
+            let mut output = String::new();
+            let used = row
+                .iter()
+                .rposition(|cell| !matches!(cell, Cell::Lines(0)))
+                .map_or(0, |last| last + 1);
+            let mut run = String::new();
+            let mut run_style = None;
+            for cell in &row[..used] {
+                let (text, style) = match cell {
+                    Cell::Text(text, style) => (text.clone(), *style),
+                    Cell::Lines(bits) => (box_char(*bits).to_string(), None),
+                    Cell::Wide => continue,
+                };
+                if style != run_style {
+                    output.push_str(&ascii.paint(&run, run_style));
+                    run.clear();
+                    run_style = style;
+                }
+                run.push_str(&text);
+            }
+            output.push_str(&ascii.paint(&run, run_style));
+            output.push('\n');
+            ascii.write(&output)?;
+        }
+        Ok(())
+    }
+}
+
+fn box_char(bits: u8) -> char {
+    match bits {
+        0 => ' ',
+        b if b == UP | DOWN || b == UP || b == DOWN => '│',
+        b if b == LEFT | RIGHT || b == LEFT || b == RIGHT => '─',
+        b if b == RIGHT | DOWN => '┌',
+        b if b == LEFT | DOWN => '┐',
+        b if b == UP | RIGHT => '└',
+        b if b == UP | LEFT => '┘',
+        b if b == UP | DOWN | RIGHT => '├',
+        b if b == UP | DOWN | LEFT => '┤',
+        b if b == LEFT | RIGHT | DOWN => '┬',
+        b if b == LEFT | RIGHT | UP => '┴',
+        _ => '┼',
+    }
+}
+
+/// Columns `start..=end` and the connectors between them
+fn draw_band(
+    layers: &Layers,
+    geometry: &Geometry,
+    (texts, styles): (&[String], &[Option<Style>]),
+    start: usize,
+    end: usize,
+) -> Canvas {
+    let mut x = vec![0; end + 1];
+    for l in start..end {
+        x[l + 1] = x[l] + geometry.column_width[l] + geometry.gap_width(l);
+    }
+    let width = x[end] + geometry.column_width[end];
+    let height = layers.layers[start..=end]
+        .iter()
+        .flatten()
+        .map(|&s| geometry.row[s] + geometry.height[s])
+        .max()
+        .unwrap_or(0);
+    let mut canvas = Canvas::new(width, height);
+
+    for &slot in layers.layers[start..=end].iter().flatten() {
+        let (l, y) = (layers.slots[slot].layer, geometry.row[slot]);
+        if layers.slots[slot].address.is_some() {
+            canvas.text(x[l], y, &texts[slot], styles[slot]);
+        } else {
+            // Dummies carry their edge straight across the column
+            canvas.horizontal(y, x[l], x[l] + geometry.column_width[l] - 1);
+        }
+    }
+
+    for (i, segment) in layers.segments.iter().enumerate() {
+        let l = layers.slots[segment.from].layer;
+        if l < start || l >= end {
+            continue;
+        }
+        let gap_x = x[l] + geometry.column_width[l];
+        let stub = gap_x + 2;
+        let (label, tracks) = geometry.gaps[l];
+        let first_track = stub + 1 + label_region(label);
+        let arrival = first_track + 2 * tracks;
+        let arrow = arrival + 2;
+        let (from_row, to_row) = (geometry.row[segment.from], geometry.row[segment.to]);
+        let (leave, reach) = geometry.ports[i];
+
+        // Out of the source and down to this segment's row
+        let source_end = match layers.slots[segment.from].address {
+            Some(_) => x[l] + display_width(&texts[segment.from]) + 1,
+            None => x[l] + geometry.column_width[l] - 1,
+        };
+        canvas.horizontal(from_row, source_end, stub);
+        canvas.vertical(stub, from_row, leave);
+
+        match geometry.track[i] {
+            Some(track) => {
+                let track_x = first_track + 2 * track;
+                canvas.horizontal(leave, stub, track_x);
+                canvas.vertical(track_x, leave, reach);
+                canvas.horizontal(reach, track_x, arrival);
+            }
+            None => canvas.horizontal(leave, stub, arrival),
+        }
+        if let Some(label) = &segment.label {
+            canvas.text(stub + 2, leave, &format!(" {} ", label), None);
+            canvas.text(stub + 3, leave, label, segment.style);
+        }
+
+        // Into the target
+        canvas.vertical(arrival, reach, to_row);
+        if layers.slots[segment.to].address.is_some() {
+            canvas.horizontal(to_row, arrival, arrow);
+            canvas.text(arrow, to_row, "→", None);
+        } else {
+            canvas.horizontal(to_row, arrival, x[l + 1]);
+        }
+    }
+
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsciiConfig, RenderConfig, TokenAmount, Transfer};
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "T".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    fn layered(graph: &TransferGraph) -> String {
+        let mut ascii = AsciiRenderer::new(graph.render_config.ascii.clone(), Vec::new());
+        ascii.render_layered(graph).unwrap();
+        String::from_utf8(ascii.into_inner()).unwrap()
+    }
+
+    fn graph(transfers: &[(&str, &str, u64)]) -> TransferGraph {
+        let mut graph = TransferGraph::with_config(RenderConfig {
+            node_icon: "o".to_string(),
+            origin_icon: "O".to_string(),
+            ..RenderConfig::default()
+        });
+        for &(from, to, amount) in transfers {
+            graph.add_transfer(transfer(from, to, amount));
+        }
+        graph.origin = transfers.first().map(|t| t.0.to_string());
+        graph
+    }
+
+    #[test]
+    fn test_chain() {
+        let graph = graph(&[("A", "B", 10), ("B", "C", 5), ("B", "C", 2)]);
+        assert_eq!(
+            layered(&graph),
+            "O A ─── 10.00 T ───→ o B ─── 7.00 T ×2 ───→ o C\n"
+        );
+    }
+
+    #[test]
+    fn test_fan_out_and_in() {
+        let graph = graph(&[("A", "B", 1), ("A", "C", 2), ("B", "D", 3), ("C", "D", 4)]);
+        let expected = "\
+O A ─┬─ 1.00 T ─────→ o B ─── 3.00 T ───┬─→ o D
+     └─ 2.00 T ─┐                     ┌─┘
+                └───→ o C ─── 4.00 T ─┘
+";
+        assert_eq!(layered(&graph), expected);
+    }
+
+    #[test]
+    fn test_crossings_are_reduced() {
+        // Sorted by address, X's and Y's targets would cross
+        let graph = graph(&[("R", "X", 1), ("R", "Y", 1), ("X", "Q", 1), ("Y", "P", 1)]);
+        let ascii = AsciiRenderer::new(AsciiConfig::default(), Vec::new());
+        let mut layers = graph.layered(20, &ascii);
+        assert_eq!(layers.crossings(), 1);
+        layers.reduce_crossings();
+        assert_eq!(layers.crossings(), 0);
+    }
+
+    #[test]
+    fn test_long_and_back_edges() {
+        // X is not reached from the origin and starts its own column 0
+        let graph = graph(&[("A", "B", 1), ("B", "C", 1), ("X", "C", 9), ("C", "A", 1)]);
+        let expected = "\
+O A ─── 1.00 T ───→ o B ─── 1.00 T ───┬─→ o C
+                                    ┌─┘
+o X ─── 9.00 T ─────────────────────┘
+
+Edges back to the same or an earlier column:
+  ↺ C → A  [1.00 T]
+";
+        assert_eq!(layered(&graph), expected);
+    }
+
+    #[test]
+    fn test_bands_fit_width() {
+        let hops: Vec<(String, String)> = (0..12)
+            .map(|i| (format!("N{:02}", i), format!("N{:02}", i + 1)))
+            .collect();
+        let transfers: Vec<(&str, &str, u64)> = hops
+            .iter()
+            .map(|(a, b)| (a.as_str(), b.as_str(), 1))
+            .collect();
+        let mut graph = graph(&transfers);
+        graph.render_config.ascii.layout_width = 60;
+        let output = layered(&graph);
+        assert!(output.starts_with("Columns 1–"));
+        assert!(output.contains(" of 13:"));
+        for line in output.lines() {
+            assert!(line.chars().count() <= 60, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_fan_in_fits_width() {
+        let sources: Vec<String> = (0..50).map(|i| format!("S{:02}", i)).collect();
+        let mut transfers = vec![("A", "T", 1)];
+        transfers.extend(sources.iter().map(|s| (s.as_str(), "T", 1)));
+        let output = layered(&graph(&transfers));
+        for line in output.lines() {
+            assert!(line.chars().count() <= 120, "{}", line);
+        }
+        let (drawing, left_out) = output
+            .split_once("Edges left out of the drawing to fit the width:\n")
+            .unwrap();
+        assert!(left_out.contains("  … S49 → T  [1.00 T]\n"));
+        for source in &sources {
+            let drawn = drawing.contains(&format!("o {} ─", source));
+            assert_ne!(drawn, left_out.contains(&format!(" {} → T ", source)));
+        }
+
+        let sources: Vec<String> = (0..1000).map(|i| format!("S{:03}", i)).collect();
+        let transfers: Vec<(&str, &str, u64)> =
+            sources.iter().map(|s| (s.as_str(), "T", 1)).collect();
+        let output = layered(&graph(&transfers));
+        assert!(output.lines().all(|line| line.chars().count() <= 120));
+        assert!(output.len() < 100_000);
+    }
+}
@@ -0,0 +1,1222 @@
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::Duration;
+
+use trace_flow::{
+    parse_transfers, terminal_width, AsciiLayout, Charset, ClusterMethod, ColorMode, PathLimit,
+    TaintConfig, TaintPolicy, TemporalConfig, Timestamp, TimestampFormat, TokenAmount,
+    TraceDirection, Transfer, TransferGraph,
+};
+
+const USAGE: &str = "\
+Usage: trace-flow [OPTIONS] [FILE]
+
+Read tab-separated transfers from FILE (or stdin when FILE is omitted or `-`)
+and render the resulting transfer graph.
+
+Each input line is:
+  from<TAB>to<TAB>amount<TAB>symbol[<TAB>timestamp[<TAB>note[<TAB>slot]]]
+Blank lines and lines starting with `#` are ignored. When built with the
+`serde` feature, a JSON graph document can be read instead (see --input-format).
+
+Options:
+  -o, --origin <ADDR>       Origin address the trace starts from
+  -t, --target <ADDR>       Target address the trace is looking for
+      --token <NAME>        Token name shown in the header
+      --mint <ADDR>         Token mint address shown in the header
+  -l, --label <ADDR=LABEL>  Label a node (repeatable)
+  -f, --format <FORMAT>     Output format: ascii (default), paths, flow, dot,
+                            mermaid, svg, graphml, gexf, json, sankey
+  -i, --input-format <FMT>  Input format: tsv, json (default: json for *.json
+                            files, tsv otherwise)
+      --title <TEXT>        Header title
+      --decimals <N>        Mint decimals; amounts are rescaled exactly to N
+                            decimals (error if an amount has more)
+      --truncate <N>        Characters kept on each side of truncated addresses
+      --since <TIME>        Only keep transfers at or after TIME
+      --until <TIME>        Only keep transfers before TIME
+      --time-format <FMT>   Timestamp display: human (default), iso, date, unix
+      --sort-by-time        List outgoing transfers in chronological order
+      --max-depth <N>       Longest path (in hops) searched for (default: 10)
+      --max-paths <N>       Stop after N paths (default: 100)
+      --max-steps <N>       Stop after examining N transfers (default: 100000)
+      --min-amount <AMT>    Ignore transfers below AMT when searching paths
+      --temporal            With --format paths, only list paths whose hops
+                            happen in chronological order, with hop latencies;
+                            with --format flow, only route funds forward in time
+      --max-dwell <DUR>     Temporal paths: longest wait at any address
+                            (e.g. 90s, 15m, 2h, 1d12h; implies --temporal)
+      --max-duration <DUR>  Temporal paths: longest first-to-last hop time
+                            (implies --temporal)
+      --top <K>             Only the K best paths: in the ascii paths summary
+                            (widest first) and with --format paths
+      --rank <RANK>         With --format paths and --top: widest (default,
+                            largest bottleneck amount) or shortest (fewest hops)
+      --taint <ADDR>        Treat ADDR as a tainted source and show each node's
+                            tainted share in the ascii output (repeatable)
+      --taint-policy <P>    How taint spreads: haircut (default), poison, fifo,
+                            lifo
+      --collapse-clusters <METHOD>
+                            Draw each cluster of related wallets as one node in
+                            drawn output: louvain or components
+      --direction <DIR>     Ascii tree to draw: forward (default, from the
+                            origin), backward (who funded the target) or both
+                            (with the addresses where they meet)
+      --trace-depth <N>     Hops the ascii trees walk from the origin or back
+                            from the target
+      --duplicate-shared <N>
+                            Draw the subtree of a node reached again N levels
+                            deep instead of a `see #3 above` reference
+      --layout <LAYOUT>     Ascii graph drawing: tree (default) or layered
+                            (columns by hop distance)
+      --width <N>           Columns the ascii output fits into: the layered
+                            layout, title box, separators and stats box
+                            (default: the terminal width when printing to one)
+      --color <WHEN>        Colour the origin, target, labels and large
+                            amounts: auto (default, unless NO_COLOR is set or
+                            stdout is not a terminal), always, never
+      --ascii               Draw with plain ASCII instead of box-drawing
+                            characters and emoji
+      --aggregate           Draw one edge per sender, receiver and token with
+                            the total of the transfers it merges
+      --no-header           Hide the title box
+      --no-paths            Hide the paths summary
+      --no-cycles           Hide the circular flows section
+      --max-cycle-length <N>
+                            Longest cycle (in addresses) listed as a circular
+                            flow (default: 6)
+      --no-stats            Hide the stats summary
+      --top-hubs <N>        Intermediaries listed in the stats summary, ranked
+                            by betweenness; slow on large graphs (default: 0)
+      --max-lines <N>       Stop the ascii output after N lines with a
+                            truncation notice
+      --max-bytes <N>       Stop the ascii output before it exceeds N bytes
+                            with a truncation notice
+  -h, --help                Print this help
+  -V, --version             Print version
+";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    Paths,
+    Flow,
+    Dot,
+    Mermaid,
+    Svg,
+    Graphml,
+    Gexf,
+    Json,
+    Sankey,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rank {
+    Widest,
+    Shortest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum InputFormat {
+    Tsv,
+    Json,
+}
+
+#[derive(Debug, Default)]
+struct Options {
+    input: Option<String>,
+    origin: Option<String>,
+    target: Option<String>,
+    token: Option<String>,
+    mint: Option<String>,
+    labels: Vec<(String, String)>,
+    format: Option<Format>,
+    input_format: Option<InputFormat>,
+    title: Option<String>,
+    decimals: Option<u8>,
+    truncate: Option<usize>,
+    since: Option<Timestamp>,
+    until: Option<Timestamp>,
+    time_format: Option<TimestampFormat>,
+    sort_by_time: bool,
+    max_depth: Option<usize>,
+    max_paths: Option<usize>,
+    max_steps: Option<usize>,
+    min_amount: Option<TokenAmount>,
+    temporal: Option<TemporalConfig>,
+    top: Option<usize>,
+    rank: Option<Rank>,
+    taint: Option<TaintConfig>,
+    collapse_clusters: Option<ClusterMethod>,
+    aggregate: bool,
+    direction: Option<TraceDirection>,
+    trace_depth: Option<usize>,
+    duplicate_shared: Option<usize>,
+    layout: Option<AsciiLayout>,
+    width: Option<usize>,
+    color: Option<ColorMode>,
+    ascii: bool,
+    no_header: bool,
+    no_paths: bool,
+    no_cycles: bool,
+    max_cycle_length: Option<usize>,
+    no_stats: bool,
+    top_hubs: Option<usize>,
+    max_lines: Option<usize>,
+    max_bytes: Option<usize>,
+}
+
+#[derive(Debug)]
+enum Command {
+    Run(Box<Options>),
+    Help,
+    Version,
+}
+
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut opts = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("`{}` requires a value", name))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-o" | "--origin" => opts.origin = Some(value(&arg)?),
+            "-t" | "--target" => opts.target = Some(value(&arg)?),
+            "--token" => opts.token = Some(value(&arg)?),
+            "--mint" => opts.mint = Some(value(&arg)?),
+            "-l" | "--label" => {
+                let raw = value(&arg)?;
+                let (addr, label) = raw
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid label `{}`, expected ADDR=LABEL", raw))?;
+                opts.labels.push((addr.to_string(), label.to_string()));
+            }
+            "-f" | "--format" => {
+                opts.format = Some(match value(&arg)?.as_str() {
+                    "ascii" => Format::Ascii,
+                    "paths" => Format::Paths,
+                    "flow" => Format::Flow,
+                    "dot" => Format::Dot,
+                    "mermaid" => Format::Mermaid,
+                    "svg" => Format::Svg,
+                    "graphml" => Format::Graphml,
+                    "gexf" => Format::Gexf,
+                    "json" => Format::Json,
+                    "sankey" => Format::Sankey,
+                    other => return Err(format!("unknown format `{}`", other)),
+                });
+            }
+            "-i" | "--input-format" => {
+                opts.input_format = Some(match value(&arg)?.as_str() {
+                    "tsv" => InputFormat::Tsv,
+                    "json" => InputFormat::Json,
+                    other => return Err(format!("unknown input format `{}`", other)),
+                });
+            }
+            "--title" => opts.title = Some(value(&arg)?),
+            "--decimals" => opts.decimals = Some(parse_value(&arg, &value(&arg)?)?),
+            "--truncate" => opts.truncate = Some(parse_value(&arg, &value(&arg)?)?),
+            "--since" | "--until" => {
+                let raw = value(&arg)?;
+                let ts = raw.parse::<Timestamp>().map_err(|e| e.to_string())?;
+                if arg == "--since" {
+                    opts.since = Some(ts);
+                } else {
+                    opts.until = Some(ts);
+                }
+            }
+            "--time-format" => {
+                opts.time_format = Some(match value(&arg)?.as_str() {
+                    "human" => TimestampFormat::Human,
+                    "iso" => TimestampFormat::Iso8601,
+                    "date" => TimestampFormat::Date,
+                    "unix" => TimestampFormat::Unix,
+                    other => return Err(format!("unknown time format `{}`", other)),
+                });
+            }
+            "--sort-by-time" => opts.sort_by_time = true,
+            "--max-depth" | "--max-paths" | "--max-steps" => {
+                let n = parse_value(&arg, &value(&arg)?)?;
+                match arg.as_str() {
+                    "--max-depth" => opts.max_depth = Some(n),
+                    "--max-paths" => opts.max_paths = Some(n),
+                    _ => opts.max_steps = Some(n),
+                }
+            }
+            "--min-amount" => {
+                let raw = value(&arg)?;
+                opts.min_amount = Some(
+                    raw.replace(',', "")
+                        .parse()
+                        .map_err(|_| format!("invalid amount `{}`", raw))?,
+                );
+            }
+            "--temporal" => {
+                opts.temporal.get_or_insert_with(TemporalConfig::default);
+            }
+            "--max-dwell" => {
+                let max = parse_duration(&value(&arg)?)?;
+                opts.temporal
+                    .get_or_insert_with(TemporalConfig::default)
+                    .max_dwell = Some(max);
+            }
+            "--max-duration" => {
+                let max = parse_duration(&value(&arg)?)?;
+                opts.temporal
+                    .get_or_insert_with(TemporalConfig::default)
+                    .max_duration = Some(max);
+            }
+            "--top" => opts.top = Some(parse_value(&arg, &value(&arg)?)?),
+            "--rank" => {
+                opts.rank = Some(match value(&arg)?.as_str() {
+                    "widest" => Rank::Widest,
+                    "shortest" => Rank::Shortest,
+                    other => return Err(format!("unknown rank `{}`", other)),
+                });
+            }
+            "--taint" => {
+                let seed = value(&arg)?;
+                opts.taint
+                    .get_or_insert_with(TaintConfig::default)
+                    .seeds
+                    .push(seed);
+            }
+            "--taint-policy" => {
+                let policy = match value(&arg)?.as_str() {
+                    "poison" => TaintPolicy::Poison,
+                    "haircut" => TaintPolicy::Haircut,
+                    "fifo" => TaintPolicy::Fifo,
+                    "lifo" => TaintPolicy::Lifo,
+                    other => return Err(format!("unknown taint policy `{}`", other)),
+                };
+                opts.taint.get_or_insert_with(TaintConfig::default).policy = policy;
+            }
+            "--collapse-clusters" => {
+                opts.collapse_clusters = Some(match value(&arg)?.as_str() {
+                    "louvain" => ClusterMethod::Louvain,
+                    "components" => ClusterMethod::Components,
+                    other => return Err(format!("unknown cluster method `{}`", other)),
+                });
+            }
+            "--duplicate-shared" => {
+                opts.duplicate_shared = Some(parse_value(&arg, &value(&arg)?)?);
+            }
+            "--layout" => {
+                opts.layout = Some(match value(&arg)?.as_str() {
+                    "tree" => AsciiLayout::Tree,
+                    "layered" => AsciiLayout::Layered,
+                    other => return Err(format!("unknown layout `{}`", other)),
+                });
+            }
+            "--width" => opts.width = Some(parse_value(&arg, &value(&arg)?)?),
+            "--color" => {
+                opts.color = Some(match value(&arg)?.as_str() {
+                    "auto" => ColorMode::Auto,
+                    "always" => ColorMode::Always,
+                    "never" => ColorMode::Never,
+                    other => return Err(format!("unknown color mode `{}`", other)),
+                });
+            }
+            "--ascii" => opts.ascii = true,
+            "--aggregate" => opts.aggregate = true,
+            "--direction" => {
+                opts.direction = Some(match value(&arg)?.as_str() {
+                    "forward" => TraceDirection::Forward,
+                    "backward" => TraceDirection::Backward,
+                    "both" => TraceDirection::Both,
+                    other => return Err(format!("unknown direction `{}`", other)),
+                });
+            }
+            "--trace-depth" => opts.trace_depth = Some(parse_value(&arg, &value(&arg)?)?),
+            "--no-header" => opts.no_header = true,
+            "--no-paths" => opts.no_paths = true,
+            "--no-cycles" => opts.no_cycles = true,
+            "--max-cycle-length" => {
+                opts.max_cycle_length = Some(parse_value(&arg, &value(&arg)?)?);
+            }
+            "--no-stats" => opts.no_stats = true,
+            "--top-hubs" => opts.top_hubs = Some(parse_value(&arg, &value(&arg)?)?),
+            "--max-lines" => opts.max_lines = Some(parse_value(&arg, &value(&arg)?)?),
+            "--max-bytes" => opts.max_bytes = Some(parse_value(&arg, &value(&arg)?)?),
+            "-" => opts.input = Some(arg),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ => {
+                if opts.input.is_some() {
+                    return Err(format!("unexpected argument `{}`", arg));
+                }
+                opts.input = Some(arg);
+            }
+        }
+    }
+
+    if let Some(temporal) = &opts.temporal {
+        match opts.format {
+            Some(Format::Paths) => {}
+            Some(Format::Flow)
+                if temporal.max_dwell.is_none() && temporal.max_duration.is_none() => {}
+            Some(Format::Flow) => {
+                return Err(
+                    "`--max-dwell` and `--max-duration` require `--format paths`".to_string(),
+                )
+            }
+            _ => return Err("temporal path options require `--format paths`".to_string()),
+        }
+    }
+    if opts.taint.as_ref().is_some_and(|t| t.seeds.is_empty()) {
+        return Err("`--taint-policy` requires at least one `--taint` address".to_string());
+    }
+    if opts.rank.is_some() && (opts.top.is_none() || opts.format != Some(Format::Paths)) {
+        return Err("`--rank` requires `--top` and `--format paths`".to_string());
+    }
+    if opts.temporal.is_some() && opts.top.is_some() {
+        return Err("`--top` cannot be combined with temporal path options".to_string());
+    }
+
+    Ok(Command::Run(Box::new(opts)))
+}
+
+/// Parse the value given to a numeric `flag`
+fn parse_value<T: FromStr>(flag: &str, raw: &str) -> Result<T, String> {
+    raw.parse()
+        .map_err(|_| format!("invalid number `{}` for `{}`", raw, flag))
+}
+
+/// Parse `90`, `90s`, `15m`, `2h`, `3d` or combinations such as `1h30m`;
+/// a bare number is seconds
+fn parse_duration(raw: &str) -> Result<Duration, String> {
+    let err = || format!("invalid duration `{}`", raw);
+    if let Ok(secs) = raw.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut total: u64 = 0;
+    let mut digits = String::new();
+    for c in raw.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86_400,
+            _ => return Err(err()),
+        };
+        let n: u64 = digits.parse().map_err(|_| err())?;
+        total = n
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(err)?;
+        digits.clear();
+    }
+    if !digits.is_empty() || raw.is_empty() {
+        return Err(err());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// Compact duration such as `1d 2h 5m` (zero units omitted)
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let parts: Vec<String> = [
+        (secs / 86_400, "d"),
+        (secs / 3600 % 24, "h"),
+        (secs / 60 % 60, "m"),
+        (secs % 60, "s"),
+    ]
+    .iter()
+    .filter(|(n, _)| *n > 0)
+    .map(|(n, unit)| format!("{}{}", n, unit))
+    .collect();
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+fn read_input(path: Option<&str>) -> Result<String, String> {
+    match path {
+        None | Some("-") => {
+            let mut buf = String::new();
+            io::stdin()
+                .read_to_string(&mut buf)
+                .map_err(|e| format!("failed to read stdin: {}", e))?;
+            Ok(buf)
+        }
+        Some(path) => {
+            fs::read_to_string(path).map_err(|e| format!("failed to read `{}`: {}", path, e))
+        }
+    }
+}
+
+fn load_graph(opts: &Options, input: &str) -> Result<TransferGraph, String> {
+    let input_format = opts.input_format.unwrap_or_else(|| match &opts.input {
+        Some(path) if path.ends_with(".json") => InputFormat::Json,
+        _ => InputFormat::Tsv,
+    });
+
+    match input_format {
+        InputFormat::Tsv => {
+            let mut graph = TransferGraph::new();
+            for transfer in parse_transfers(input).map_err(|e| e.to_string())? {
+                graph.add_transfer(transfer);
+            }
+            Ok(graph)
+        }
+        #[cfg(feature = "serde")]
+        InputFormat::Json => TransferGraph::from_json(input).map_err(|e| e.to_string()),
+        #[cfg(not(feature = "serde"))]
+        InputFormat::Json => Err("JSON input requires the `serde` feature".to_string()),
+    }
+}
+
+fn build_graph(opts: &Options, input: &str) -> Result<TransferGraph, String> {
+    let mut graph = load_graph(opts, input)?;
+
+    if let Some(decimals) = opts.decimals {
+        for node in graph.nodes.values_mut() {
+            for transfer in node.incoming.iter_mut().chain(node.outgoing.iter_mut()) {
+                transfer.amount = transfer.amount.rescale(decimals).ok_or_else(|| {
+                    format!(
+                        "amount {} has more than {} decimals",
+                        transfer.amount, decimals
+                    )
+                })?;
+            }
+        }
+    }
+
+    if opts.since.is_some() || opts.until.is_some() {
+        graph = graph.time_window(opts.since, opts.until);
+    }
+
+    let config = &mut graph.render_config;
+    if let Some(title) = &opts.title {
+        config.title = title.clone();
+    }
+    if let Some(n) = opts.truncate {
+        config.address_truncate_length = n;
+    }
+    if let Some(format) = opts.time_format {
+        config.timestamp_format = format;
+    }
+    config.tree.sort_by_time |= opts.sort_by_time;
+    config.paths_summary_top_k = opts.top.or(config.paths_summary_top_k);
+    if opts.taint.is_some() {
+        config.taint = opts.taint.clone();
+    }
+    config.collapse_clusters = opts.collapse_clusters.or(config.collapse_clusters);
+    config.aggregate_edges |= opts.aggregate;
+    config.tree.direction = opts.direction.unwrap_or(config.tree.direction);
+    config.tree.depth = opts.trace_depth.or(config.tree.depth);
+    if let Some(levels) = opts.duplicate_shared {
+        config.tree.duplicate_shared_depth = levels;
+    }
+    config.ascii.layout = opts.layout.unwrap_or(config.ascii.layout);
+    if let Some(width) = opts.width {
+        config.ascii.layout_width = width;
+        config.ascii.max_width = Some(width);
+    }
+    if opts.ascii {
+        config.ascii.charset = Charset::Ascii;
+    }
+    let limits = &mut config.path_limits;
+    limits.max_depth = opts.max_depth.or(limits.max_depth);
+    limits.max_paths = opts.max_paths.or(limits.max_paths);
+    limits.max_steps = opts.max_steps.or(limits.max_steps);
+    limits.min_amount = opts.min_amount.or(limits.min_amount);
+    config.show_header &= !opts.no_header;
+    config.show_paths_summary &= !opts.no_paths;
+    config.show_cycles_summary &= !opts.no_cycles;
+    if let Some(n) = opts.max_cycle_length {
+        config.cycles.max_length = n;
+    }
+    config.show_stats_summary &= !opts.no_stats;
+    if let Some(n) = opts.top_hubs {
+        config.hubs.count = n;
+    }
+    let output_limits = &mut config.ascii.output_limits;
+    output_limits.max_lines = opts.max_lines.or(output_limits.max_lines);
+    output_limits.max_bytes = opts.max_bytes.or(output_limits.max_bytes);
+
+    if opts.origin.is_some() {
+        graph.origin = opts.origin.clone();
+    }
+    if opts.target.is_some() {
+        graph.target = opts.target.clone();
+    }
+    if opts.token.is_some() {
+        graph.token_name = opts.token.clone();
+    }
+    if opts.mint.is_some() {
+        graph.token_mint = opts.mint.clone();
+    }
+    for (addr, label) in &opts.labels {
+        graph.set_node_label(addr, label.clone());
+    }
+
+    Ok(graph)
+}
+
+/// Stream `format` to `out`
+fn write(graph: &TransferGraph, format: Format, mut out: impl Write) -> io::Result<()> {
+    match format {
+        Format::Ascii => graph.write_ascii(out),
+        Format::Paths => {
+            let (origin, target) = endpoints(graph, "paths")?;
+            let mut search = graph.iter_paths(origin, target, &graph.render_config.path_limits);
+            for path in search.by_ref() {
+                writeln!(out, "{}", path.addresses().join(" -> "))?;
+            }
+            warn_path_limit(search.limit_reached());
+            Ok(())
+        }
+        Format::Flow => write_flow(graph, false, out),
+        Format::Dot => graph.write_dot(out),
+        Format::Mermaid => graph.write_mermaid(out),
+        Format::Svg => graph.write_svg(out),
+        Format::Graphml => graph.write_graphml(out),
+        Format::Gexf => graph.write_gexf(out),
+        #[cfg(feature = "serde")]
+        Format::Json => {
+            graph.write_json_pretty(&mut out)?;
+            writeln!(out)
+        }
+        #[cfg(feature = "serde")]
+        Format::Sankey => {
+            graph.write_sankey_json(&mut out)?;
+            writeln!(out)
+        }
+        #[cfg(not(feature = "serde"))]
+        Format::Json | Format::Sankey => Err(invalid("JSON output requires the `serde` feature")),
+    }
+}
+
+/// An error in the request rather than in writing the output
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// The --origin and --target addresses that the `format` output needs
+fn endpoints<'a>(graph: &'a TransferGraph, format: &str) -> io::Result<(&'a str, &'a str)> {
+    match (&graph.origin, &graph.target) {
+        (Some(origin), Some(target)) => Ok((origin, target)),
+        _ => Err(invalid(&format!(
+            "the `{}` format requires --origin and --target",
+            format
+        ))),
+    }
+}
+
+/// Warn on stderr when a path search was cut short by one of its limits
+fn warn_path_limit(limit: Option<PathLimit>) {
+    let warning = match limit {
+        None => return,
+        Some(PathLimit::Depth) => "paths longer than --max-depth were skipped",
+        Some(PathLimit::Paths) => "stopped after --max-paths paths",
+        Some(PathLimit::Steps) => "stopped after --max-steps transfers",
+    };
+    eprintln!("trace-flow: warning: {}", warning);
+}
+
+/// One line per time-respecting path: the addresses, the first and last hop
+/// times (or slots), the wait at each intermediate address and the total
+/// duration, with `?` for waits that only slots order
+fn write_temporal_paths(
+    graph: &TransferGraph,
+    config: &TemporalConfig,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let (origin, target) = endpoints(graph, "paths")?;
+    let time_format = graph.render_config.timestamp_format;
+    let mut search =
+        graph.iter_temporal_paths(origin, target, config, &graph.render_config.path_limits);
+    let moment = |transfer: &Transfer| match (transfer.timestamp, transfer.slot) {
+        (Some(time), _) => time.format(time_format),
+        (None, Some(slot)) => format!("slot {}", slot),
+        (None, None) => "?".to_string(),
+    };
+    let duration = |d: Option<Duration>| d.map_or("?".to_string(), format_duration);
+    for path in search.by_ref() {
+        let hops = &path.path.hops;
+        write!(
+            out,
+            "{}  ({} → {}",
+            path.path.addresses().join(" -> "),
+            moment(hops[0]),
+            moment(hops[hops.len() - 1])
+        )?;
+        if !path.latencies.is_empty() {
+            let dwell: Vec<String> = path.latencies.iter().map(|d| duration(*d)).collect();
+            write!(out, "; dwell {}", dwell.join(", "))?;
+        }
+        writeln!(out, "; total {})", duration(path.duration()))?;
+    }
+    warn_path_limit(search.limit_reached());
+    let unplaced = graph
+        .transfers_by_time()
+        .iter()
+        .filter(|t| t.timestamp.is_none() && t.slot.is_none())
+        .count();
+    if unplaced > 0 {
+        eprintln!(
+            "trace-flow: warning: {} transfers without a timestamp or slot left out of paths",
+            unplaced
+        );
+    }
+    Ok(())
+}
+
+/// The max-flow value, the flow on each edge that carries some as
+/// `flow / capacity`, the min-cut edges and the wallets on the cut
+fn write_flow(graph: &TransferGraph, temporal: bool, mut out: impl Write) -> io::Result<()> {
+    let (origin, target) = endpoints(graph, "flow")?;
+    let flow = if temporal {
+        graph.temporal_max_flow(origin, target)
+    } else {
+        graph.max_flow(origin, target)
+    }
+    .ok_or_else(|| invalid("amounts overflow when brought to common decimals"))?;
+    for transfer in &flow.skipped {
+        eprintln!(
+            "trace-flow: warning: transfer {} -> {} of {} {} left out of the temporal flow",
+            transfer.from, transfer.to, transfer.amount, transfer.token_symbol
+        );
+    }
+
+    writeln!(out, "max flow: {}", flow.value)?;
+    for edge in flow.used_edges() {
+        writeln!(
+            out,
+            "{} -> {}  {} / {}",
+            edge.from, edge.to, edge.flow, edge.capacity
+        )?;
+    }
+    let cut: Vec<String> = flow
+        .min_cut
+        .iter()
+        .map(|e| format!("{} -> {}", e.from, e.to))
+        .collect();
+    writeln!(out, "min cut: {}", cut.join(", "))?;
+    writeln!(
+        out,
+        "chokepoints: {}",
+        flow.chokepoints(origin, target).join(", ")
+    )
+}
+
+/// The `k` best paths, one per line; widest paths also show their
+/// bottleneck amount
+fn write_ranked_paths(
+    graph: &TransferGraph,
+    rank: Rank,
+    k: usize,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let (origin, target) = endpoints(graph, "paths")?;
+    let paths = match rank {
+        Rank::Widest => graph.k_widest_paths(origin, target, k),
+        Rank::Shortest => graph.k_shortest_paths(origin, target, k),
+    };
+    for path in paths {
+        write!(out, "{}", path.addresses().join(" -> "))?;
+        if rank == Rank::Widest {
+            if let Some(narrowest) = path.hops.iter().min_by_key(|t| t.amount) {
+                write!(
+                    out,
+                    "  (bottleneck {} {})",
+                    narrowest.amount, narrowest.token_symbol
+                )?;
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn run(opts: Options) -> Result<(), String> {
+    let input = read_input(opts.input.as_deref())?;
+    let mut graph = build_graph(&opts, &input)?;
+
+    // Terminal defaults only apply when printing, not to graphs built in tests
+    let config = &mut graph.render_config;
+    config.ascii.color = opts.color.unwrap_or(ColorMode::Auto);
+    if opts.width.is_none() && io::stdout().is_terminal() {
+        if let Some(width) = terminal_width() {
+            config.ascii.layout_width = width;
+            config.ascii.max_width = Some(width);
+        }
+    }
+
+    let format = opts.format.unwrap_or(Format::Ascii);
+    if let Some(taint) = &graph.render_config.taint {
+        for transfer in graph.taint(taint).skipped {
+            eprintln!(
+                "trace-flow: warning: transfer {} -> {} of {} {} left out of taint tracking",
+                transfer.from, transfer.to, transfer.amount, transfer.token_symbol
+            );
+        }
+    }
+    // Streamed, so large outputs print as they are produced
+    let mut stdout = io::BufWriter::new(io::stdout().lock());
+    let written = match (format, &opts.temporal, opts.top) {
+        (Format::Paths, Some(temporal), _) => write_temporal_paths(&graph, temporal, &mut stdout),
+        (Format::Paths, None, Some(k)) => {
+            write_ranked_paths(&graph, opts.rank.unwrap_or(Rank::Widest), k, &mut stdout)
+        }
+        (Format::Flow, Some(_), _) => write_flow(&graph, true, &mut stdout),
+        _ => write(&graph, format, &mut stdout),
+    };
+    match written.and_then(|()| stdout.flush()) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn main() -> ExitCode {
+    let opts = match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Run(opts)) => *opts,
+        Ok(Command::Help) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("trace-flow {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("trace-flow: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(opts) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("trace-flow: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// What `write` streams, as a string
+    fn output(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> Result<String, String> {
+        let mut out = Vec::new();
+        write(&mut out).map_err(|e| e.to_string())?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn render(graph: &TransferGraph, format: Format) -> Result<String, String> {
+        output(|out| write(graph, format, out))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let cmd = parse_args(args(&[
+            "-o", "A", "--target", "C", "-l", "A=Mint", "-f", "paths", "in.tsv",
+        ]))
+        .unwrap();
+        let Command::Run(opts) = cmd else {
+            panic!("expected run command");
+        };
+        assert_eq!(opts.origin.as_deref(), Some("A"));
+        assert_eq!(opts.target.as_deref(), Some("C"));
+        assert_eq!(opts.labels, vec![("A".to_string(), "Mint".to_string())]);
+        assert_eq!(opts.format, Some(Format::Paths));
+        assert_eq!(opts.input.as_deref(), Some("in.tsv"));
+
+        assert!(parse_args(args(&["--format", "png"])).is_err());
+        assert!(parse_args(args(&["--label", "nolabel"])).is_err());
+        assert!(parse_args(args(&["--origin"])).is_err());
+        assert!(parse_args(args(&["--max-paths", "-1"])).is_err());
+        assert!(parse_args(args(&["--max-cycle-length", "x"])).is_err());
+        assert_eq!(
+            parse_args(args(&["--top-hubs", "many"])).err(),
+            Some("invalid number `many` for `--top-hubs`".to_string())
+        );
+    }
+
+    #[test]
+    fn test_top_hubs() {
+        let input = "A\tB\t10\tTOK\nB\tC\t9\tTOK\n";
+        let Command::Run(opts) = parse_args(args(&["-o", "A"])).unwrap() else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, input).unwrap();
+        assert!(!render(&graph, Format::Ascii).unwrap().contains("Hubs"));
+
+        let Command::Run(opts) = parse_args(args(&["-o", "A", "--top-hubs", "3"])).unwrap() else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, input).unwrap();
+        assert!(render(&graph, Format::Ascii).unwrap().contains("│   1. B "));
+    }
+
+    #[test]
+    fn test_collapse_clusters() {
+        let Command::Run(opts) =
+            parse_args(args(&["-o", "A", "--collapse-clusters", "components"])).unwrap()
+        else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, "A\tB\t10\tTOK\nC\tD\t5\tTOK\n").unwrap();
+        let dot = render(&graph, Format::Dot).unwrap();
+        assert!(dot.contains("Cluster 0 (2 wallets)"));
+        assert!(dot.contains("Cluster 1 (2 wallets)"));
+
+        assert!(parse_args(args(&["--collapse-clusters", "kmeans"])).is_err());
+    }
+
+    #[test]
+    fn test_backward_trace() {
+        let Command::Run(opts) = parse_args(args(&[
+            "-t",
+            "C",
+            "--direction",
+            "backward",
+            "--trace-depth",
+            "1",
+        ]))
+        .unwrap() else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, "A\tB\t10\tTOK\nB\tC\t5\tTOK\n").unwrap();
+        let ascii = render(&graph, Format::Ascii).unwrap();
+        assert!(ascii.contains("      ○ B [+1 beyond depth]\n"));
+        assert!(ascii.contains("\n🎯 TARGET C\n"));
+
+        assert!(parse_args(args(&["--direction", "sideways"])).is_err());
+        assert!(parse_args(args(&["--trace-depth", "deep"])).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_shared() {
+        let input = "A\tB\t1\tTOK\nA\tC\t1\tTOK\nB\tD\t1\tTOK\nC\tD\t1\tTOK\n";
+        let Command::Run(opts) = parse_args(args(&["-o", "A"])).unwrap() else {
+            panic!("expected run command");
+        };
+        let ascii = render(&build_graph(&opts, input).unwrap(), Format::Ascii).unwrap();
+        assert!(ascii.contains("────────→ D  ↺ see #3 above"));
+
+        let Command::Run(opts) = parse_args(args(&["-o", "A", "--duplicate-shared", "1"])).unwrap()
+        else {
+            panic!("expected run command");
+        };
+        let ascii = render(&build_graph(&opts, input).unwrap(), Format::Ascii).unwrap();
+        assert_eq!(ascii.matches("○ D #3").count(), 2);
+        assert!(!ascii.contains("↺"));
+    }
+
+    #[test]
+    fn test_layered_layout() {
+        let Command::Run(opts) =
+            parse_args(args(&["-o", "A", "--layout", "layered", "--width", "50"])).unwrap()
+        else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, "A\tB\t10\tTOK\nB\tC\t5\tTOK\n").unwrap();
+        let ascii = render(&graph, Format::Ascii).unwrap();
+        assert!(ascii.contains("Columns 1–2 of 3:"));
+        assert!(ascii.contains("🏦 ORIGIN A ─── 10.00 TOK ───→ ○ B\n"));
+        assert!(!ascii.contains("────────→ B"));
+
+        assert!(parse_args(args(&["--layout", "radial"])).is_err());
+        assert!(parse_args(args(&["--width", "wide"])).is_err());
+    }
+
+    #[test]
+    fn test_terminal_options() {
+        let Command::Run(opts) = parse_args(args(&[
+            "-o", "A", "--ascii", "--width", "50", "--color", "never",
+        ]))
+        .unwrap() else {
+            panic!("expected run command");
+        };
+        assert_eq!(opts.color, Some(ColorMode::Never));
+        let graph = build_graph(&opts, "A\tB\t10\tTOK\n").unwrap();
+        let ascii = render(&graph, Format::Ascii).unwrap();
+        assert!(ascii.is_ascii());
+        assert!(ascii.contains("ORIGIN A\n"));
+        assert!(ascii.contains(&format!("+{}+\n", "=".repeat(48))));
+
+        assert!(parse_args(args(&["--color", "sometimes"])).is_err());
+    }
+
+    #[test]
+    fn test_output_limits() {
+        let Command::Run(opts) = parse_args(args(&["-o", "A", "--max-lines", "8"])).unwrap() else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, "A\tB\t10\tTOK\nB\tC\t5\tTOK\n").unwrap();
+        let ascii = render(&graph, Format::Ascii).unwrap();
+        assert_eq!(ascii.lines().count(), 9);
+        assert!(ascii.ends_with("\n... output truncated after 8 lines\n"));
+
+        assert!(parse_args(args(&["--max-bytes", "lots"])).is_err());
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let Command::Run(opts) = parse_args(args(&["-o", "A", "--aggregate"])).unwrap() else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, "A\tB\t10\tTOK\nA\tB\t5\tTOK\n").unwrap();
+        let mermaid = render(&graph, Format::Mermaid).unwrap();
+        assert_eq!(mermaid.matches("-->").count(), 1);
+        assert!(mermaid.contains("15.00 TOK"));
+    }
+
+    #[test]
+    fn test_cycle_options() {
+        let input = "A\tB\t10\tTOK\nB\tA\t9\tTOK\n";
+        let Command::Run(opts) = parse_args(args(&["-o", "A"])).unwrap() else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, input).unwrap();
+        assert!(render(&graph, Format::Ascii)
+            .unwrap()
+            .contains("CYCLE #1: A → B → A"));
+
+        for flags in [
+            &["-o", "A", "--no-cycles"][..],
+            &["-o", "A", "--max-cycle-length", "1"],
+        ] {
+            let Command::Run(opts) = parse_args(args(flags)).unwrap() else {
+                panic!("expected run command");
+            };
+            let graph = build_graph(&opts, input).unwrap();
+            assert!(!render(&graph, Format::Ascii).unwrap().contains("CIRCULAR"));
+        }
+    }
+
+    #[test]
+    fn test_build_and_render_paths() {
+        let Command::Run(opts) = parse_args(args(&["-o", "A", "-t", "C", "-l", "B=Hub"])).unwrap()
+        else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, "A\tB\t10\tTOK\nB\tC\t5\tTOK\n").unwrap();
+        assert_eq!(graph.nodes["B"].label.as_deref(), Some("Hub"));
+        assert_eq!(render(&graph, Format::Paths).unwrap(), "A -> B -> C\n");
+        assert!(render(&graph, Format::Ascii).unwrap().contains("Hub"));
+
+        let Command::Run(opts) =
+            parse_args(args(&["-o", "A", "-t", "C", "--min-amount", "6"])).unwrap()
+        else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, "A\tB\t10\tTOK\nB\tC\t5\tTOK\n").unwrap();
+        assert_eq!(graph.render_config.path_limits.max_paths, Some(100));
+        assert_eq!(render(&graph, Format::Paths).unwrap(), "");
+    }
+
+    #[test]
+    fn test_ranked_paths() {
+        let Command::Run(opts) = parse_args(args(&["-o", "A", "-t", "D", "--top", "2"])).unwrap()
+        else {
+            panic!("expected run command");
+        };
+        let input = "A\tD\t1\tTOK\nA\tB\t10\tTOK\nB\tD\t8\tTOK\nA\tC\t3\tTOK\nC\tD\t3\tTOK\n";
+        let graph = build_graph(&opts, input).unwrap();
+        assert_eq!(graph.render_config.paths_summary_top_k, Some(2));
+        assert_eq!(
+            output(|out| write_ranked_paths(&graph, Rank::Widest, 2, out)).unwrap(),
+            "A -> B -> D  (bottleneck 8 TOK)\nA -> C -> D  (bottleneck 3 TOK)\n"
+        );
+        assert_eq!(
+            output(|out| write_ranked_paths(&graph, Rank::Shortest, 2, out)).unwrap(),
+            "A -> D\nA -> B -> D\n"
+        );
+
+        assert!(parse_args(args(&["--rank", "shortest", "-f", "paths"])).is_err());
+        assert!(parse_args(args(&["--top", "2", "--rank", "longest"])).is_err());
+        assert!(parse_args(args(&["-f", "paths", "--top", "1", "--temporal"])).is_err());
+    }
+
+    #[test]
+    fn test_flow() {
+        let Command::Run(opts) =
+            parse_args(args(&["-o", "A", "-t", "C", "-f", "flow", "--temporal"])).unwrap()
+        else {
+            panic!("expected run command");
+        };
+        assert!(opts.temporal.is_some());
+        let input = "A\tB\t10\tTOK\t2024-01-02\n\
+                     B\tC\t4\tTOK\t2024-01-01\n\
+                     B\tC\t3\tTOK\t2024-01-03\n\
+                     A\tC\t2\tTOK\t2024-01-04\n";
+        let graph = build_graph(&opts, input).unwrap();
+        assert_eq!(
+            output(|out| write_flow(&graph, false, out)).unwrap(),
+            "max flow: 9\n\
+             A -> B  7 / 10\n\
+             A -> C  2 / 2\n\
+             B -> C  7 / 7\n\
+             min cut: A -> C, B -> C\n\
+             chokepoints: B\n"
+        );
+        assert!(output(|out| write_flow(&graph, true, out))
+            .unwrap()
+            .starts_with("max flow: 5\n"));
+
+        assert!(parse_args(args(&["-f", "flow", "--max-dwell", "1h"])).is_err());
+        assert!(parse_args(args(&["-f", "dot", "--temporal"])).is_err());
+    }
+
+    #[test]
+    fn test_taint_options() {
+        let Command::Run(opts) =
+            parse_args(args(&["-o", "A", "--taint", "A", "--taint-policy", "fifo"])).unwrap()
+        else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, "A\tB\t10\tTOK\nC\tB\t30\tTOK\n").unwrap();
+        let taint = graph.render_config.taint.as_ref().unwrap();
+        assert_eq!(taint.seeds, ["A"]);
+        assert_eq!(taint.policy, TaintPolicy::Fifo);
+        assert!(render(&graph, Format::Ascii)
+            .unwrap()
+            .contains("B [taint 25.0%]"));
+
+        assert!(parse_args(args(&["--taint-policy", "lifo"])).is_err());
+        assert!(parse_args(args(&["--taint", "A", "--taint-policy", "mixed"])).is_err());
+    }
+
+    #[test]
+    fn test_decimals_rescaling() {
+        let Command::Run(opts) = parse_args(args(&["--decimals", "6"])).unwrap() else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, "A\tB\t1.5\tTOK\n").unwrap();
+        let amount = graph.nodes["B"].incoming[0].amount;
+        assert_eq!((amount.raw, amount.decimals), (1_500_000, 6));
+
+        let err = build_graph(&opts, "A\tB\t0.0000001\tTOK\n").unwrap_err();
+        assert_eq!(err, "amount 0.0000001 has more than 6 decimals");
+    }
+
+    #[test]
+    fn test_time_window_and_format() {
+        let Command::Run(opts) = parse_args(args(&[
+            "-o",
+            "A",
+            "--since",
+            "2024-01-02",
+            "--until",
+            "Jan 3, 2024",
+            "--time-format",
+            "iso",
+            "--no-stats",
+        ]))
+        .unwrap() else {
+            panic!("expected run command");
+        };
+        let input = "A\tB\t1\tTOK\t2024-01-01\n\
+                     A\tC\t2\tTOK\t2024-01-02 12:00:00\n\
+                     A\tD\t3\tTOK\n";
+        let graph = build_graph(&opts, input).unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert!(render(&graph, Format::Ascii)
+            .unwrap()
+            .contains("(2024-01-02T12:00:00Z)"));
+
+        assert!(parse_args(args(&["--since", "soon"])).is_err());
+        assert!(parse_args(args(&["--time-format", "julian"])).is_err());
+    }
+
+    #[test]
+    fn test_temporal_paths() {
+        let Command::Run(opts) = parse_args(args(&[
+            "-o",
+            "A",
+            "-t",
+            "C",
+            "-f",
+            "paths",
+            "--max-dwell",
+            "1d",
+            "--time-format",
+            "iso",
+        ]))
+        .unwrap() else {
+            panic!("expected run command");
+        };
+        let input = "A\tB\t10\tTOK\t2024-01-01 10:00:00\n\
+                     B\tC\t5\tTOK\t2024-01-01 09:00:00\n\
+                     B\tC\t4\tTOK\t2024-01-01 12:30:05\n\
+                     B\tC\t3\tTOK\t2024-01-03\n";
+        let graph = build_graph(&opts, input).unwrap();
+        let temporal = opts.temporal.as_ref().unwrap();
+        assert_eq!(
+            output(|out| write_temporal_paths(&graph, temporal, out)).unwrap(),
+            "A -> B -> C  (2024-01-01T10:00:00Z → 2024-01-01T12:30:05Z; dwell 2h 30m 5s; total 2h 30m 5s)\n"
+        );
+
+        let Command::Run(opts) = parse_args(args(&[
+            "-o",
+            "A",
+            "-t",
+            "C",
+            "-f",
+            "paths",
+            "--temporal",
+            "--max-paths",
+            "1",
+        ]))
+        .unwrap() else {
+            panic!("expected run command");
+        };
+        let graph = build_graph(&opts, input).unwrap();
+        let temporal = opts.temporal.as_ref().unwrap();
+        let out = output(|out| write_temporal_paths(&graph, temporal, out)).unwrap();
+        assert_eq!(out.lines().count(), 1);
+
+        assert!(parse_args(args(&["--temporal"])).is_err());
+        assert!(parse_args(args(&["-f", "paths", "--max-duration", "2x"])).is_err());
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("5m3").is_err());
+        assert_eq!(format_duration(Duration::from_secs(90_061)), "1d 1h 1m 1s");
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_input_with_overrides() {
+        let Command::Run(opts) =
+            parse_args(args(&["-i", "json", "-t", "C", "--no-stats"])).unwrap()
+        else {
+            panic!("expected run command");
+        };
+        let input = r#"{"version":1,"origin":"A","target":"B","transfers":[
+            {"from":"A","to":"B","amount":10,"token_symbol":"TOK"},
+            {"from":"B","to":"C","amount":5,"token_symbol":"TOK"}]}"#;
+        let graph = build_graph(&opts, input).unwrap();
+        assert_eq!(graph.origin.as_deref(), Some("A"));
+        assert_eq!(graph.target.as_deref(), Some("C"));
+        assert!(!graph.render_config.show_stats_summary);
+        assert_eq!(render(&graph, Format::Paths).unwrap(), "A -> B -> C\n");
+        assert!(render(&graph, Format::Json)
+            .unwrap()
+            .contains("\"version\": 3"));
+    }
+}
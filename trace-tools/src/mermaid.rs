@@ -0,0 +1,340 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::io::{self, Write};
+
+use crate::output::render_to_string;
+use crate::{GraphNode, TransferGraph};
+
+/// Configuration for Mermaid flowchart output, see
+/// [`RenderConfig::mermaid`](crate::RenderConfig::mermaid)
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct MermaidConfig {
+    /// Flowchart direction (`LR`, `TD`, ...)
+    pub direction: String,
+    /// Maximum number of nodes drawn before the rest is summarised
+    pub max_nodes: usize,
+    pub show_timestamps: bool,
+    pub show_notes: bool,
+    /// Wrap the diagram in a ```` ```mermaid ```` code fence
+    pub code_fence: bool,
+    pub origin_style: String,
+    pub target_style: String,
+}
+
+impl Default for MermaidConfig {
+    fn default() -> Self {
+        MermaidConfig {
+            direction: "LR".to_string(),
+            max_nodes: 50,
+            show_timestamps: false,
+            show_notes: false,
+            code_fence: false,
+            origin_style: "fill:#065f46,stroke:#34d399,stroke-width:2px,color:#fff".to_string(),
+            target_style: "fill:#7f1d1d,stroke:#f87171,stroke-width:2px,color:#fff".to_string(),
+        }
+    }
+}
+
+impl TransferGraph {
+    /// Render the graph as a Mermaid flowchart using `RenderConfig::mermaid`
+    pub fn render_mermaid(&self) -> String {
+        self.render_mermaid_with(&self.render_config.mermaid)
+    }
+
+    /// Render the graph as a Mermaid flowchart with `mermaid` instead of
+    /// `RenderConfig::mermaid`
+    pub fn render_mermaid_with(&self, mermaid: &MermaidConfig) -> String {
+        render_to_string(|out| self.write_mermaid_with(mermaid, out))
+    }
+
+    /// Stream the flowchart of `render_mermaid` to `out`
+    ///
+    /// Wrap unbuffered writers such as files in a `BufWriter`.
+    pub fn write_mermaid<W: Write>(&self, out: W) -> io::Result<()> {
+        self.write_mermaid_with(&self.render_config.mermaid, out)
+    }
+
+    /// `write_mermaid` with `mermaid` instead of `RenderConfig::mermaid`
+    pub fn write_mermaid_with<W: Write>(
+        &self,
+        mermaid: &MermaidConfig,
+        mut out: W,
+    ) -> io::Result<()> {
+        if let Some(view) = self.render_view() {
+            return view.write_mermaid_with(mermaid, out);
+        }
+        let cfg = &self.render_config;
+
+        if mermaid.code_fence {
+            out.write_all(b"```mermaid\n")?;
+        }
+        if cfg.show_header {
+            writeln!(out, "---\ntitle: \"{}\"\n---", escape_yaml(&cfg.title))?;
+        }
+        writeln!(out, "flowchart {}", mermaid.direction)?;
+
+        let order = self.mermaid_node_order();
+        let shown = &order[..order.len().min(mermaid.max_nodes)];
+        let ids: HashMap<&str, String> = shown
+            .iter()
+            .enumerate()
+            .map(|(idx, node)| (node.address.as_str(), format!("n{}", idx)))
+            .collect();
+
+        for node in shown {
+            writeln!(
+                out,
+                "    {}[\"{}\"]",
+                ids[node.address.as_str()],
+                self.mermaid_node_text(node)
+            )?;
+        }
+
+        let mut hidden_transfers = 0;
+        let mut overflow_sources = BTreeSet::new();
+        for node in self.sorted_nodes() {
+            for transfer in &node.outgoing {
+                match (
+                    ids.get(transfer.from.as_str()),
+                    ids.get(transfer.to.as_str()),
+                ) {
+                    (Some(from), Some(to)) => {
+                        let mut text = format!(
+                            "[{} {}]",
+                            self.format_amount(&transfer.amount),
+                            escape_mermaid(&transfer.token_symbol)
+                        );
+                        if mermaid.show_timestamps {
+                            if let Some(time) = self.format_time(transfer) {
+                                text.push_str(&format!("<br/>{}", escape_mermaid(&time)));
+                            }
+                        }
+                        if mermaid.show_notes {
+                            if let Some(note) = &transfer.note {
+                                text.push_str(&format!("<br/>{}", escape_mermaid(note)));
+                            }
+                        }
+                        writeln!(out, "    {} -->|\"{}\"| {}", from, text, to)?;
+                    }
+                    (Some(from), None) => {
+                        hidden_transfers += 1;
+                        overflow_sources.insert(from.clone());
+                    }
+                    _ => hidden_transfers += 1,
+                }
+            }
+        }
+
+        let hidden_nodes = order.len() - shown.len();
+        if hidden_nodes > 0 {
+            writeln!(
+                out,
+                "    overflow[\"… {} more nodes and {} transfers not shown\"]",
+                hidden_nodes, hidden_transfers
+            )?;
+            for from in &overflow_sources {
+                writeln!(out, "    {} -.-> overflow", from)?;
+            }
+        }
+
+        writeln!(out, "    classDef origin {}", mermaid.origin_style)?;
+        writeln!(out, "    classDef target {}", mermaid.target_style)?;
+        if let Some(id) = self.origin.as_deref().and_then(|a| ids.get(a)) {
+            writeln!(out, "    class {} origin", id)?;
+        }
+        if let Some(id) = self.target.as_deref().and_then(|a| ids.get(a)) {
+            writeln!(out, "    class {} target", id)?;
+        }
+        if hidden_nodes > 0 {
+            out.write_all(b"    classDef overflow stroke-dasharray:4 4\n")?;
+            out.write_all(b"    class overflow overflow\n")?;
+        }
+
+        if mermaid.code_fence {
+            out.write_all(b"```\n")?;
+        }
+        Ok(())
+    }
+
+    /// Nodes in drawing priority: origin, target, then breadth-first from the
+    /// origin, then everything else by address
+    fn mermaid_node_order(&self) -> Vec<&GraphNode> {
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut seen = HashSet::new();
+
+        for addr in [&self.origin, &self.target].into_iter().flatten() {
+            if let Some(node) = self.nodes.get(addr) {
+                if seen.insert(addr.as_str()) {
+                    order.push(node);
+                }
+            }
+        }
+
+        let mut expanded = HashSet::new();
+        let mut queue: VecDeque<&str> = self.origin.as_deref().into_iter().collect();
+        while let Some(addr) = queue.pop_front() {
+            if !expanded.insert(addr) {
+                continue;
+            }
+            let Some(node) = self.nodes.get(addr) else {
+                continue;
+            };
+            for transfer in &node.outgoing {
+                if seen.insert(transfer.to.as_str()) {
+                    order.push(&self.nodes[&transfer.to]);
+                }
+                queue.push_back(transfer.to.as_str());
+            }
+        }
+
+        for node in self.sorted_nodes() {
+            if seen.insert(node.address.as_str()) {
+                order.push(node);
+            }
+        }
+        order
+    }
+
+    fn mermaid_node_text(&self, node: &GraphNode) -> String {
+        let cfg = &self.render_config;
+        let mut lines = Vec::new();
+        if Some(node.address.as_str()) == self.origin.as_deref() {
+            lines.push(escape_mermaid(&cfg.origin_icon));
+        } else if Some(node.address.as_str()) == self.target.as_deref() {
+            lines.push(escape_mermaid(&cfg.target_icon));
+        }
+        if let Some(label) = &node.label {
+            lines.push(escape_mermaid(label));
+        }
+        lines.push(escape_mermaid(
+            &self.truncate_address(&node.address, cfg.address_truncate_length),
+        ));
+        lines.join("<br/>")
+    }
+}
+
+/// Escape text for a quoted Mermaid label using Mermaid's `#entity;` codes
+fn escape_mermaid(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("#quot;"),
+            '#' => escaped.push_str("#35;"),
+            '<' => escaped.push_str("#lt;"),
+            '>' => escaped.push_str("#gt;"),
+            '|' => escaped.push_str("#124;"),
+            ';' => escaped.push_str("#59;"),
+            '[' => escaped.push_str("#91;"),
+            ']' => escaped.push_str("#93;"),
+            '\n' => escaped.push_str("<br/>"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape `text` for a double-quoted YAML scalar such as the front matter
+/// title, where Mermaid's `#quot;` entities are not decoded
+fn escape_yaml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TokenAmount, Transfer};
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_render_mermaid() {
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("EQ3iykiT6Jg1ReuaaLc2bnxFXwxBkiXgZifYJxaULAEC".to_string());
+        graph.target = Some("Target789".to_string());
+        graph.add_transfer(transfer(
+            "EQ3iykiT6Jg1ReuaaLc2bnxFXwxBkiXgZifYJxaULAEC",
+            "Middle456",
+            1000,
+        ));
+        graph.add_transfer(transfer("Middle456", "Target789", 500));
+        graph.set_node_label("Middle456", "Hub <\"A\">".to_string());
+
+        let mermaid = graph.render_mermaid();
+        assert!(
+            mermaid.starts_with("---\ntitle: \"TOKEN DISTRIBUTION TRACE\"\n---\nflowchart LR\n")
+        );
+        assert!(mermaid.contains("    n0[\"🏦 ORIGIN<br/>EQ3iykiT6Jg1...ZifYJxaULAEC\"]\n"));
+        assert!(mermaid.contains("    n1[\"🎯 TARGET<br/>Target789\"]\n"));
+        assert!(mermaid.contains("    n2[\"Hub #lt;#quot;A#quot;#gt;<br/>Middle456\"]\n"));
+        assert!(mermaid.contains("    n0 -->|\"[1,000.00 SVMAI]\"| n2\n"));
+        assert!(mermaid.contains("    n2 -->|\"[500.00 SVMAI]\"| n1\n"));
+        assert!(mermaid.contains("    class n0 origin\n    class n1 target\n"));
+        assert!(!mermaid.contains("overflow"));
+    }
+
+    #[test]
+    fn test_render_mermaid_escapes_token_symbol() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(Transfer {
+            token_symbol: "A|\"B\"];C".to_string(),
+            ..transfer("A", "B", 1)
+        });
+
+        let mermaid = graph.render_mermaid();
+        assert!(mermaid.contains("    n0 -->|\"[1.00 A#124;#quot;B#quot;#93;#59;C]\"| n1\n"));
+    }
+
+    #[test]
+    fn test_render_mermaid_escapes_title() {
+        let mut graph = TransferGraph::new();
+        graph.render_config.title = "Trace \"A\" C:\\temp #1".to_string();
+        let mermaid = graph.render_mermaid();
+        assert!(mermaid.starts_with("---\ntitle: \"Trace \\\"A\\\" C:\\\\temp #1\"\n---\n"));
+    }
+
+    #[test]
+    fn test_render_mermaid_overflow() {
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("A".to_string());
+        for to in ["B", "C", "D", "E"] {
+            graph.add_transfer(transfer("A", to, 1));
+        }
+        graph.add_transfer(transfer("E", "F", 1));
+
+        let mermaid = graph.render_mermaid_with(&MermaidConfig {
+            max_nodes: 3,
+            code_fence: true,
+            ..MermaidConfig::default()
+        });
+        assert!(mermaid.starts_with("```mermaid\n"));
+        assert!(mermaid.ends_with("```\n"));
+        assert_eq!(mermaid.matches(" -->|").count(), 2);
+        assert!(mermaid.contains("overflow[\"… 3 more nodes and 3 transfers not shown\"]"));
+        assert!(mermaid.contains("    n0 -.-> overflow\n"));
+    }
+}
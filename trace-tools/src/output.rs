@@ -0,0 +1,192 @@
+use std::io::{self, Write};
+
+/// Caps on the output of a streaming renderer, see
+/// [`AsciiConfig::output_limits`](crate::AsciiConfig::output_limits)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct OutputLimits {
+    /// Lines written at most
+    pub max_lines: Option<usize>,
+    /// Bytes written at most, counting whole lines only
+    pub max_bytes: Option<usize>,
+}
+
+impl OutputLimits {
+    /// No limits
+    pub fn unbounded() -> Self {
+        OutputLimits::default()
+    }
+}
+
+/// Which of the [`OutputLimits`] cut the output short
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputLimit {
+    Lines,
+    Bytes,
+}
+
+/// A writer passing output on until one of its [`OutputLimits`] is reached
+/// and dropping everything after that
+///
+/// Output is passed on a line at a time, so the last line written is
+/// whole; an unfinished line is held back until it ends or the writer is
+/// flushed. Writes after the cut still report success; check
+/// [`limit_reached`](LimitedWriter::limit_reached) to stop producing
+/// output early and write a notice with
+/// [`write_notice`](LimitedWriter::write_notice).
+#[derive(Debug)]
+pub struct LimitedWriter<W> {
+    inner: W,
+    limits: OutputLimits,
+    lines: usize,
+    bytes: usize,
+    /// The line being written, not passed on yet
+    partial: Vec<u8>,
+    /// The last byte passed on ends a line (or nothing was written yet)
+    at_line_start: bool,
+    limit_reached: Option<OutputLimit>,
+}
+
+impl<W: Write> LimitedWriter<W> {
+    pub fn new(inner: W, limits: OutputLimits) -> Self {
+        LimitedWriter {
+            inner,
+            limits,
+            lines: 0,
+            bytes: 0,
+            partial: Vec::new(),
+            at_line_start: true,
+            limit_reached: None,
+        }
+    }
+
+    /// The limit output was cut at, if any
+    pub fn limit_reached(&self) -> Option<OutputLimit> {
+        self.limit_reached
+    }
+
+    /// Lines and bytes passed on so far
+    pub fn written(&self) -> (usize, usize) {
+        (self.lines, self.bytes)
+    }
+
+    /// Write `notice` on a line of its own, whatever the limits
+    pub fn write_notice(&mut self, notice: &str) -> io::Result<()> {
+        self.commit()?;
+        if !self.at_line_start {
+            self.inner.write_all(b"\n")?;
+        }
+        writeln!(self.inner, "{}", notice)?;
+        self.at_line_start = true;
+        Ok(())
+    }
+
+    /// The inner writer; an unfinished line not flushed yet is dropped
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Pass the held back line on if it stays within the limits, and
+    /// return whether it did
+    fn commit(&mut self) -> io::Result<bool> {
+        if self.partial.is_empty() || self.limit_reached.is_some() {
+            return Ok(self.limit_reached.is_none());
+        }
+        if self.limits.max_lines.is_some_and(|max| self.lines >= max) {
+            self.limit_reached = Some(OutputLimit::Lines);
+        } else if self
+            .limits
+            .max_bytes
+            .is_some_and(|max| self.bytes + self.partial.len() > max)
+        {
+            self.limit_reached = Some(OutputLimit::Bytes);
+        }
+        if self.limit_reached.is_some() {
+            self.partial = Vec::new();
+            return Ok(false);
+        }
+        self.inner.write_all(&self.partial)?;
+        self.bytes += self.partial.len();
+        self.at_line_start = self.partial.ends_with(b"\n");
+        if self.at_line_start {
+            self.lines += 1;
+        }
+        self.partial.clear();
+        Ok(true)
+    }
+}
+
+impl<W: Write> Write for LimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while let Some(end) = rest.iter().position(|&b| b == b'\n') {
+            let (line, tail) = rest.split_at(end + 1);
+            self.partial.extend_from_slice(line);
+            if !self.commit()? {
+                return Ok(buf.len());
+            }
+            rest = tail;
+        }
+        if self.limit_reached.is_none() {
+            self.partial.extend_from_slice(rest);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.commit()?;
+        self.inner.flush()
+    }
+}
+
+/// Collect what `write` streams into a `String`, for the `render_*`
+/// wrappers around the `write_*` methods
+pub(crate) fn render_to_string(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+    let mut output = Vec::new();
+    write(&mut output).expect("writing to memory cannot fail");
+    String::from_utf8(output).expect("renderers write UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_limit() {
+        let limits = OutputLimits {
+            max_lines: Some(2),
+            ..OutputLimits::unbounded()
+        };
+        let mut out = LimitedWriter::new(Vec::new(), limits);
+        out.write_all(b"one\ntw").unwrap();
+        assert_eq!(out.written(), (1, 4));
+        out.write_all(b"o\nthree\nfour\n").unwrap();
+        assert_eq!(out.limit_reached(), Some(OutputLimit::Lines));
+        assert_eq!(out.written(), (2, 8));
+        out.write_notice("[cut]").unwrap();
+        assert_eq!(out.into_inner(), b"one\ntwo\n[cut]\n");
+    }
+
+    #[test]
+    fn test_byte_limit() {
+        let limits = OutputLimits {
+            max_bytes: Some(10),
+            ..OutputLimits::unbounded()
+        };
+        let mut out = LimitedWriter::new(Vec::new(), limits);
+        out.write_all("αβ\nγδεζ\n".as_bytes()).unwrap();
+        assert_eq!(out.limit_reached(), Some(OutputLimit::Bytes));
+        out.write_notice("[cut]").unwrap();
+        assert_eq!(out.into_inner(), "αβ\n[cut]\n".as_bytes());
+
+        let mut out = LimitedWriter::new(Vec::new(), OutputLimits::unbounded());
+        out.write_all(b"partial").unwrap();
+        assert_eq!(out.written(), (0, 0));
+        out.write_notice("[note]").unwrap();
+        assert_eq!(out.into_inner(), b"partial\n[note]\n");
+    }
+}
@@ -0,0 +1,384 @@
+use std::collections::HashSet;
+
+use crate::temporal::placed_in_time;
+use crate::time::chronological;
+use crate::{TemporalConfig, TokenAmount, Transfer, TransferGraph};
+
+/// A route through the graph as the sequence of transfers taken
+///
+/// Consecutive hops are connected: each hop's `from` is the previous hop's
+/// `to`. Parallel transfers between the same pair of addresses are distinct
+/// hops, so two paths may visit the same addresses through different
+/// transfers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferPath<'a> {
+    pub hops: Vec<&'a Transfer>,
+}
+
+impl<'a> TransferPath<'a> {
+    pub fn new(hops: Vec<&'a Transfer>) -> Self {
+        TransferPath { hops }
+    }
+
+    /// Number of hops
+    pub fn len(&self) -> usize {
+        self.hops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hops.is_empty()
+    }
+
+    /// Addresses visited, starting with the sender of the first hop
+    pub fn addresses(&self) -> Vec<&'a str> {
+        let mut addresses: Vec<&str> = self
+            .hops
+            .first()
+            .map(|t| t.from.as_str())
+            .into_iter()
+            .collect();
+        addresses.extend(self.hops.iter().map(|t| t.to.as_str()));
+        addresses
+    }
+
+    /// Smallest hop amount, i.e. the most the path can have carried
+    pub fn bottleneck(&self) -> Option<TokenAmount> {
+        self.hops.iter().map(|t| t.amount).min()
+    }
+}
+
+/// Bounds on path enumeration, so dense graphs cannot make a search run
+/// forever
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct PathLimits {
+    /// Most hops in a returned path
+    pub max_depth: Option<usize>,
+    /// Stop after this many paths
+    pub max_paths: Option<usize>,
+    /// Stop after examining this many transfers
+    pub max_steps: Option<usize>,
+    /// Ignore transfers smaller than this
+    pub min_amount: Option<TokenAmount>,
+}
+
+impl PathLimits {
+    /// No limits at all; only safe on small graphs
+    pub fn unbounded() -> Self {
+        PathLimits {
+            max_depth: None,
+            max_paths: None,
+            max_steps: None,
+            min_amount: None,
+        }
+    }
+}
+
+impl Default for PathLimits {
+    fn default() -> Self {
+        PathLimits {
+            max_depth: Some(10),
+            max_paths: Some(100),
+            max_steps: Some(100_000),
+            min_amount: None,
+        }
+    }
+}
+
+/// Which limit cut a path search short
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathLimit {
+    /// Paths longer than `max_depth` were skipped
+    Depth,
+    /// `max_paths` paths were returned and the search stopped before
+    /// finishing; more paths may exist
+    Paths,
+    /// The `max_steps` budget ran out
+    Steps,
+}
+
+/// Lazy depth-first enumeration of simple paths, see
+/// [`TransferGraph::iter_paths`]
+#[derive(Debug)]
+pub struct PathIter<'a> {
+    graph: &'a TransferGraph,
+    target: String,
+    limits: PathLimits,
+    /// Time constraints between consecutive hops, for time-respecting paths
+    temporal: Option<TemporalConfig>,
+    /// Outgoing transfers of each address on the current path and the index
+    /// of the next one to try; always one frame more than `hops`
+    stack: Vec<(Vec<&'a Transfer>, usize)>,
+    hops: Vec<&'a Transfer>,
+    on_path: HashSet<&'a str>,
+    steps: usize,
+    found: usize,
+    limit: Option<PathLimit>,
+}
+
+impl<'a> PathIter<'a> {
+    /// The limit that cut the search short, if any. Only final once the
+    /// iterator has returned `None`.
+    pub fn limit_reached(&self) -> Option<PathLimit> {
+        self.limit
+    }
+
+    /// Transfers examined so far
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub(crate) fn new(
+        graph: &'a TransferGraph,
+        from: &str,
+        to: &str,
+        limits: &PathLimits,
+        temporal: Option<TemporalConfig>,
+    ) -> Self {
+        let mut iter = PathIter {
+            graph,
+            target: to.to_string(),
+            limits: limits.clone(),
+            temporal,
+            stack: Vec::new(),
+            hops: Vec::new(),
+            on_path: HashSet::new(),
+            steps: 0,
+            found: 0,
+            limit: None,
+        };
+        if let Some(node) = graph.nodes.get(from) {
+            iter.on_path.insert(&node.address);
+            iter.stack.push((iter.outgoing(from), 0));
+        }
+        iter
+    }
+
+    /// Transfers leaving `addr` in the order they are tried: insertion
+    /// order, or only the ones with a timestamp or slot in chronological
+    /// order for temporal searches
+    fn outgoing(&self, addr: &str) -> Vec<&'a Transfer> {
+        let Some(node) = self.graph.nodes.get(addr) else {
+            return Vec::new();
+        };
+        let mut outgoing: Vec<&'a Transfer> = node.outgoing.iter().collect();
+        if self.temporal.is_some() {
+            outgoing.retain(|t| placed_in_time(t));
+            outgoing.sort_by(|a, b| chronological(a, b));
+        }
+        outgoing
+    }
+
+    fn stop(&mut self, limit: PathLimit) -> Option<TransferPath<'a>> {
+        self.limit = Some(limit);
+        self.stack.clear();
+        None
+    }
+}
+
+impl<'a> Iterator for PathIter<'a> {
+    type Item = TransferPath<'a>;
+
+    fn next(&mut self) -> Option<TransferPath<'a>> {
+        if self.limits.max_paths.is_some_and(|max| self.found >= max) {
+            return if self.stack.is_empty() {
+                None
+            } else {
+                self.stop(PathLimit::Paths)
+            };
+        }
+
+        loop {
+            let (outgoing, next) = self.stack.last_mut()?;
+            let Some(&transfer) = outgoing.get(*next) else {
+                self.stack.pop();
+                if let Some(hop) = self.hops.pop() {
+                    self.on_path.remove(hop.to.as_str());
+                }
+                continue;
+            };
+            *next += 1;
+
+            self.steps += 1;
+            if self.limits.max_steps.is_some_and(|max| self.steps > max) {
+                self.steps -= 1;
+                return self.stop(PathLimit::Steps);
+            }
+            if self
+                .limits
+                .min_amount
+                .is_some_and(|min| transfer.amount < min)
+            {
+                continue;
+            }
+            if self
+                .temporal
+                .as_ref()
+                .is_some_and(|temporal| !temporal.can_follow(&self.hops, transfer))
+            {
+                continue;
+            }
+
+            let depth = self.hops.len() + 1;
+            let too_deep = |depth: usize| self.limits.max_depth.is_some_and(|max| depth > max);
+            if transfer.to == self.target {
+                if too_deep(depth) {
+                    self.limit.get_or_insert(PathLimit::Depth);
+                    continue;
+                }
+                let mut hops = self.hops.clone();
+                hops.push(transfer);
+                self.found += 1;
+                return Some(TransferPath::new(hops));
+            }
+            if self.on_path.contains(transfer.to.as_str()) {
+                continue;
+            }
+            let outgoing = self.outgoing(&transfer.to);
+            if too_deep(depth + 1) {
+                if !outgoing.is_empty() {
+                    self.limit.get_or_insert(PathLimit::Depth);
+                }
+                continue;
+            }
+            self.hops.push(transfer);
+            self.on_path.insert(&transfer.to);
+            self.stack.push((outgoing, 0));
+        }
+    }
+}
+
+impl TransferGraph {
+    /// Lazily enumerate simple paths from `from` to `to` within `limits`
+    ///
+    /// Paths are produced depth-first in transfer insertion order, one per
+    /// combination of parallel transfers, so only as much of the graph is
+    /// searched as the caller consumes. Passing the same address as `from`
+    /// and `to` yields round trips. Check
+    /// [`PathIter::limit_reached`] afterwards to learn whether the results
+    /// are complete.
+    pub fn iter_paths(&self, from: &str, to: &str, limits: &PathLimits) -> PathIter<'_> {
+        PathIter::new(self, from, to, limits, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    fn addresses(paths: &[TransferPath]) -> Vec<String> {
+        paths.iter().map(|p| p.addresses().join(" -> ")).collect()
+    }
+
+    fn diamond() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 100));
+        graph.add_transfer(transfer("A", "C", 5));
+        graph.add_transfer(transfer("B", "D", 60));
+        graph.add_transfer(transfer("C", "D", 5));
+        graph.add_transfer(transfer("B", "C", 30));
+        graph.add_transfer(transfer("D", "A", 1));
+        graph
+    }
+
+    #[test]
+    fn test_iter_paths_matches_find_paths() {
+        let graph = diamond();
+        let paths: Vec<TransferPath> = graph
+            .iter_paths("A", "D", &PathLimits::unbounded())
+            .collect();
+        assert_eq!(
+            addresses(&paths),
+            ["A -> B -> D", "A -> B -> C -> D", "A -> C -> D"]
+        );
+        assert_eq!(paths[0].bottleneck(), Some(TokenAmount::from(60)));
+        let expected: Vec<Vec<String>> = paths
+            .iter()
+            .map(|p| p.addresses().iter().map(|a| a.to_string()).collect())
+            .collect();
+        assert_eq!(graph.find_paths("A", "D"), expected);
+        assert_eq!(graph.find_paths("A", "A"), vec![vec!["A".to_string()]]);
+    }
+
+    #[test]
+    fn test_iter_paths_limits() {
+        let graph = diamond();
+        let run = |limits: PathLimits| {
+            let mut iter = graph.iter_paths("A", "D", &limits);
+            let paths: Vec<TransferPath> = iter.by_ref().collect();
+            (addresses(&paths), iter.limit_reached())
+        };
+
+        let depth = run(PathLimits {
+            max_depth: Some(2),
+            ..PathLimits::unbounded()
+        });
+        assert_eq!(depth.0, ["A -> B -> D", "A -> C -> D"]);
+        assert_eq!(depth.1, Some(PathLimit::Depth));
+
+        let count = run(PathLimits {
+            max_paths: Some(1),
+            ..PathLimits::unbounded()
+        });
+        assert_eq!(
+            count,
+            (vec!["A -> B -> D".to_string()], Some(PathLimit::Paths))
+        );
+        let exact = run(PathLimits {
+            max_paths: Some(4),
+            ..PathLimits::unbounded()
+        });
+        assert_eq!(exact.1, None);
+
+        let steps = run(PathLimits {
+            max_steps: Some(2),
+            ..PathLimits::unbounded()
+        });
+        assert_eq!(
+            steps,
+            (vec!["A -> B -> D".to_string()], Some(PathLimit::Steps))
+        );
+
+        let amount = run(PathLimits {
+            min_amount: Some(TokenAmount::from(10)),
+            ..PathLimits::unbounded()
+        });
+        assert_eq!(amount, (vec!["A -> B -> D".to_string()], None));
+
+        let round_trips: Vec<TransferPath> =
+            graph.iter_paths("A", "A", &PathLimits::default()).collect();
+        assert_eq!(round_trips.len(), 3);
+    }
+
+    #[test]
+    fn test_iter_paths_dense_graph_terminates() {
+        // Complete graph on 12 nodes: ~10^8 simple paths between two nodes
+        let mut graph = TransferGraph::new();
+        for a in 0..12 {
+            for b in 0..12 {
+                if a != b {
+                    graph.add_transfer(transfer(&a.to_string(), &b.to_string(), 1));
+                }
+            }
+        }
+        let mut iter = graph.iter_paths("0", "11", &PathLimits::default());
+        assert_eq!(iter.by_ref().count(), 100);
+        assert_eq!(iter.limit_reached(), Some(PathLimit::Paths));
+    }
+}
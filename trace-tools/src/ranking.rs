@@ -0,0 +1,342 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+use crate::{TokenAmount, Transfer, TransferGraph, TransferPath};
+
+/// Edges and addresses a spur search must avoid (Yen's algorithm)
+#[derive(Default)]
+struct Exclusions<'a> {
+    edges: HashSet<(&'a str, &'a str)>,
+    nodes: HashSet<&'a str>,
+}
+
+/// How candidate paths are ranked
+#[derive(Clone, Copy)]
+enum Rank {
+    /// Fewest hops
+    Hops,
+    /// Largest bottleneck amount, then fewest hops
+    Bottleneck,
+}
+
+impl TransferGraph {
+    /// Path from `from` to `to` with the fewest hops
+    ///
+    /// Parallel transfers between the same pair count as one edge; the hop
+    /// carries the largest of them. Among equally short paths the one found
+    /// first in transfer insertion order wins. Passing the same address as
+    /// `from` and `to` finds the shortest round trip.
+    pub fn shortest_path(&self, from: &str, to: &str) -> Option<TransferPath<'_>> {
+        self.best_route(from, to, Rank::Hops, &Exclusions::default())
+            .map(TransferPath::new)
+    }
+
+    /// Path from `from` to `to` whose smallest hop is as large as possible
+    ///
+    /// This is the most that can have flowed along a single route. Among
+    /// paths with the same bottleneck, the one with the fewest hops wins.
+    pub fn widest_path(&self, from: &str, to: &str) -> Option<TransferPath<'_>> {
+        self.best_route(from, to, Rank::Bottleneck, &Exclusions::default())
+            .map(TransferPath::new)
+    }
+
+    /// Up to `k` loopless paths in order of increasing hop count (Yen's
+    /// algorithm)
+    pub fn k_shortest_paths(&self, from: &str, to: &str, k: usize) -> Vec<TransferPath<'_>> {
+        self.yen(from, to, k, Rank::Hops)
+    }
+
+    /// Up to `k` loopless paths in order of decreasing bottleneck amount,
+    /// ties broken by hop count (Yen's algorithm)
+    pub fn k_widest_paths(&self, from: &str, to: &str, k: usize) -> Vec<TransferPath<'_>> {
+        self.yen(from, to, k, Rank::Bottleneck)
+    }
+
+    fn yen(&self, from: &str, to: &str, k: usize, rank: Rank) -> Vec<TransferPath<'_>> {
+        let key = |hops: &[&Transfer]| {
+            let width = match rank {
+                Rank::Hops => None,
+                Rank::Bottleneck => hops.iter().map(|t| t.amount).min(),
+            };
+            (Reverse(width), hops.len())
+        };
+
+        let mut accepted: Vec<Vec<&Transfer>> = Vec::new();
+        let mut candidates: Vec<Vec<&Transfer>> = Vec::new();
+        let mut seen: HashSet<Vec<&str>> = HashSet::new();
+
+        if k == 0 {
+            return Vec::new();
+        }
+        let Some(first) = self.best_route(from, to, rank, &Exclusions::default()) else {
+            return Vec::new();
+        };
+        seen.insert(route_addresses(&first));
+        accepted.push(first);
+
+        while accepted.len() < k {
+            let previous = accepted.last().expect("at least one accepted path");
+            let previous_addresses = route_addresses(previous);
+
+            for spur_idx in 0..previous.len() {
+                let spur = previous_addresses[spur_idx];
+                let root = &previous[..spur_idx];
+
+                let mut exclusions = Exclusions::default();
+                for path in &accepted {
+                    let addresses = route_addresses(path);
+                    if path.len() > spur_idx
+                        && addresses[..=spur_idx] == previous_addresses[..=spur_idx]
+                    {
+                        exclusions
+                            .edges
+                            .insert((addresses[spur_idx], addresses[spur_idx + 1]));
+                    }
+                }
+                exclusions.nodes.extend(&previous_addresses[..spur_idx]);
+
+                let Some(spur_route) = self.best_route(spur, to, rank, &exclusions) else {
+                    continue;
+                };
+                let mut route = root.to_vec();
+                route.extend(spur_route);
+                if seen.insert(route_addresses(&route)) {
+                    candidates.push(route);
+                }
+            }
+
+            // Stable minimum: earlier candidates win ties
+            let Some(best) = (0..candidates.len()).min_by_key(|&i| key(&candidates[i])) else {
+                break;
+            };
+            accepted.push(candidates.remove(best));
+        }
+
+        accepted.into_iter().map(TransferPath::new).collect()
+    }
+
+    fn best_route<'a>(
+        &'a self,
+        from: &str,
+        to: &str,
+        rank: Rank,
+        exclusions: &Exclusions,
+    ) -> Option<Vec<&'a Transfer>> {
+        match rank {
+            Rank::Hops => self.fewest_hops(from, to, None, exclusions),
+            Rank::Bottleneck => {
+                let width = self.max_bottleneck(from, to, exclusions)?;
+                self.fewest_hops(from, to, Some(width), exclusions)
+            }
+        }
+    }
+
+    /// One transfer per receiver: the largest transfer from `addr` to it,
+    /// in order of first appearance
+    fn route_edges(&self, addr: &str) -> Vec<&Transfer> {
+        let Some(node) = self.nodes.get(addr) else {
+            return Vec::new();
+        };
+        let mut edges: Vec<&Transfer> = Vec::new();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for transfer in &node.outgoing {
+            match index.get(transfer.to.as_str()) {
+                Some(&i) => {
+                    if transfer.amount > edges[i].amount {
+                        edges[i] = transfer;
+                    }
+                }
+                None => {
+                    index.insert(&transfer.to, edges.len());
+                    edges.push(transfer);
+                }
+            }
+        }
+        edges
+    }
+
+    /// Breadth-first search using only transfers of at least `min_amount`
+    fn fewest_hops<'a>(
+        &'a self,
+        from: &str,
+        to: &str,
+        min_amount: Option<TokenAmount>,
+        exclusions: &Exclusions,
+    ) -> Option<Vec<&'a Transfer>> {
+        let mut predecessor: HashMap<&str, &Transfer> = HashMap::new();
+        let mut visited: HashSet<&str> = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(addr) = queue.pop_front() {
+            for transfer in self.route_edges(addr) {
+                let next = transfer.to.as_str();
+                if exclusions.edges.contains(&(addr, next))
+                    || min_amount.is_some_and(|min| transfer.amount < min)
+                {
+                    continue;
+                }
+                if next == to {
+                    let mut hops = vec![transfer];
+                    let mut current = addr;
+                    while current != from {
+                        let hop = predecessor[current];
+                        hops.push(hop);
+                        current = &hop.from;
+                    }
+                    hops.reverse();
+                    return Some(hops);
+                }
+                if exclusions.nodes.contains(next) || !visited.insert(next) {
+                    continue;
+                }
+                predecessor.insert(next, transfer);
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+
+    /// Largest bottleneck amount of any path (a max-min Dijkstra)
+    fn max_bottleneck(&self, from: &str, to: &str, exclusions: &Exclusions) -> Option<TokenAmount> {
+        let mut best: HashMap<&str, TokenAmount> = HashMap::new();
+        let mut heap: BinaryHeap<(TokenAmount, &str)> = BinaryHeap::new();
+        let mut arrival: Option<TokenAmount> = None;
+        // The origin is expanded first with an unbounded width
+        let mut next_expansion = Some((from, None));
+
+        while let Some((addr, width)) = next_expansion.take().or_else(|| {
+            let (width, addr) = heap.pop()?;
+            Some((addr, Some(width)))
+        }) {
+            if let Some(width) = width {
+                // Every remaining path is at most as wide as this one
+                if arrival.is_some_and(|a| a >= width) {
+                    break;
+                }
+                if best.get(addr).is_some_and(|b| *b > width) {
+                    continue;
+                }
+            }
+            for transfer in self.route_edges(addr) {
+                let next = transfer.to.as_str();
+                if exclusions.edges.contains(&(addr, next)) {
+                    continue;
+                }
+                let width = width.map_or(transfer.amount, |w: TokenAmount| w.min(transfer.amount));
+                if next == to {
+                    arrival = arrival.max(Some(width));
+                } else if next != from
+                    && !exclusions.nodes.contains(next)
+                    && best.get(next).is_none_or(|b| width > *b)
+                {
+                    best.insert(next, width);
+                    heap.push((width, next));
+                }
+            }
+        }
+        arrival
+    }
+}
+
+fn route_addresses<'a>(hops: &[&'a Transfer]) -> Vec<&'a str> {
+    TransferPath::new(hops.to_vec()).addresses()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    fn addresses(path: &TransferPath) -> String {
+        path.addresses().join(" -> ")
+    }
+
+    /// A -> E directly (small), A -> B -> E (medium) and a wide detour
+    /// A -> C -> D -> E
+    fn graph() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 50));
+        graph.add_transfer(transfer("A", "C", 100));
+        graph.add_transfer(transfer("A", "E", 5));
+        graph.add_transfer(transfer("B", "E", 40));
+        graph.add_transfer(transfer("C", "D", 90));
+        graph.add_transfer(transfer("D", "E", 80));
+        graph.add_transfer(transfer("D", "E", 85));
+        graph.add_transfer(transfer("C", "B", 70));
+        graph
+    }
+
+    #[test]
+    fn test_shortest_and_widest() {
+        let graph = graph();
+        let shortest = graph.shortest_path("A", "E").unwrap();
+        assert_eq!(addresses(&shortest), "A -> E");
+
+        let widest = graph.widest_path("A", "E").unwrap();
+        assert_eq!(addresses(&widest), "A -> C -> D -> E");
+        assert_eq!(widest.bottleneck(), Some(TokenAmount::from(85)));
+        assert_eq!(widest.hops[2].amount, TokenAmount::from(85));
+
+        assert!(graph.shortest_path("E", "A").is_none());
+        assert!(graph.widest_path("A", "Z").is_none());
+    }
+
+    #[test]
+    fn test_k_shortest_paths() {
+        let graph = graph();
+        let paths: Vec<String> = graph
+            .k_shortest_paths("A", "E", 10)
+            .iter()
+            .map(addresses)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "A -> E",
+                "A -> B -> E",
+                "A -> C -> D -> E",
+                "A -> C -> B -> E"
+            ]
+        );
+        assert_eq!(graph.k_shortest_paths("A", "E", 2).len(), 2);
+        assert!(graph.k_shortest_paths("A", "E", 0).is_empty());
+    }
+
+    #[test]
+    fn test_k_widest_paths() {
+        let graph = graph();
+        let paths = graph.k_widest_paths("A", "E", 3);
+        let summary: Vec<(String, TokenAmount)> = paths
+            .iter()
+            .map(|p| (addresses(p), p.bottleneck().unwrap()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("A -> C -> D -> E".to_string(), TokenAmount::from(85)),
+                ("A -> B -> E".to_string(), TokenAmount::from(40)),
+                ("A -> C -> B -> E".to_string(), TokenAmount::from(40)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut graph = graph();
+        graph.add_transfer(transfer("E", "A", 1));
+        let path = graph.shortest_path("A", "A").unwrap();
+        assert_eq!(addresses(&path), "A -> E -> A");
+        assert_eq!(graph.k_shortest_paths("A", "A", 10).len(), 4);
+    }
+}
@@ -0,0 +1,630 @@
+use std::collections::HashSet;
+use std::io;
+
+use crate::{
+    Cycle, CycleLimit, MeetingPoint, NodeBalance, NodeCentrality, NodeTaint, PathLimit, TaintReport,
+    TokenAmount, TraceDirection, Transfer, TransferGraph, TransferPath,
+};
+
+/// Output format driven by [`TransferGraph::render_with`]
+///
+/// The graph calls these in order: `header`, `body`, then for each tree
+/// (unless `body` returned `false`) `begin_tree`, the `node`, `edge` and
+/// `end_node` visits and `end_tree`, then `meeting_points`,
+/// `paths_summary`, `cycles_summary`, `stats` and `finish`. `header`,
+/// `body` and `finish` are always called; the summaries are skipped when
+/// turned off in [`RenderConfig`](crate::RenderConfig). Every method does
+/// nothing by default, so a format only implements what it shows;
+/// [`AsciiRenderer`](crate::AsciiRenderer) implements them all.
+///
+/// `graph` is the graph being walked, which is the collapsed or aggregated
+/// view when `RenderConfig::collapse_clusters` or `aggregate_edges` is set.
+pub trait Renderer {
+    /// Title, token and target of the trace (`RenderConfig::show_header`
+    /// only concerns the title)
+    fn header(&mut self, graph: &TransferGraph) -> io::Result<()> {
+        let _ = graph;
+        Ok(())
+    }
+
+    /// Before the trees; return `false` to skip them and the meeting
+    /// points, e.g. after drawing the whole graph some other way
+    fn body(&mut self, graph: &TransferGraph) -> io::Result<bool> {
+        let _ = graph;
+        Ok(true)
+    }
+
+    /// A tree from the origin (`Forward`) or back from the target
+    /// (`Backward`) starts
+    fn begin_tree(&mut self, graph: &TransferGraph, direction: TraceDirection) -> io::Result<()> {
+        let _ = (graph, direction);
+        Ok(())
+    }
+
+    /// An address in the tree, before its edges
+    fn node(&mut self, graph: &TransferGraph, node: &NodeVisit<'_>) -> io::Result<()> {
+        let _ = (graph, node);
+        Ok(())
+    }
+
+    /// A transfer from the node visited last at `edge.depth` (to it in a
+    /// backward tree), before the subtree at its other end
+    fn edge(&mut self, graph: &TransferGraph, edge: &EdgeVisit<'_>) -> io::Result<()> {
+        let _ = (graph, edge);
+        Ok(())
+    }
+
+    /// Every edge and subtree of `node` has been visited
+    fn end_node(&mut self, graph: &TransferGraph, node: &NodeVisit<'_>) -> io::Result<()> {
+        let _ = (graph, node);
+        Ok(())
+    }
+
+    fn end_tree(&mut self, graph: &TransferGraph, direction: TraceDirection) -> io::Result<()> {
+        let _ = (graph, direction);
+        Ok(())
+    }
+
+    /// Where the forward and backward trees meet, with
+    /// `TraceDirection::Both` and both an origin and a target
+    fn meeting_points(
+        &mut self,
+        graph: &TransferGraph,
+        points: &[MeetingPoint<'_>],
+    ) -> io::Result<()> {
+        let _ = (graph, points);
+        Ok(())
+    }
+
+    /// Paths from the origin to the target, when both are set
+    fn paths_summary(
+        &mut self,
+        graph: &TransferGraph,
+        summary: &PathsSummary<'_>,
+    ) -> io::Result<()> {
+        let _ = (graph, summary);
+        Ok(())
+    }
+
+    /// Circular flows, which may be none
+    fn cycles_summary(
+        &mut self,
+        graph: &TransferGraph,
+        summary: &CyclesSummary<'_>,
+    ) -> io::Result<()> {
+        let _ = (graph, summary);
+        Ok(())
+    }
+
+    fn stats(&mut self, graph: &TransferGraph, stats: &StatsSummary<'_>) -> io::Result<()> {
+        let _ = (graph, stats);
+        Ok(())
+    }
+
+    /// Called last, e.g. to flush buffered output
+    fn finish(&mut self, graph: &TransferGraph) -> io::Result<()> {
+        let _ = graph;
+        Ok(())
+    }
+
+    /// Whether the renderer hit its output limits; the walk then stops and
+    /// goes straight to `finish`
+    fn is_truncated(&self) -> bool {
+        false
+    }
+}
+
+/// What an address is to the trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeRole {
+    Origin,
+    Target,
+    Intermediary,
+}
+
+/// An address reached while walking a tree, see [`Renderer::node`]
+#[derive(Debug, Clone)]
+pub struct NodeVisit<'a> {
+    pub address: &'a str,
+    pub label: Option<&'a str>,
+    pub role: NodeRole,
+    /// Hops from the root of the tree
+    pub depth: usize,
+    /// Tainted share under `RenderConfig::taint`
+    pub taint: Option<&'a NodeTaint>,
+    /// Transfers not followed because the node is at `TreeConfig::depth`
+    pub beyond_depth: usize,
+}
+
+/// How [`TransferGraph::render_with`] walks the trees, see
+/// [`RenderConfig::tree`](crate::RenderConfig::tree)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct TreeConfig {
+    /// Draw the tree from the origin, the inverted tree of who funded the
+    /// target, or both with the addresses where they meet
+    pub direction: TraceDirection,
+    /// Hops the trees walk from the origin or back from the target
+    pub depth: Option<usize>,
+    /// Draw the subtree of a node reached again this many levels deep
+    /// instead of only pointing back to it (cycles are always pointed back)
+    pub duplicate_shared_depth: usize,
+    /// Follow each node's transfers in chronological order instead of
+    /// insertion order
+    pub sort_by_time: bool,
+}
+
+/// A transfer followed while walking a tree, see [`Renderer::edge`]
+#[derive(Debug, Clone)]
+pub struct EdgeVisit<'a> {
+    pub transfer: &'a Transfer,
+    /// Depth of the node the edge belongs to
+    pub depth: usize,
+    /// Position among the node's edges
+    pub index: usize,
+    /// Number of edges of the node
+    pub siblings: usize,
+    /// The address at the other end is drawn elsewhere (or going there
+    /// would go round a cycle), so no subtree follows
+    pub reference: bool,
+}
+
+impl EdgeVisit<'_> {
+    pub fn is_last(&self) -> bool {
+        self.index + 1 == self.siblings
+    }
+}
+
+/// Routes from the origin to the target, see [`Renderer::paths_summary`]
+#[derive(Debug, Clone)]
+pub struct PathsSummary<'a> {
+    pub paths: Vec<TransferPath<'a>>,
+    /// The widest `RenderConfig::paths_summary_top_k` paths rather than
+    /// every path found
+    pub ranked: bool,
+    /// The limit the search stopped at, if any
+    pub limit: Option<PathLimit>,
+}
+
+/// Circular flows, see [`Renderer::cycles_summary`]
+#[derive(Debug, Clone)]
+pub struct CyclesSummary<'a> {
+    pub cycles: Vec<Cycle<'a>>,
+    /// The limit the search stopped at, if any
+    pub limit: Option<CycleLimit>,
+}
+
+/// Graph totals, see [`Renderer::stats`]
+#[derive(Debug, Clone)]
+pub struct StatsSummary<'a> {
+    pub nodes: usize,
+    pub transfers: usize,
+    /// Balance of the origin, when it is in the graph
+    pub origin: Option<NodeBalance<'a>>,
+    /// Balance of the target, when it is in the graph
+    pub target: Option<NodeBalance<'a>>,
+    /// Residuals of every address but the origin and target; `None` on
+    /// overflow
+    pub held_by_intermediaries: Option<TokenAmount>,
+    /// See [`TransferGraph::conservation_violations`]
+    pub violations: Vec<NodeBalance<'a>>,
+    /// The `HubConfig::count` intermediaries
+    pub hubs: Vec<NodeCentrality<'a>>,
+}
+
+impl TransferGraph {
+    /// Walk the graph as configured and hand every section to `renderer`
+    ///
+    /// Trees are walked as set in `RenderConfig::tree`; transfers are
+    /// visited in insertion order, or chronologically with
+    /// `TreeConfig::sort_by_time`.
+    pub fn render_with<R: Renderer + ?Sized>(&self, renderer: &mut R) -> io::Result<()> {
+        if let Some(view) = self.render_view() {
+            return view.render_with(renderer);
+        }
+        let cfg = &self.render_config;
+        renderer.header(self)?;
+
+        if !renderer.is_truncated() && renderer.body(self)? {
+            let taint = cfg.taint.as_ref().map(|config| self.taint(config));
+            if cfg.tree.direction != TraceDirection::Backward {
+                if let Some(origin) = self.origin.as_deref() {
+                    self.walk(renderer, TraceDirection::Forward, origin, taint.as_ref())?;
+                }
+            }
+            if cfg.tree.direction != TraceDirection::Forward && !renderer.is_truncated() {
+                if let Some(target) = self.target.as_deref() {
+                    self.walk(renderer, TraceDirection::Backward, target, taint.as_ref())?;
+                }
+            }
+            if cfg.tree.direction == TraceDirection::Both && !renderer.is_truncated() {
+                if let (Some(origin), Some(target)) = (&self.origin, &self.target) {
+                    let points = self.meeting_points(origin, target, cfg.tree.depth);
+                    renderer.meeting_points(self, &points)?;
+                }
+            }
+        }
+
+        if cfg.show_paths_summary && !renderer.is_truncated() {
+            if let (Some(origin), Some(target)) = (&self.origin, &self.target) {
+                let summary = match cfg.paths_summary_top_k {
+                    Some(k) => PathsSummary {
+                        paths: self.k_widest_paths(origin, target, k),
+                        ranked: true,
+                        limit: None,
+                    },
+                    None => {
+                        let mut search = self.iter_paths(origin, target, &cfg.path_limits);
+                        let paths = search.by_ref().collect();
+                        PathsSummary {
+                            paths,
+                            ranked: false,
+                            limit: search.limit_reached(),
+                        }
+                    }
+                };
+                renderer.paths_summary(self, &summary)?;
+            }
+        }
+
+        if cfg.show_cycles_summary && !renderer.is_truncated() {
+            let mut search = self.iter_cycles(&cfg.cycles);
+            let cycles = search.by_ref().collect();
+            let summary = CyclesSummary {
+                cycles,
+                limit: search.limit_reached(),
+            };
+            renderer.cycles_summary(self, &summary)?;
+        }
+
+        if cfg.show_stats_summary && !renderer.is_truncated() {
+            renderer.stats(self, &self.stats_summary())?;
+        }
+        renderer.finish(self)
+    }
+
+    /// Walk the tree from `root` in `direction` (`Forward` or `Backward`)
+    pub(crate) fn walk<R: Renderer + ?Sized>(
+        &self,
+        renderer: &mut R,
+        direction: TraceDirection,
+        root: &str,
+        taint: Option<&TaintReport>,
+    ) -> io::Result<()> {
+        renderer.begin_tree(self, direction)?;
+        let mut walk = Walk {
+            graph: self,
+            renderer,
+            taint,
+            backward: direction == TraceDirection::Backward,
+            visited: HashSet::new(),
+            path: Vec::new(),
+        };
+        walk.run(root)?;
+        renderer.end_tree(self, direction)
+    }
+
+    fn stats_summary(&self) -> StatsSummary<'_> {
+        let cfg = &self.render_config;
+        let balances = self.balances();
+        let balance = |addr: Option<&str>| {
+            addr.and_then(|addr| balances.iter().find(|b| b.address == addr))
+                .cloned()
+        };
+        // What the trace left with the addresses in between
+        let held: Option<Vec<TokenAmount>> = balances
+            .iter()
+            .filter(|b| Some(b.address) != self.origin.as_deref())
+            .filter(|b| Some(b.address) != self.target.as_deref())
+            .map(|b| b.residual())
+            .collect();
+
+        StatsSummary {
+            nodes: self.nodes.len(),
+            transfers: self.nodes.values().map(|n| n.outgoing.len()).sum(),
+            origin: balance(self.origin.as_deref()),
+            target: balance(self.target.as_deref()),
+            held_by_intermediaries: held.and_then(|held| TokenAmount::checked_sum(held.iter())),
+            violations: self.conservation_violations(),
+            hubs: self.hubs(cfg.hubs.count, cfg.hubs.weighting),
+        }
+    }
+}
+
+/// State of one tree walk
+struct Walk<'g, 'r, R: ?Sized> {
+    graph: &'g TransferGraph,
+    renderer: &'r mut R,
+    taint: Option<&'g TaintReport>,
+    /// Follow incoming transfers instead of outgoing ones
+    backward: bool,
+    visited: HashSet<&'g str>,
+    /// Nodes from the root down to the one being visited
+    path: Vec<&'g str>,
+}
+
+/// A node on the walk's stack, with the transfers still to follow
+struct Frame<'g> {
+    visit: NodeVisit<'g>,
+    /// Empty when the node is at `TreeConfig::depth`
+    transfers: Vec<&'g Transfer>,
+    /// Index of the next transfer to follow
+    next: usize,
+    /// Copy levels left for nodes reached again below this one
+    levels: usize,
+}
+
+impl<'g, R: Renderer + ?Sized> Walk<'g, '_, R> {
+    /// Visit the tree below `root`
+    ///
+    /// A node reached again is visited once more, down to
+    /// `TreeConfig::duplicate_shared_depth` levels, with the levels
+    /// counting down inside such a copy; past that, or when visiting it
+    /// again would go round a cycle, the edge is a reference. The walk
+    /// keeps its own stack, so deep trees need no deep call stack.
+    fn run(&mut self, root: &'g str) -> io::Result<()> {
+        let graph = self.graph;
+        let mut stack = vec![self.enter(root, 0, None)?];
+
+        while let Some(frame) = stack.last_mut() {
+            let index = frame.next;
+            let Some(&transfer) = frame.transfers.get(index) else {
+                self.renderer.end_node(graph, &frame.visit)?;
+                self.path.pop();
+                stack.pop();
+                continue;
+            };
+            if self.renderer.is_truncated() {
+                frame.next = frame.transfers.len();
+                continue;
+            }
+            frame.next += 1;
+
+            let depth = frame.visit.depth;
+            let levels = frame.levels;
+            let next = if self.backward {
+                transfer.from.as_str()
+            } else {
+                transfer.to.as_str()
+            };
+            let next_levels = if !self.visited.contains(next) {
+                Some(None)
+            } else if levels > 0 && !self.path.contains(&next) {
+                Some(Some(levels - 1))
+            } else {
+                None
+            };
+            let edge = EdgeVisit {
+                transfer,
+                depth,
+                index,
+                siblings: frame.transfers.len(),
+                reference: next_levels.is_none(),
+            };
+            self.renderer.edge(graph, &edge)?;
+            if let Some(next_levels) = next_levels {
+                let child = self.enter(next, depth + 1, next_levels)?;
+                stack.push(child);
+            }
+        }
+        Ok(())
+    }
+
+    /// Visit the node `addr`, with `copy_levels` left when it is a copy
+    fn enter(
+        &mut self,
+        addr: &'g str,
+        depth: usize,
+        copy_levels: Option<usize>,
+    ) -> io::Result<Frame<'g>> {
+        let graph = self.graph;
+        let cfg = &graph.render_config;
+        self.visited.insert(addr);
+        self.path.push(addr);
+
+        let node = graph.nodes.get(addr);
+        let transfers = node.map_or_else(Vec::new, |node| {
+            graph.ordered_transfers(if self.backward {
+                &node.incoming
+            } else {
+                &node.outgoing
+            })
+        });
+        let at_limit = cfg.tree.depth.is_some_and(|max| depth >= max);
+        let visit = NodeVisit {
+            address: addr,
+            label: node.and_then(|n| n.label.as_deref()),
+            role: if Some(addr) == graph.origin.as_deref() {
+                NodeRole::Origin
+            } else if Some(addr) == graph.target.as_deref() {
+                NodeRole::Target
+            } else {
+                NodeRole::Intermediary
+            },
+            depth,
+            taint: self.taint.and_then(|report| report.get(addr)),
+            beyond_depth: if at_limit { transfers.len() } else { 0 },
+        };
+        self.renderer.node(graph, &visit)?;
+
+        Ok(Frame {
+            visit,
+            transfers: if at_limit { Vec::new() } else { transfers },
+            next: 0,
+            levels: copy_levels.unwrap_or(cfg.tree.duplicate_shared_depth),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "T".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    /// Every call, one line each
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Renderer for Recorder {
+        fn begin_tree(&mut self, _: &TransferGraph, direction: TraceDirection) -> io::Result<()> {
+            self.0.push(format!("tree {:?}", direction));
+            Ok(())
+        }
+
+        fn node(&mut self, _: &TransferGraph, node: &NodeVisit<'_>) -> io::Result<()> {
+            self.0
+                .push(format!("node {} {:?}", node.address, node.role));
+            Ok(())
+        }
+
+        fn edge(&mut self, _: &TransferGraph, edge: &EdgeVisit<'_>) -> io::Result<()> {
+            let reference = if edge.reference { " ref" } else { "" };
+            let t = edge.transfer;
+            self.0
+                .push(format!("edge {}>{}{}", t.from, t.to, reference));
+            Ok(())
+        }
+
+        fn end_node(&mut self, _: &TransferGraph, node: &NodeVisit<'_>) -> io::Result<()> {
+            self.0.push(format!("end {}", node.address));
+            Ok(())
+        }
+
+        fn meeting_points(
+            &mut self,
+            _: &TransferGraph,
+            points: &[MeetingPoint<'_>],
+        ) -> io::Result<()> {
+            self.0.push(format!("meet {}", points.len()));
+            Ok(())
+        }
+
+        fn paths_summary(&mut self, _: &TransferGraph, s: &PathsSummary<'_>) -> io::Result<()> {
+            self.0.push(format!("paths {}", s.paths.len()));
+            Ok(())
+        }
+
+        fn stats(&mut self, _: &TransferGraph, stats: &StatsSummary<'_>) -> io::Result<()> {
+            self.0
+                .push(format!("stats {} {}", stats.nodes, stats.transfers));
+            Ok(())
+        }
+    }
+
+    /// Counts node visits and the deepest one
+    #[derive(Default)]
+    struct Depth {
+        nodes: usize,
+        deepest: usize,
+    }
+
+    impl Renderer for Depth {
+        fn node(&mut self, _: &TransferGraph, node: &NodeVisit<'_>) -> io::Result<()> {
+            self.nodes += 1;
+            self.deepest = self.deepest.max(node.depth);
+            Ok(())
+        }
+    }
+
+    /// O pays A and B, which both pay T
+    fn diamond() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("O".to_string());
+        graph.target = Some("T".to_string());
+        graph.render_config.show_cycles_summary = false;
+        for (from, to) in [("O", "A"), ("O", "B"), ("A", "T"), ("B", "T")] {
+            graph.add_transfer(transfer(from, to, 1));
+        }
+        graph
+    }
+
+    #[test]
+    fn test_visit_order() {
+        let mut recorder = Recorder::default();
+        diamond().render_with(&mut recorder).unwrap();
+        let expected = [
+            "tree Forward",
+            "node O Origin",
+            "edge O>A",
+            "node A Intermediary",
+            "edge A>T",
+            "node T Target",
+            "end T",
+            "end A",
+            "edge O>B",
+            "node B Intermediary",
+            "edge B>T ref",
+            "end B",
+            "end O",
+            "paths 2",
+            "stats 4 4",
+        ];
+        assert_eq!(recorder.0, expected);
+    }
+
+    #[test]
+    fn test_backward_and_both() {
+        let mut graph = diamond();
+        graph.render_config.tree.direction = TraceDirection::Both;
+        graph.render_config.show_paths_summary = false;
+        graph.render_config.show_stats_summary = false;
+        let mut recorder = Recorder::default();
+        graph.render_with(&mut recorder).unwrap();
+        let backward: Vec<&str> = recorder
+            .0
+            .iter()
+            .skip_while(|line| *line != "tree Backward")
+            .map(String::as_str)
+            .collect();
+        let expected = [
+            "tree Backward",
+            "node T Target",
+            "edge A>T",
+            "node A Intermediary",
+            "edge O>A",
+            "node O Origin",
+            "end O",
+            "end A",
+            "edge B>T",
+            "node B Intermediary",
+            "edge O>B ref",
+            "end B",
+            "end T",
+            "meet 2",
+        ];
+        assert_eq!(backward, expected);
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        // Far deeper than a test thread's call stack would allow recursing
+        let hops = 100_000;
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("C0".to_string());
+        graph.render_config.show_paths_summary = false;
+        graph.render_config.show_cycles_summary = false;
+        graph.render_config.show_stats_summary = false;
+        for i in 0..hops {
+            graph.add_transfer(transfer(&format!("C{}", i), &format!("C{}", i + 1), 1));
+        }
+        let mut depth = Depth::default();
+        graph.render_with(&mut depth).unwrap();
+        assert_eq!(depth.nodes, hops + 1);
+        assert_eq!(depth.deepest, hops);
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{TokenAmount, TransferGraph};
+
+/// A node of a Sankey diagram
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SankeyNode {
+    /// Display name: the node label, or the truncated address
+    pub name: String,
+    pub address: String,
+    pub label: Option<String>,
+}
+
+/// A link of a Sankey diagram aggregating every transfer of one token
+/// between the same pair of addresses
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SankeyLink {
+    /// Index into [`SankeyDiagram::nodes`]
+    pub source: usize,
+    /// Index into [`SankeyDiagram::nodes`]
+    pub target: usize,
+    /// Link width for d3-sankey (approximate, see `amount`)
+    pub value: f64,
+    /// Exact summed amount, `None` if the sum overflowed
+    pub amount: Option<TokenAmount>,
+    pub token_symbol: String,
+    pub transfer_count: usize,
+}
+
+/// d3-sankey compatible `{nodes, links}` data
+///
+/// Sankey layouts require an acyclic graph, so links closing a cycle are
+/// moved to `removed_links` (which d3-sankey ignores) instead of `links`.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SankeyDiagram {
+    pub nodes: Vec<SankeyNode>,
+    pub links: Vec<SankeyLink>,
+    pub removed_links: Vec<SankeyLink>,
+}
+
+/// Aggregated edge keyed by (from, to, token_symbol)
+type EdgeKey<'a> = (&'a str, &'a str, &'a str);
+
+impl TransferGraph {
+    /// Build a Sankey diagram of the token flows in the graph
+    ///
+    /// Parallel transfers of the same token between the same pair are summed
+    /// into one link. Cycles are broken by a depth-first search that starts at
+    /// the origin (then every other node in address order) and drops each
+    /// link pointing back to a node still on the search stack.
+    pub fn sankey(&self) -> SankeyDiagram {
+        let mut edges: BTreeMap<EdgeKey, (Option<TokenAmount>, f64, usize)> = BTreeMap::new();
+        for node in self.nodes.values() {
+            for transfer in &node.outgoing {
+                let entry = edges
+                    .entry((&transfer.from, &transfer.to, &transfer.token_symbol))
+                    .or_insert((Some(TokenAmount::ZERO), 0.0, 0));
+                entry.0 = entry.0.and_then(|sum| sum.checked_add(&transfer.amount));
+                entry.1 += transfer.amount.to_f64();
+                entry.2 += 1;
+            }
+        }
+
+        let mut adjacency: BTreeMap<&str, Vec<EdgeKey>> = BTreeMap::new();
+        for key in edges.keys() {
+            adjacency.entry(key.0).or_default().push(*key);
+        }
+
+        let back_edges = find_back_edges(self, &adjacency);
+
+        let mut diagram = SankeyDiagram::default();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for key in edges.keys() {
+            for addr in [key.0, key.1] {
+                if !index.contains_key(addr) {
+                    index.insert(addr, diagram.nodes.len());
+                    diagram.nodes.push(self.sankey_node(addr));
+                }
+            }
+        }
+
+        for (key, (amount, value, count)) in &edges {
+            let link = SankeyLink {
+                source: index[key.0],
+                target: index[key.1],
+                value: *value,
+                amount: *amount,
+                token_symbol: key.2.to_string(),
+                transfer_count: *count,
+            };
+            if back_edges.contains(key) {
+                diagram.removed_links.push(link);
+            } else {
+                diagram.links.push(link);
+            }
+        }
+
+        diagram
+    }
+
+    fn sankey_node(&self, addr: &str) -> SankeyNode {
+        let label = self.nodes.get(addr).and_then(|n| n.label.clone());
+        let name = label.clone().unwrap_or_else(|| {
+            self.truncate_address(addr, self.render_config.address_truncate_length)
+        });
+        SankeyNode {
+            name,
+            address: addr.to_string(),
+            label,
+        }
+    }
+
+    /// Render the Sankey diagram as d3-sankey compatible JSON
+    #[cfg(feature = "serde")]
+    pub fn render_sankey_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.sankey())
+    }
+
+    /// Stream the JSON of `render_sankey_json` to `out`
+    #[cfg(feature = "serde")]
+    pub fn write_sankey_json<W: std::io::Write>(&self, out: W) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(out, &self.sankey())
+    }
+}
+
+/// Iterative depth-first search returning every edge that points at a node
+/// currently on the stack (including self-loops)
+fn find_back_edges<'a>(
+    graph: &'a TransferGraph,
+    adjacency: &BTreeMap<&'a str, Vec<EdgeKey<'a>>>,
+) -> HashSet<EdgeKey<'a>> {
+    let mut back_edges = HashSet::new();
+    let mut visited: HashSet<&str> = HashSet::new();
+    let mut on_stack: HashSet<&str> = HashSet::new();
+
+    let roots = graph
+        .origin
+        .as_deref()
+        .into_iter()
+        .chain(adjacency.keys().copied());
+
+    for root in roots {
+        if !visited.insert(root) {
+            continue;
+        }
+        on_stack.insert(root);
+        // (node, index of the next outgoing edge to inspect)
+        let mut stack = vec![(root, 0usize)];
+
+        while let Some((addr, next)) = stack.last_mut() {
+            let addr = *addr;
+            let out = adjacency.get(addr).map_or(&[][..], Vec::as_slice);
+            if *next == out.len() {
+                on_stack.remove(addr);
+                stack.pop();
+                continue;
+            }
+            let edge = out[*next];
+            *next += 1;
+
+            let to = edge.1;
+            if on_stack.contains(to) {
+                back_edges.insert(edge);
+            } else if visited.insert(to) {
+                on_stack.insert(to);
+                stack.push((to, 0));
+            }
+        }
+    }
+
+    back_edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Transfer;
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_sankey_aggregates_parallel_transfers() {
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("A".to_string());
+        graph.add_transfer(transfer("A", "B", 10));
+        graph.add_transfer(transfer("A", "B", 5));
+        graph.add_transfer(Transfer {
+            amount: TokenAmount::new(75, 1),
+            ..transfer("B", "C", 0)
+        });
+        graph.set_node_label("B", "Hub".to_string());
+
+        let sankey = graph.sankey();
+        let names: Vec<&str> = sankey.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["A", "Hub", "C"]);
+        assert_eq!(
+            sankey.links[0],
+            SankeyLink {
+                source: 0,
+                target: 1,
+                value: 15.0,
+                amount: Some(TokenAmount::from(15)),
+                token_symbol: "SVMAI".to_string(),
+                transfer_count: 2,
+            }
+        );
+        assert_eq!(sankey.links[1].amount.unwrap().to_string(), "7.5");
+        assert!(sankey.removed_links.is_empty());
+    }
+
+    #[test]
+    fn test_sankey_breaks_cycles() {
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("A".to_string());
+        graph.add_transfer(transfer("A", "B", 10));
+        graph.add_transfer(transfer("B", "C", 8));
+        graph.add_transfer(transfer("C", "A", 3));
+        graph.add_transfer(transfer("C", "C", 1));
+
+        let sankey = graph.sankey();
+        assert_eq!(sankey.links.len(), 2);
+        assert_eq!(sankey.removed_links.len(), 2);
+        let removed: Vec<(&str, &str)> = sankey
+            .removed_links
+            .iter()
+            .map(|l| {
+                (
+                    sankey.nodes[l.source].address.as_str(),
+                    sankey.nodes[l.target].address.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(removed, vec![("C", "A"), ("C", "C")]);
+    }
+}
@@ -0,0 +1,457 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+
+use crate::output::render_to_string;
+use crate::xml::escape_xml;
+use crate::{GraphNode, Transfer, TransferGraph};
+
+/// Configuration for native SVG output, see
+/// [`RenderConfig::svg`](crate::RenderConfig::svg)
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct SvgConfig {
+    pub node_width: f64,
+    pub node_height: f64,
+    /// Horizontal space between layers
+    pub column_gap: f64,
+    /// Vertical space between nodes of the same layer
+    pub row_gap: f64,
+    pub margin: f64,
+    /// Edge stroke width for the smallest transfer
+    pub min_edge_width: f64,
+    /// Edge stroke width for the largest transfer
+    pub max_edge_width: f64,
+    pub show_legend: bool,
+    pub font_family: String,
+    pub background: String,
+    pub node_fill: String,
+    pub origin_fill: String,
+    pub target_fill: String,
+    pub edge_color: String,
+}
+
+impl Default for SvgConfig {
+    fn default() -> Self {
+        SvgConfig {
+            node_width: 220.0,
+            node_height: 52.0,
+            column_gap: 140.0,
+            row_gap: 28.0,
+            margin: 24.0,
+            min_edge_width: 1.0,
+            max_edge_width: 12.0,
+            show_legend: true,
+            font_family: "Helvetica, Arial, sans-serif".to_string(),
+            background: "#ffffff".to_string(),
+            node_fill: "#f1f5f9".to_string(),
+            origin_fill: "#a7f3d0".to_string(),
+            target_fill: "#fecaca".to_string(),
+            edge_color: "#64748b".to_string(),
+        }
+    }
+}
+
+const TITLE_HEIGHT: f64 = 40.0;
+const LEGEND_HEIGHT: f64 = 56.0;
+
+impl TransferGraph {
+    /// Render the graph as a standalone SVG image using `RenderConfig::svg`
+    pub fn render_svg(&self) -> String {
+        self.render_svg_with(&self.render_config.svg)
+    }
+
+    /// Render the graph as a standalone SVG image with a layered
+    /// left-to-right layout, with `svg` instead of `RenderConfig::svg`
+    pub fn render_svg_with(&self, svg: &SvgConfig) -> String {
+        render_to_string(|out| self.write_svg_with(svg, out))
+    }
+
+    /// Stream the image of `render_svg` to `out`
+    ///
+    /// Wrap unbuffered writers such as files in a `BufWriter`.
+    pub fn write_svg<W: Write>(&self, out: W) -> io::Result<()> {
+        self.write_svg_with(&self.render_config.svg, out)
+    }
+
+    /// `write_svg` with `svg` instead of `RenderConfig::svg`
+    pub fn write_svg_with<W: Write>(&self, svg: &SvgConfig, mut out: W) -> io::Result<()> {
+        if let Some(view) = self.render_view() {
+            return view.write_svg_with(svg, out);
+        }
+        let cfg = &self.render_config;
+        let layers = self.svg_layers();
+
+        let top = svg.margin + if cfg.show_header { TITLE_HEIGHT } else { 0.0 };
+        let tallest = layers.iter().map(Vec::len).max().unwrap_or(0) as f64;
+        let graph_height = (tallest * (svg.node_height + svg.row_gap) - svg.row_gap).max(0.0);
+        let columns = layers.len() as f64;
+        let width = (2.0 * svg.margin + columns * (svg.node_width + svg.column_gap)
+            - svg.column_gap)
+            .max(2.0 * svg.margin + 360.0);
+        let height =
+            top + graph_height + svg.margin + if svg.show_legend { LEGEND_HEIGHT } else { 0.0 };
+
+        // Top-left corner of every node box, layers centred vertically
+        let mut positions: HashMap<&str, (f64, f64)> = HashMap::new();
+        for (col, layer) in layers.iter().enumerate() {
+            let layer_height = layer.len() as f64 * (svg.node_height + svg.row_gap) - svg.row_gap;
+            let y0 = top + (graph_height - layer_height) / 2.0;
+            for (row, node) in layer.iter().enumerate() {
+                positions.insert(
+                    node.address.as_str(),
+                    (
+                        svg.margin + col as f64 * (svg.node_width + svg.column_gap),
+                        y0 + row as f64 * (svg.node_height + svg.row_gap),
+                    ),
+                );
+            }
+        }
+
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.0} {:.0}\" font-family=\"{}\" font-size=\"12\">",
+            width,
+            height,
+            width,
+            height,
+            escape_xml(&svg.font_family)
+        )?;
+        writeln!(
+            out,
+            "  <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+            escape_xml(&svg.background)
+        )?;
+        if cfg.show_header {
+            writeln!(
+                out,
+                "  <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-size=\"18\" font-weight=\"bold\">{}</text>",
+                width / 2.0,
+                svg.margin + 20.0,
+                escape_xml(&cfg.title)
+            )?;
+        }
+
+        let nodes = self.sorted_nodes();
+        let max_amount = nodes
+            .iter()
+            .flat_map(|n| n.outgoing.iter())
+            .map(|t| t.amount.to_f64())
+            .fold(0.0_f64, f64::max);
+
+        // Arrowheads scale with the edge they end, like the edge width
+        writeln!(
+            out,
+            "  <defs>\n    <marker id=\"arrowhead\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"3\" markerHeight=\"3\" orient=\"auto\"><path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"{}\" fill-opacity=\"0.7\"/></marker>\n  </defs>",
+            escape_xml(&svg.edge_color)
+        )?;
+        out.write_all(b"  <g class=\"edges\" fill=\"none\">\n")?;
+        for node in &nodes {
+            let mut parallel: BTreeMap<&str, usize> = BTreeMap::new();
+            for transfer in &node.outgoing {
+                *parallel.entry(transfer.to.as_str()).or_default() += 1;
+            }
+            let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+            for transfer in &node.outgoing {
+                let index = seen.entry(transfer.to.as_str()).or_default();
+                let offset =
+                    (*index as f64 - (parallel[transfer.to.as_str()] - 1) as f64 / 2.0) * 8.0;
+                *index += 1;
+                self.write_svg_edge(&mut out, transfer, &positions, offset, max_amount, svg)?;
+            }
+        }
+        out.write_all(b"  </g>\n")?;
+
+        out.write_all(b"  <g class=\"nodes\">\n")?;
+        for layer in &layers {
+            for node in layer {
+                self.write_svg_node(&mut out, node, positions[node.address.as_str()], svg)?;
+            }
+        }
+        out.write_all(b"  </g>\n")?;
+
+        if svg.show_legend {
+            self.write_svg_legend(&mut out, height - svg.margin - LEGEND_HEIGHT + 16.0, svg)?;
+        }
+
+        out.write_all(b"</svg>\n")
+    }
+
+    /// Assign nodes to columns by hop distance (see `hop_columns`). Within a
+    /// column nodes are ordered by the average row of their predecessors,
+    /// then by address.
+    fn svg_layers(&self) -> Vec<Vec<&GraphNode>> {
+        let nodes = self.sorted_nodes();
+        let depth = self.hop_columns();
+
+        let columns = depth.values().max().map_or(0, |d| d + 1);
+        let mut layers: Vec<Vec<&GraphNode>> = vec![Vec::new(); columns];
+        for node in &nodes {
+            layers[depth[node.address.as_str()]].push(node);
+        }
+
+        let mut rows: HashMap<&str, usize> = HashMap::new();
+        for layer in &mut layers {
+            let barycenter = |node: &GraphNode| {
+                let preds: Vec<usize> = node
+                    .incoming
+                    .iter()
+                    .filter_map(|t| rows.get(t.from.as_str()).copied())
+                    .collect();
+                if preds.is_empty() {
+                    f64::MAX
+                } else {
+                    preds.iter().sum::<usize>() as f64 / preds.len() as f64
+                }
+            };
+            layer.sort_by(|a, b| {
+                barycenter(a)
+                    .total_cmp(&barycenter(b))
+                    .then_with(|| a.address.cmp(&b.address))
+            });
+            for (row, node) in layer.iter().enumerate() {
+                rows.insert(node.address.as_str(), row);
+            }
+        }
+
+        layers
+    }
+
+    fn write_svg_node<W: Write>(
+        &self,
+        out: &mut W,
+        node: &GraphNode,
+        (x, y): (f64, f64),
+        svg: &SvgConfig,
+    ) -> io::Result<()> {
+        let cfg = &self.render_config;
+        let addr = node.address.as_str();
+        let (fill, stroke_width, heading) = if Some(addr) == self.origin.as_deref() {
+            (&svg.origin_fill, 2.0, Some(cfg.origin_icon.as_str()))
+        } else if Some(addr) == self.target.as_deref() {
+            (&svg.target_fill, 2.0, Some(cfg.target_icon.as_str()))
+        } else {
+            (&svg.node_fill, 1.0, None)
+        };
+
+        let mut first_line = heading.unwrap_or(&cfg.node_icon).to_string();
+        if let Some(label) = &node.label {
+            first_line.push(' ');
+            first_line.push_str(label);
+        }
+
+        let mut tooltip = addr.to_string();
+        if let Some(label) = &node.label {
+            tooltip = format!("{} ({})", label, addr);
+        }
+
+        out.write_all(b"    <g class=\"node\">\n")?;
+        writeln!(out, "      <title>{}</title>", escape_xml(&tooltip))?;
+        writeln!(
+            out,
+            "      <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"8\" fill=\"{}\" stroke=\"#334155\" stroke-width=\"{:.1}\"/>",
+            x,
+            y,
+            svg.node_width,
+            svg.node_height,
+            escape_xml(fill),
+            stroke_width
+        )?;
+        writeln!(
+            out,
+            "      <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-weight=\"bold\">{}</text>",
+            x + svg.node_width / 2.0,
+            y + svg.node_height / 2.0 - 4.0,
+            escape_xml(&first_line)
+        )?;
+        writeln!(
+            out,
+            "      <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-family=\"monospace\" font-size=\"11\">{}</text>",
+            x + svg.node_width / 2.0,
+            y + svg.node_height / 2.0 + 12.0,
+            escape_xml(&self.truncate_address(addr, cfg.address_truncate_length))
+        )?;
+        out.write_all(b"    </g>\n")
+    }
+
+    fn write_svg_edge<W: Write>(
+        &self,
+        out: &mut W,
+        transfer: &Transfer,
+        positions: &HashMap<&str, (f64, f64)>,
+        offset: f64,
+        max_amount: f64,
+        svg: &SvgConfig,
+    ) -> io::Result<()> {
+        let (fx, fy) = positions[transfer.from.as_str()];
+        let (tx, ty) = positions[transfer.to.as_str()];
+        let x1 = fx + svg.node_width;
+        let y1 = fy + svg.node_height / 2.0 + offset;
+        let x2 = tx;
+        let y2 = ty + svg.node_height / 2.0 + offset;
+        // Backward and same-column edges bend further out so they stay visible
+        let bend = if x2 > x1 {
+            (x2 - x1) / 2.0
+        } else {
+            svg.column_gap
+        };
+
+        let width = if max_amount > 0.0 {
+            svg.min_edge_width
+                + (svg.max_edge_width - svg.min_edge_width)
+                    * (transfer.amount.to_f64() / max_amount).clamp(0.0, 1.0)
+        } else {
+            svg.min_edge_width
+        };
+
+        let mut tooltip = format!(
+            "{} → {}: {} {}",
+            transfer.from,
+            transfer.to,
+            self.format_amount(&transfer.amount),
+            transfer.token_symbol
+        );
+        if let Some(time) = self.format_time(transfer) {
+            tooltip.push_str(&format!(" ({})", time));
+        }
+        if let Some(note) = &transfer.note {
+            tooltip.push_str(&format!(" [{}]", note));
+        }
+
+        writeln!(
+            out,
+            "    <path d=\"M {:.1} {:.1} C {:.1} {:.1}, {:.1} {:.1}, {:.1} {:.1}\" stroke=\"{}\" stroke-width=\"{:.2}\" stroke-opacity=\"0.7\" marker-end=\"url(#arrowhead)\"><title>{}</title></path>",
+            x1,
+            y1,
+            x1 + bend,
+            y1,
+            x2 - bend,
+            y2,
+            x2,
+            y2,
+            escape_xml(&svg.edge_color),
+            width,
+            escape_xml(&tooltip)
+        )?;
+        writeln!(
+            out,
+            "    <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-size=\"10\" fill=\"#334155\">{} {}</text>",
+            (x1 + x2) / 2.0,
+            (y1 + y2) / 2.0 - 4.0,
+            escape_xml(&self.format_amount(&transfer.amount)),
+            escape_xml(&transfer.token_symbol)
+        )
+    }
+
+    fn write_svg_legend<W: Write>(&self, out: &mut W, y: f64, svg: &SvgConfig) -> io::Result<()> {
+        let cfg = &self.render_config;
+        let x = svg.margin;
+        out.write_all(b"  <g class=\"legend\" font-size=\"11\">\n")?;
+        let swatches = [
+            (&svg.origin_fill, cfg.origin_icon.as_str()),
+            (&svg.target_fill, cfg.target_icon.as_str()),
+            (&svg.node_fill, cfg.node_icon.as_str()),
+        ];
+        for (idx, (fill, text)) in swatches.iter().enumerate() {
+            let sx = x + idx as f64 * 120.0;
+            writeln!(
+                out,
+                "    <rect x=\"{:.1}\" y=\"{:.1}\" width=\"14\" height=\"14\" rx=\"3\" fill=\"{}\" stroke=\"#334155\"/>",
+                sx,
+                y,
+                escape_xml(fill)
+            )?;
+            writeln!(
+                out,
+                "    <text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+                sx + 20.0,
+                y + 11.0,
+                escape_xml(text)
+            )?;
+        }
+        writeln!(
+            out,
+            "    <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" stroke-width=\"{:.2}\"/>",
+            x,
+            y + 32.0,
+            x + 40.0,
+            y + 32.0,
+            escape_xml(&svg.edge_color),
+            svg.max_edge_width / 2.0
+        )?;
+        writeln!(
+            out,
+            "    <text x=\"{:.1}\" y=\"{:.1}\">Edge width proportional to amount</text>",
+            x + 48.0,
+            y + 36.0
+        )?;
+        out.write_all(b"  </g>\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TokenAmount;
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    fn sample_graph() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("Origin123".to_string());
+        graph.target = Some("Target789".to_string());
+        graph.add_transfer(transfer("Origin123", "Middle456", 1000));
+        graph.add_transfer(transfer("Origin123", "Side000", 250));
+        graph.add_transfer(Transfer {
+            note: Some("Final <transfer>".to_string()),
+            ..transfer("Middle456", "Target789", 500)
+        });
+        graph.set_node_label("Middle456", "Hub & Co".to_string());
+        graph
+    }
+
+    #[test]
+    fn test_render_svg_golden() {
+        let svg = sample_graph().render_svg();
+        assert_eq!(svg, include_str!("../testdata/basic.svg"));
+    }
+
+    #[test]
+    fn test_render_svg_layout() {
+        let graph = sample_graph();
+        let layers = graph.svg_layers();
+        let addrs: Vec<Vec<&str>> = layers
+            .iter()
+            .map(|l| l.iter().map(|n| n.address.as_str()).collect())
+            .collect();
+        assert_eq!(
+            addrs,
+            vec![
+                vec!["Origin123"],
+                vec!["Middle456", "Side000"],
+                vec!["Target789"]
+            ]
+        );
+
+        let svg = graph.render_svg();
+        assert!(svg.contains("<title>Hub &amp; Co (Middle456)</title>"));
+        assert!(svg.contains("[Final &lt;transfer&gt;]</title>"));
+        assert!(svg.contains("stroke-width=\"12.00\""));
+        assert!(svg.contains("<marker id=\"arrowhead\""));
+        assert_eq!(svg.matches("marker-end=\"url(#arrowhead)\"").count(), 3);
+        assert!(svg.contains("Edge width proportional to amount"));
+    }
+}
@@ -0,0 +1,516 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{TokenAmount, Transfer, TransferGraph};
+
+/// How tainted and clean funds mix inside a wallet
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TaintPolicy {
+    /// Any contact taints: once a wallet receives tainted funds, its whole
+    /// balance and everything it sends afterwards is tainted
+    Poison,
+    /// Every transfer carries the sender's current tainted share of its
+    /// balance
+    #[default]
+    Haircut,
+    /// Funds leave in the order they arrived
+    Fifo,
+    /// The most recently received funds leave first
+    Lifo,
+}
+
+/// Where taint starts and how it spreads, see [`TransferGraph::taint`]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct TaintConfig {
+    /// Tainted source addresses; everything they send is tainted
+    pub seeds: Vec<String>,
+    pub policy: TaintPolicy,
+}
+
+/// Taint attributed to one address
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeTaint {
+    pub address: String,
+    pub seed: bool,
+    pub received: TokenAmount,
+    pub tainted_received: TokenAmount,
+    pub sent: TokenAmount,
+    pub tainted_sent: TokenAmount,
+    /// Funds received and not sent on again
+    pub balance: TokenAmount,
+    pub tainted_balance: TokenAmount,
+}
+
+impl NodeTaint {
+    /// Tainted share of the balance, or of everything received once the
+    /// balance is spent; seeds are fully tainted
+    pub fn fraction(&self) -> f64 {
+        if self.seed {
+            1.0
+        } else if !self.balance.is_zero() {
+            self.tainted_balance.to_f64() / self.balance.to_f64()
+        } else if !self.received.is_zero() {
+            self.tainted_received.to_f64() / self.received.to_f64()
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Result of [`TransferGraph::taint`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaintReport {
+    pub policy: TaintPolicy,
+    /// Every address in the graph, sorted
+    pub nodes: Vec<NodeTaint>,
+    /// Transfers that moved nothing because their amount is negative,
+    /// cannot be expressed in the report's base units or would overflow a
+    /// wallet's totals; taint they carried is missing from `nodes`
+    pub skipped: Vec<Transfer>,
+}
+
+impl TaintReport {
+    pub fn get(&self, address: &str) -> Option<&NodeTaint> {
+        self.nodes
+            .binary_search_by(|n| n.address.as_str().cmp(address))
+            .ok()
+            .map(|i| &self.nodes[i])
+    }
+}
+
+impl TransferGraph {
+    /// Attribute taint from `config.seeds` to every address
+    ///
+    /// Transfers are replayed in chronological order (untimed transfers
+    /// last, see [`transfers_by_time`](TransferGraph::transfers_by_time)).
+    /// Each wallet's balance is split into tainted and clean funds according
+    /// to `config.policy`. When a wallet sends more than it has received,
+    /// the excess is assumed to be clean funds held from before the trace,
+    /// except for seeds, whose funds are always tainted. Amounts are exact
+    /// except that haircut shares round up to the nearest base unit, so the
+    /// tainted and clean parts of a transfer add up to its amount. Transfers
+    /// that cannot be replayed exactly are listed in
+    /// [`TaintReport::skipped`].
+    pub fn taint(&self, config: &TaintConfig) -> TaintReport {
+        let transfers = self.transfers_by_time();
+        let decimals = transfers
+            .iter()
+            .map(|t| t.amount.decimals)
+            .max()
+            .unwrap_or(0);
+
+        let mut wallets: HashMap<&str, Wallet> = HashMap::new();
+        for seed in &config.seeds {
+            wallets.entry(seed).or_default().seed = true;
+        }
+        let mut skipped = Vec::new();
+        for transfer in transfers {
+            // The receiver is checked first so that a transfer it cannot
+            // take leaves the sender untouched
+            let lots = base_units(transfer, decimals)
+                .filter(|&amount| {
+                    wallets
+                        .get(transfer.to.as_str())
+                        .is_none_or(|wallet| wallet.received.checked_add(amount).is_some())
+                })
+                .and_then(|amount| {
+                    wallets
+                        .entry(&transfer.from)
+                        .or_default()
+                        .send(config.policy, amount)
+                });
+            let received = lots.and_then(|lots| {
+                wallets
+                    .entry(&transfer.to)
+                    .or_default()
+                    .receive(config.policy, lots)
+            });
+            if received.is_none() {
+                skipped.push(transfer.clone());
+            }
+        }
+
+        let amount = |raw| TokenAmount::new(raw, decimals);
+        let mut nodes: Vec<NodeTaint> = self
+            .sorted_nodes()
+            .into_iter()
+            .map(|node| {
+                let wallet = wallets.remove(node.address.as_str()).unwrap_or_default();
+                NodeTaint {
+                    address: node.address.clone(),
+                    seed: wallet.seed,
+                    received: amount(wallet.received),
+                    tainted_received: amount(wallet.tainted_received),
+                    sent: amount(wallet.sent),
+                    tainted_sent: amount(wallet.tainted_sent),
+                    balance: amount(wallet.clean + wallet.tainted),
+                    tainted_balance: amount(wallet.tainted),
+                }
+            })
+            .collect();
+        // Seeds that never transacted are still reported
+        let mut idle: Vec<&str> = wallets.into_keys().collect();
+        idle.sort_unstable();
+        for address in idle {
+            nodes.push(NodeTaint {
+                address: address.to_string(),
+                seed: true,
+                received: TokenAmount::ZERO,
+                tainted_received: TokenAmount::ZERO,
+                sent: TokenAmount::ZERO,
+                tainted_sent: TokenAmount::ZERO,
+                balance: TokenAmount::ZERO,
+                tainted_balance: TokenAmount::ZERO,
+            });
+        }
+        nodes.sort_by(|a, b| a.address.cmp(&b.address));
+
+        TaintReport {
+            policy: config.policy,
+            nodes,
+            skipped,
+        }
+    }
+}
+
+/// Transfer amount in base units at `decimals`, or `None` if it cannot be
+/// rescaled or is negative
+fn base_units(transfer: &Transfer, decimals: u8) -> Option<i128> {
+    transfer
+        .amount
+        .rescale(decimals)
+        .map(|a| a.raw)
+        .filter(|raw| *raw >= 0)
+}
+
+/// A run of funds that is either entirely tainted or entirely clean
+#[derive(Debug, Clone, Copy)]
+struct Lot {
+    amount: i128,
+    tainted: bool,
+}
+
+#[derive(Debug, Default)]
+struct Wallet {
+    seed: bool,
+    poisoned: bool,
+    clean: i128,
+    tainted: i128,
+    /// Funds in arrival order, adjacent lots of the same kind merged; only
+    /// kept for FIFO and LIFO
+    lots: VecDeque<Lot>,
+    received: i128,
+    tainted_received: i128,
+    sent: i128,
+    tainted_sent: i128,
+}
+
+impl Wallet {
+    /// Take `amount` out of the wallet, returning the lots that leave, or
+    /// `None` without touching the wallet if its totals would overflow
+    fn send(&mut self, policy: TaintPolicy, amount: i128) -> Option<Vec<Lot>> {
+        let sent = self.sent.checked_add(amount)?;
+        let held = self.clean + self.tainted;
+        let taken = amount.min(held);
+        let mut lots = match policy {
+            TaintPolicy::Poison => vec![Lot {
+                amount: taken,
+                tainted: self.poisoned || self.seed,
+            }],
+            TaintPolicy::Haircut => {
+                let tainted = share(taken, self.tainted, held);
+                vec![
+                    Lot {
+                        amount: tainted,
+                        tainted: true,
+                    },
+                    Lot {
+                        amount: taken - tainted,
+                        tainted: false,
+                    },
+                ]
+            }
+            TaintPolicy::Fifo | TaintPolicy::Lifo => self.take_lots(policy, taken),
+        };
+
+        let tainted_taken: i128 = lots.iter().filter(|l| l.tainted).map(|l| l.amount).sum();
+        self.tainted -= tainted_taken;
+        self.clean -= taken - tainted_taken;
+
+        // Funds beyond what the trace saw arriving
+        lots.push(Lot {
+            amount: amount - taken,
+            tainted: self.seed || self.poisoned,
+        });
+        lots.retain(|lot| lot.amount > 0);
+        self.sent = sent;
+        for lot in lots.iter().filter(|lot| lot.tainted) {
+            self.tainted_sent = self.tainted_sent.checked_add(lot.amount)?;
+        }
+        Some(lots)
+    }
+
+    fn take_lots(&mut self, policy: TaintPolicy, mut amount: i128) -> Vec<Lot> {
+        let mut taken = Vec::new();
+        while amount > 0 {
+            let lot = match policy {
+                TaintPolicy::Lifo => self.lots.back_mut(),
+                _ => self.lots.front_mut(),
+            };
+            let Some(lot) = lot else {
+                break;
+            };
+            let part = lot.amount.min(amount);
+            lot.amount -= part;
+            amount -= part;
+            taken.push(Lot {
+                amount: part,
+                tainted: lot.tainted,
+            });
+            if lot.amount == 0 {
+                match policy {
+                    TaintPolicy::Lifo => self.lots.pop_back(),
+                    _ => self.lots.pop_front(),
+                };
+            }
+        }
+        taken
+    }
+
+    /// Add `lots` to the wallet, or `None` if its totals would overflow
+    ///
+    /// Balances never exceed what was received, so once `received` has
+    /// room for the lots nothing else can overflow.
+    fn receive(&mut self, policy: TaintPolicy, lots: Vec<Lot>) -> Option<()> {
+        for lot in lots {
+            self.received = self.received.checked_add(lot.amount)?;
+            if lot.tainted {
+                self.tainted_received = self.tainted_received.checked_add(lot.amount)?;
+            }
+            // Whatever a seed or a poisoned wallet holds counts as tainted
+            let tainted = lot.tainted || self.seed || self.poisoned;
+            if tainted {
+                self.tainted = self.tainted.checked_add(lot.amount)?;
+            } else {
+                self.clean = self.clean.checked_add(lot.amount)?;
+            }
+            if matches!(policy, TaintPolicy::Fifo | TaintPolicy::Lifo) {
+                match self.lots.back_mut() {
+                    Some(last) if last.tainted == tainted => {
+                        last.amount = last.amount.checked_add(lot.amount)?;
+                    }
+                    _ => self.lots.push_back(Lot {
+                        amount: lot.amount,
+                        tainted,
+                    }),
+                }
+            }
+        }
+        if policy == TaintPolicy::Poison && self.tainted > 0 && !self.poisoned {
+            self.poisoned = true;
+            self.tainted = self.tainted.checked_add(self.clean)?;
+            self.clean = 0;
+        }
+        Some(())
+    }
+}
+
+/// `amount * part / whole`, rounded up
+fn share(amount: i128, part: i128, whole: i128) -> i128 {
+    if whole == 0 {
+        return 0;
+    }
+    match amount.checked_mul(part) {
+        Some(product) => product / whole + i128::from(product % whole != 0),
+        None => ((amount as f64 * part as f64 / whole as f64).ceil() as i128).min(part),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str, amount: u64, timestamp: &str) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: Some(timestamp.parse().unwrap()),
+            slot: None,
+            note: None,
+        }
+    }
+
+    /// B receives 40 clean from C, then 60 tainted from the seed A, then
+    /// sends 50 to D and finally 30 to E
+    fn graph() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("C", "B", 40, "2024-01-01 00:00"));
+        graph.add_transfer(transfer("A", "B", 60, "2024-01-01 01:00"));
+        graph.add_transfer(transfer("B", "E", 30, "2024-01-01 03:00"));
+        graph.add_transfer(transfer("B", "D", 50, "2024-01-01 02:00"));
+        graph
+    }
+
+    fn tainted(report: &TaintReport, address: &str) -> (u64, u64) {
+        let node = report.get(address).unwrap();
+        (
+            node.tainted_received.raw as u64,
+            node.tainted_balance.raw as u64,
+        )
+    }
+
+    fn run(policy: TaintPolicy) -> TaintReport {
+        graph().taint(&TaintConfig {
+            seeds: vec!["A".to_string()],
+            policy,
+        })
+    }
+
+    #[test]
+    fn test_taint_policies() {
+        let poison = run(TaintPolicy::Poison);
+        assert_eq!(tainted(&poison, "B"), (60, 20));
+        assert_eq!(tainted(&poison, "D"), (50, 50));
+        assert_eq!(tainted(&poison, "E"), (30, 30));
+        assert_eq!(tainted(&poison, "C"), (0, 0));
+
+        let haircut = run(TaintPolicy::Haircut);
+        assert_eq!(tainted(&haircut, "B"), (60, 12));
+        assert_eq!(tainted(&haircut, "D"), (30, 30));
+        assert_eq!(tainted(&haircut, "E"), (18, 18));
+        assert_eq!(haircut.get("B").unwrap().fraction(), 0.6);
+
+        let fifo = run(TaintPolicy::Fifo);
+        assert_eq!(tainted(&fifo, "B"), (60, 20));
+        assert_eq!(tainted(&fifo, "D"), (10, 10));
+        assert_eq!(tainted(&fifo, "E"), (30, 30));
+
+        let lifo = run(TaintPolicy::Lifo);
+        assert_eq!(tainted(&lifo, "B"), (60, 0));
+        assert_eq!(tainted(&lifo, "D"), (50, 50));
+        assert_eq!(tainted(&lifo, "E"), (10, 10));
+        assert_eq!(lifo.get("E").unwrap().fraction(), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn test_haircut_rounds_towards_taint() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 1, "2024-01-01 00:00"));
+        graph.add_transfer(transfer("C", "B", 2, "2024-01-01 01:00"));
+        graph.add_transfer(transfer("B", "D", 1, "2024-01-01 02:00"));
+        graph.add_transfer(transfer("B", "E", 1, "2024-01-01 03:00"));
+        let report = graph.taint(&TaintConfig {
+            seeds: vec!["A".to_string()],
+            policy: TaintPolicy::Haircut,
+        });
+
+        // A third of B is tainted: the first unit out carries the taint
+        // rather than losing it to rounding
+        assert_eq!(tainted(&report, "D"), (1, 1));
+        assert_eq!(tainted(&report, "E"), (0, 0));
+        assert_eq!(tainted(&report, "B"), (1, 0));
+        assert!(report.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_taint_skips_unscalable_amounts() {
+        let mut graph = graph();
+        // No i128 holds this many units at 18 decimals
+        graph.add_transfer(Transfer {
+            amount: TokenAmount::new(i128::MAX / 2, 0),
+            ..transfer("A", "F", 0, "2024-01-01 04:00")
+        });
+        graph.add_transfer(Transfer {
+            amount: TokenAmount::new(1, 18),
+            ..transfer("C", "G", 0, "2024-01-01 05:00")
+        });
+        let report = graph.taint(&TaintConfig {
+            seeds: vec!["A".to_string()],
+            policy: TaintPolicy::Haircut,
+        });
+
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].to, "F");
+        assert!(report.get("F").unwrap().received.is_zero());
+        assert_eq!(report.get("G").unwrap().received, TokenAmount::new(1, 18));
+    }
+
+    #[test]
+    fn test_poisoned_wallet_taints_later_deposits() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("S", "W", 10, "2024-01-01 00:00"));
+        graph.add_transfer(transfer("C", "W", 5, "2024-01-01 01:00"));
+        graph.add_transfer(transfer("W", "X", 12, "2024-01-01 02:00"));
+        let report = graph.taint(&TaintConfig {
+            seeds: vec!["S".to_string()],
+            policy: TaintPolicy::Poison,
+        });
+
+        let wallet = report.get("W").unwrap();
+        assert_eq!(wallet.balance, TokenAmount::from(3));
+        assert_eq!(wallet.tainted_balance, TokenAmount::from(3));
+        assert_eq!(wallet.fraction(), 1.0);
+        assert_eq!(tainted(&report, "X"), (12, 12));
+    }
+
+    #[test]
+    fn test_taint_skips_overflowing_totals() {
+        let mut graph = TransferGraph::new();
+        let half = Transfer {
+            amount: TokenAmount::new(i128::MAX / 2 + 1, 0),
+            ..transfer("A", "B", 0, "2024-01-01 00:00")
+        };
+        graph.add_transfer(half.clone());
+        graph.add_transfer(Transfer {
+            timestamp: Some("2024-01-01 01:00".parse().unwrap()),
+            ..half
+        });
+        let report = graph.taint(&TaintConfig {
+            seeds: vec!["A".to_string()],
+            policy: TaintPolicy::Fifo,
+        });
+
+        assert_eq!(report.skipped.len(), 1);
+        let sender = report.get("A").unwrap();
+        assert_eq!(sender.sent, TokenAmount::new(i128::MAX / 2 + 1, 0));
+        assert_eq!(report.get("B").unwrap().received, sender.sent);
+    }
+
+    #[test]
+    fn test_taint_seeds_and_unseen_funds() {
+        let mut graph = graph();
+        // B sends more than it ever received: the excess is clean
+        graph.add_transfer(transfer("B", "F", 100, "2024-01-02"));
+        let report = graph.taint(&TaintConfig {
+            seeds: vec!["A".to_string(), "Z".to_string()],
+            policy: TaintPolicy::Haircut,
+        });
+
+        let a = report.get("A").unwrap();
+        assert!(a.seed);
+        assert_eq!(a.fraction(), 1.0);
+        assert_eq!(a.tainted_sent, TokenAmount::from(60));
+
+        let f = report.get("F").unwrap();
+        assert_eq!(f.received, TokenAmount::from(100));
+        assert_eq!(f.tainted_received, TokenAmount::from(12));
+
+        let b = report.get("B").unwrap();
+        assert_eq!(b.balance, TokenAmount::ZERO);
+        assert_eq!(b.fraction(), 0.6);
+
+        assert!(report.get("Z").unwrap().seed);
+        assert!(report.nodes.windows(2).all(|w| w[0].address < w[1].address));
+    }
+}
@@ -0,0 +1,346 @@
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use crate::{PathIter, PathLimit, PathLimits, Timestamp, Transfer, TransferGraph, TransferPath};
+
+/// Time constraints for [`TransferGraph::iter_temporal_paths`]
+#[derive(Debug, Clone, Default)]
+pub struct TemporalConfig {
+    /// Longest time funds may sit at an intermediate address between
+    /// arriving and leaving
+    pub max_dwell: Option<Duration>,
+    /// Longest time between the first and the last hop
+    pub max_duration: Option<Duration>,
+}
+
+/// A path whose hops happen in chronological order
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemporalPath<'a> {
+    pub path: TransferPath<'a>,
+    /// Time between each hop and the next; one entry fewer than hops, and
+    /// `None` where the two hops were ordered by slot alone
+    pub latencies: Vec<Option<Duration>>,
+}
+
+impl TemporalPath<'_> {
+    /// Time of the first hop, if it has one
+    pub fn start(&self) -> Option<Timestamp> {
+        self.path.hops[0].timestamp
+    }
+
+    /// Time of the last hop, if it has one
+    pub fn end(&self) -> Option<Timestamp> {
+        self.path.hops[self.path.len() - 1].timestamp
+    }
+
+    /// Time between the first and the last hop, if both have a timestamp
+    pub fn duration(&self) -> Option<Duration> {
+        Some(elapsed(self.start()?, self.end()?))
+    }
+}
+
+impl TemporalConfig {
+    /// Whether `next` can carry on from the funds brought in by the last of
+    /// `hops`: it must leave no earlier than they arrived (see
+    /// [`in_order`]) and stay within the dwell and duration limits, which
+    /// only hops with timestamps can be shown to keep
+    pub(crate) fn can_follow(&self, hops: &[&Transfer], next: &Transfer) -> bool {
+        if !placed_in_time(next) {
+            return false;
+        }
+        let (Some(first), Some(prev)) = (hops.first(), hops.last()) else {
+            return true;
+        };
+        let within = |limit: Option<Duration>, since: &Transfer| {
+            limit.is_none_or(|max| match (since.timestamp, next.timestamp) {
+                (Some(a), Some(b)) => elapsed(a, b) <= max,
+                _ => false,
+            })
+        };
+        in_order(prev, next) == Some(true)
+            && within(self.max_dwell, prev)
+            && within(self.max_duration, first)
+    }
+}
+
+/// Whether `transfer` has a timestamp or a slot to order it by
+pub(crate) fn placed_in_time(transfer: &Transfer) -> bool {
+    transfer.timestamp.is_some() || transfer.slot.is_some()
+}
+
+/// Whether `next` happens no earlier than `prev`: by timestamp when both
+/// have one (ties broken by slot when both have one), otherwise by slot;
+/// `None` when the two cannot be ordered
+pub(crate) fn in_order(prev: &Transfer, next: &Transfer) -> Option<bool> {
+    match (prev.timestamp, next.timestamp) {
+        (Some(arrived), Some(leaves)) => Some(match leaves.cmp(&arrived) {
+            Ordering::Less => false,
+            Ordering::Greater => true,
+            Ordering::Equal => match (prev.slot, next.slot) {
+                (Some(arrived), Some(leaves)) => leaves >= arrived,
+                _ => true,
+            },
+        }),
+        _ => Some(next.slot? >= prev.slot?),
+    }
+}
+
+/// Lazy enumeration of time-respecting paths, see
+/// [`TransferGraph::iter_temporal_paths`]
+#[derive(Debug)]
+pub struct TemporalPathIter<'a> {
+    paths: PathIter<'a>,
+}
+
+impl TemporalPathIter<'_> {
+    /// The limit that cut the search short, if any. Only final once the
+    /// iterator has returned `None`.
+    pub fn limit_reached(&self) -> Option<PathLimit> {
+        self.paths.limit_reached()
+    }
+
+    /// Transfers examined so far
+    pub fn steps(&self) -> usize {
+        self.paths.steps()
+    }
+}
+
+impl<'a> Iterator for TemporalPathIter<'a> {
+    type Item = TemporalPath<'a>;
+
+    fn next(&mut self) -> Option<TemporalPath<'a>> {
+        let path = self.paths.next()?;
+        let latencies = path
+            .hops
+            .windows(2)
+            .map(|pair| Some(elapsed(pair[0].timestamp?, pair[1].timestamp?)))
+            .collect();
+        Some(TemporalPath { path, latencies })
+    }
+}
+
+impl TransferGraph {
+    /// Find every time-respecting path from `from` to `to`
+    ///
+    /// Every path is materialised without limits; use
+    /// [`iter_temporal_paths`](TransferGraph::iter_temporal_paths) on graphs
+    /// of unknown size.
+    pub fn find_temporal_paths(
+        &self,
+        from: &str,
+        to: &str,
+        config: &TemporalConfig,
+    ) -> Vec<TemporalPath<'_>> {
+        self.iter_temporal_paths(from, to, config, &PathLimits::unbounded())
+            .collect()
+    }
+
+    /// Lazily enumerate time-respecting paths from `from` to `to` within
+    /// `limits`
+    ///
+    /// Unlike [`iter_paths`](TransferGraph::iter_paths), each hop must happen
+    /// at or after the hop that brought the funds in, so every result is a
+    /// possible causal chain. Hops are compared by timestamp (ties broken by
+    /// slot when both have one), or by slot when either lacks a timestamp.
+    /// Transfers with neither are never used, nor is a hop that cannot be
+    /// ordered against the one before it (a timestamp without a slot after
+    /// a slot without a timestamp, or the other way round). Dwell and
+    /// duration limits only admit hops with timestamps. Paths are
+    /// simple except that `to` may equal `from`, which finds round trips.
+    /// At each address, later hops are tried in chronological order. Check
+    /// [`TemporalPathIter::limit_reached`] afterwards to learn whether the
+    /// results are complete.
+    pub fn iter_temporal_paths(
+        &self,
+        from: &str,
+        to: &str,
+        config: &TemporalConfig,
+        limits: &PathLimits,
+    ) -> TemporalPathIter<'_> {
+        TemporalPathIter {
+            paths: PathIter::new(self, from, to, limits, Some(config.clone())),
+        }
+    }
+}
+
+/// Non-negative time from `a` to `b`
+fn elapsed(a: Timestamp, b: Timestamp) -> Duration {
+    Duration::from_secs(b.unix().saturating_sub(a.unix()).max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TokenAmount;
+
+    fn transfer(from: &str, to: &str, amount: u64, timestamp: &str) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: Some(timestamp.parse().unwrap()),
+            slot: None,
+            note: None,
+        }
+    }
+
+    fn addresses(paths: &[TemporalPath]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.path.addresses().join(" -> "))
+            .collect()
+    }
+
+    #[test]
+    fn test_temporal_paths_respect_time() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 100, "2024-01-01 10:00:00"));
+        // Left B before the funds arrived: structurally a path, causally not
+        graph.add_transfer(transfer("B", "C", 50, "2024-01-01 09:00:00"));
+        graph.add_transfer(transfer("B", "C", 40, "2024-01-01 12:30:00"));
+        graph.add_transfer(transfer("A", "D", 10, "2024-01-02"));
+        graph.add_transfer(transfer("D", "C", 10, "2024-01-01"));
+        graph.add_transfer(Transfer {
+            timestamp: None,
+            ..transfer("A", "C", 1, "2024-01-01")
+        });
+
+        assert_eq!(graph.find_paths("A", "C").len(), 4);
+
+        let paths = graph.find_temporal_paths("A", "C", &TemporalConfig::default());
+        assert_eq!(addresses(&paths), ["A -> B -> C"]);
+        assert_eq!(paths[0].path.hops[1].amount, TokenAmount::from(40));
+        assert_eq!(paths[0].latencies, [Some(Duration::from_secs(9000))]);
+        assert_eq!(paths[0].duration(), Some(Duration::from_secs(9000)));
+        assert_eq!(paths[0].start(), Some("2024-01-01 10:00:00".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_temporal_paths_limits() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 100, "2024-01-01 00:00:00"));
+        graph.add_transfer(transfer("B", "C", 60, "2024-01-01 00:10:00"));
+        graph.add_transfer(transfer("C", "D", 50, "2024-01-01 02:10:00"));
+        graph.add_transfer(transfer("B", "D", 30, "2024-01-01 01:00:00"));
+        graph.add_transfer(transfer("D", "A", 5, "2024-01-01 03:00:00"));
+
+        let all = graph.find_temporal_paths("A", "D", &TemporalConfig::default());
+        assert_eq!(addresses(&all), ["A -> B -> C -> D", "A -> B -> D"]);
+
+        let dwell = TemporalConfig {
+            max_dwell: Some(Duration::from_secs(3600)),
+            ..TemporalConfig::default()
+        };
+        let paths = graph.find_temporal_paths("A", "D", &dwell);
+        assert_eq!(addresses(&paths), ["A -> B -> D"]);
+
+        let total = TemporalConfig {
+            max_duration: Some(Duration::from_secs(1800)),
+            ..TemporalConfig::default()
+        };
+        assert!(graph.find_temporal_paths("A", "D", &total).is_empty());
+
+        let round_trips = graph.find_temporal_paths("A", "A", &TemporalConfig::default());
+        assert_eq!(
+            addresses(&round_trips),
+            ["A -> B -> C -> D -> A", "A -> B -> D -> A"]
+        );
+        assert_eq!(
+            round_trips[1].latencies,
+            [Some(Duration::from_secs(3600)), Some(Duration::from_secs(7200))]
+        );
+    }
+
+    #[test]
+    fn test_iter_temporal_paths_bounded() {
+        // Five parallel transfers per layer, each later than the last layer
+        let mut graph = TransferGraph::new();
+        for (layer, (from, to)) in [("A", "B"), ("B", "C"), ("C", "D")].iter().enumerate() {
+            for minute in 0..5 {
+                let time = format!("2024-01-0{} 00:0{}", layer + 1, minute);
+                graph.add_transfer(transfer(from, to, 1, &time));
+            }
+        }
+        let config = TemporalConfig::default();
+        assert_eq!(graph.find_temporal_paths("A", "D", &config).len(), 125);
+
+        let limits = PathLimits {
+            max_paths: Some(10),
+            ..PathLimits::unbounded()
+        };
+        let mut search = graph.iter_temporal_paths("A", "D", &config, &limits);
+        assert_eq!(search.by_ref().count(), 10);
+        assert_eq!(search.limit_reached(), Some(PathLimit::Paths));
+
+        let limits = PathLimits {
+            max_steps: Some(20),
+            ..PathLimits::unbounded()
+        };
+        let mut search = graph.iter_temporal_paths("A", "D", &config, &limits);
+        assert!(search.by_ref().count() < 125);
+        assert_eq!(search.limit_reached(), Some(PathLimit::Steps));
+        assert_eq!(search.steps(), 20);
+
+        let limits = PathLimits {
+            max_depth: Some(2),
+            ..PathLimits::unbounded()
+        };
+        let mut search = graph.iter_temporal_paths("A", "D", &config, &limits);
+        assert_eq!(search.by_ref().count(), 0);
+        assert_eq!(search.limit_reached(), Some(PathLimit::Depth));
+    }
+
+    #[test]
+    fn test_temporal_paths_slot_ties() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(Transfer {
+            slot: Some(10),
+            ..transfer("A", "B", 1, "2024-01-01")
+        });
+        graph.add_transfer(Transfer {
+            slot: Some(9),
+            ..transfer("B", "C", 1, "2024-01-01")
+        });
+        graph.add_transfer(Transfer {
+            slot: Some(10),
+            ..transfer("B", "D", 1, "2024-01-01")
+        });
+
+        let config = TemporalConfig::default();
+        assert!(graph.find_temporal_paths("A", "C", &config).is_empty());
+        assert_eq!(graph.find_temporal_paths("A", "D", &config).len(), 1);
+    }
+
+    #[test]
+    fn test_temporal_paths_by_slot() {
+        let slotted = |from, to, slot| Transfer {
+            timestamp: None,
+            slot: Some(slot),
+            ..transfer(from, to, 1, "2024-01-01")
+        };
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(slotted("A", "B", 10));
+        graph.add_transfer(slotted("B", "C", 9));
+        graph.add_transfer(slotted("B", "C", 12));
+        graph.add_transfer(Transfer {
+            slot: Some(20),
+            ..transfer("C", "D", 1, "2024-01-02")
+        });
+
+        let config = TemporalConfig::default();
+        let paths = graph.find_temporal_paths("A", "D", &config);
+        assert_eq!(addresses(&paths), ["A -> B -> C -> D"]);
+        assert_eq!(paths[0].path.hops[1].slot, Some(12));
+        assert_eq!(paths[0].latencies, [None, None]);
+        assert_eq!(paths[0].start(), None);
+        assert_eq!(paths[0].duration(), None);
+
+        // Limits need timestamps to be checked
+        let dwell = TemporalConfig {
+            max_dwell: Some(Duration::from_secs(3600)),
+            ..TemporalConfig::default()
+        };
+        assert!(graph.find_temporal_paths("A", "C", &dwell).is_empty());
+    }
+}
@@ -0,0 +1,453 @@
+use std::io::IsTerminal;
+
+/// Characters the ASCII renderer draws lines, boxes and icons with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Charset {
+    /// Box-drawing characters, arrows and the configured icons
+    #[default]
+    Unicode,
+    /// Plain `-`, `|`, `+` and `>`, icons without emoji, and `?` for any
+    /// other non-ASCII character, for logs and CI that mangle Unicode
+    Ascii,
+}
+
+/// When the ASCII renderer colours its output with ANSI escapes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ColorMode {
+    #[default]
+    Never,
+    /// When stdout is a terminal and `NO_COLOR` is unset or empty
+    Auto,
+    Always,
+}
+
+impl ColorMode {
+    /// Whether output rendered now should be coloured
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorMode::Never => false,
+            ColorMode::Always => true,
+            ColorMode::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// What a coloured span of output shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Style {
+    Origin,
+    Target,
+    Label,
+    Amount,
+}
+
+impl Style {
+    /// `text` wrapped in the ANSI escapes of this style
+    pub(crate) fn paint(self, text: &str) -> String {
+        format!("{}{}{}", self.code(), text, RESET)
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            Style::Origin => "\x1b[1;32m",
+            Style::Target => "\x1b[1;31m",
+            Style::Label => "\x1b[36m",
+            Style::Amount => "\x1b[1;33m",
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+/// Columns `text` takes up in a terminal
+///
+/// Wide characters such as CJK and most emoji count two columns; combining
+/// marks, zero-width joiners, variation selectors and ANSI escape sequences
+/// count none.
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI sequences end with a byte in `@`..=`~`
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        width += char_width(c);
+    }
+    width
+}
+
+/// Columns a single character takes up, see [`display_width`]
+pub(crate) fn char_width(c: char) -> usize {
+    let code = c as u32;
+    if code < 0x20 || (0x7f..0xa0).contains(&code) {
+        return 0;
+    }
+    if ZERO_WIDTH.iter().any(|range| range.contains(&code)) {
+        0
+    } else if WIDE.iter().any(|range| range.contains(&code)) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Combining marks and invisible formatting characters
+const ZERO_WIDTH: &[std::ops::RangeInclusive<u32>] = &[
+    0x0300..=0x036f,
+    0x0483..=0x0489,
+    0x0591..=0x05bd,
+    0x0610..=0x061a,
+    0x064b..=0x065f,
+    0x0e31..=0x0e31,
+    0x0e34..=0x0e3a,
+    0x1ab0..=0x1aff,
+    0x1dc0..=0x1dff,
+    0x200b..=0x200f,
+    0x2060..=0x2064,
+    0x20d0..=0x20ff,
+    0xfe00..=0xfe0f,
+    0xfe20..=0xfe2f,
+    0xfeff..=0xfeff,
+    0x1f3fb..=0x1f3ff,
+    0xe0000..=0xe01ef,
+];
+
+/// East Asian wide and fullwidth characters and emoji shown as pictures
+const WIDE: &[std::ops::RangeInclusive<u32>] = &[
+    0x1100..=0x115f,
+    0x231a..=0x231b,
+    0x2329..=0x232a,
+    0x23e9..=0x23ec,
+    0x23f0..=0x23f0,
+    0x23f3..=0x23f3,
+    0x25fd..=0x25fe,
+    0x2614..=0x2615,
+    0x2648..=0x2653,
+    0x267f..=0x267f,
+    0x2693..=0x2693,
+    0x26a1..=0x26a1,
+    0x26aa..=0x26ab,
+    0x26bd..=0x26be,
+    0x26c4..=0x26c5,
+    0x26ce..=0x26ce,
+    0x26d4..=0x26d4,
+    0x26ea..=0x26ea,
+    0x26f2..=0x26f3,
+    0x26f5..=0x26f5,
+    0x26fa..=0x26fa,
+    0x26fd..=0x26fd,
+    0x2705..=0x2705,
+    0x270a..=0x270b,
+    0x2728..=0x2728,
+    0x274c..=0x274c,
+    0x274e..=0x274e,
+    0x2753..=0x2755,
+    0x2757..=0x2757,
+    0x2795..=0x2797,
+    0x27b0..=0x27b0,
+    0x27bf..=0x27bf,
+    0x2b1b..=0x2b1c,
+    0x2b50..=0x2b50,
+    0x2b55..=0x2b55,
+    0x2e80..=0x303e,
+    0x3041..=0x33ff,
+    0x3400..=0x4dbf,
+    0x4e00..=0x9fff,
+    0xa000..=0xa4cf,
+    0xa960..=0xa97f,
+    0xac00..=0xd7a3,
+    0xf900..=0xfaff,
+    0xfe10..=0xfe19,
+    0xfe30..=0xfe6f,
+    0xff00..=0xff60,
+    0xffe0..=0xffe6,
+    0x1f004..=0x1f004,
+    0x1f0cf..=0x1f0cf,
+    0x1f18e..=0x1f18e,
+    0x1f191..=0x1f19a,
+    0x1f200..=0x1f251,
+    0x1f300..=0x1f320,
+    0x1f32d..=0x1f335,
+    0x1f337..=0x1f37c,
+    0x1f37e..=0x1f393,
+    0x1f3a0..=0x1f3ca,
+    0x1f3cf..=0x1f3d3,
+    0x1f3e0..=0x1f3f0,
+    0x1f3f4..=0x1f3f4,
+    0x1f3f8..=0x1f43e,
+    0x1f440..=0x1f440,
+    0x1f442..=0x1f4fc,
+    0x1f4ff..=0x1f53d,
+    0x1f54b..=0x1f54e,
+    0x1f550..=0x1f567,
+    0x1f57a..=0x1f57a,
+    0x1f595..=0x1f596,
+    0x1f5a4..=0x1f5a4,
+    0x1f5fb..=0x1f64f,
+    0x1f680..=0x1f6c5,
+    0x1f6cc..=0x1f6cc,
+    0x1f6d0..=0x1f6d2,
+    0x1f6d5..=0x1f6d7,
+    0x1f6eb..=0x1f6ec,
+    0x1f6f4..=0x1f6fc,
+    0x1f7e0..=0x1f7eb,
+    0x1f90c..=0x1f93a,
+    0x1f93c..=0x1f945,
+    0x1f947..=0x1f9ff,
+    0x1fa70..=0x1faff,
+    0x20000..=0x2fffd,
+    0x30000..=0x3fffd,
+];
+
+/// Shorten `text` to at most `max` columns, marking the cut with `…`
+pub(crate) fn clip(text: &str, max: usize) -> String {
+    if display_width(text) <= max {
+        return text.to_string();
+    }
+    let mut clipped = String::new();
+    let mut width = 0;
+    for c in text.chars() {
+        let w = char_width(c);
+        if width + w + 1 > max {
+            break;
+        }
+        width += w;
+        clipped.push(c);
+    }
+    clipped.push('…');
+    clipped
+}
+
+/// Width of the current terminal in columns, if it can be told
+///
+/// `COLUMNS` wins when set; otherwise the size of the terminal on stdout is
+/// asked for on Linux and macOS.
+pub fn terminal_width() -> Option<usize> {
+    let columns = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.trim().parse().ok())
+        .filter(|&columns| columns > 0);
+    columns.or_else(tty_columns)
+}
+
+#[cfg(any(
+    all(
+        target_os = "linux",
+        any(
+            target_arch = "x86_64",
+            target_arch = "x86",
+            target_arch = "aarch64",
+            target_arch = "arm"
+        )
+    ),
+    target_os = "macos"
+))]
+fn tty_columns() -> Option<usize> {
+    use std::ffi::{c_int, c_ulong, c_ushort};
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct WindowSize {
+        rows: c_ushort,
+        columns: c_ushort,
+        x_pixels: c_ushort,
+        y_pixels: c_ushort,
+    }
+
+    #[cfg(target_os = "linux")]
+    const TIOCGWINSZ: c_ulong = 0x5413;
+    #[cfg(target_os = "macos")]
+    const TIOCGWINSZ: c_ulong = 0x4008_7468;
+
+    unsafe extern "C" {
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    }
+
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let mut size = WindowSize::default();
+    // SAFETY: TIOCGWINSZ only writes a `struct winsize` through the pointer
+    let status = unsafe { ioctl(1, TIOCGWINSZ, &mut size as *mut WindowSize) };
+    (status == 0 && size.columns > 0).then_some(size.columns as usize)
+}
+
+#[cfg(not(any(
+    all(
+        target_os = "linux",
+        any(
+            target_arch = "x86_64",
+            target_arch = "x86",
+            target_arch = "aarch64",
+            target_arch = "arm"
+        )
+    ),
+    target_os = "macos"
+)))]
+fn tty_columns() -> Option<usize> {
+    None
+}
+
+/// `text` in plain ASCII, each character replaced by one taking up as many
+/// columns, so boxes and connectors stay aligned
+pub(crate) fn to_ascii(text: &str) -> String {
+    let mut ascii = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            c if c.is_ascii() => ascii.push(c),
+            '─' | '━' | '–' | '—' => ascii.push('-'),
+            '═' => ascii.push('='),
+            '│' | '║' => ascii.push('|'),
+            '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼' | '╔' | '╗' | '╚' | '╝' => {
+                ascii.push('+')
+            }
+            '→' => ascii.push('>'),
+            '←' => ascii.push('<'),
+            '↺' => ascii.push('^'),
+            '○' | '•' => ascii.push('o'),
+            '…' => ascii.push('~'),
+            '×' => ascii.push('x'),
+            c => ascii.push_str(&"?".repeat(char_width(c))),
+        }
+    }
+    ascii
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsciiConfig, HubConfig, RenderConfig, TokenAmount, Transfer, TransferGraph};
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("ORIGIN"), 6);
+        assert_eq!(display_width("🏦 ORIGIN"), 9);
+        assert_eq!(display_width("○ ─→"), 4);
+        assert_eq!(display_width("日本"), 4);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width("\x1b[1;32mgreen\x1b[0m"), 5);
+        assert_eq!(clip("🏦 ORIGIN", 6), "🏦 OR…");
+        assert_eq!(clip("日本語", 5), "日本…");
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        assert_eq!(
+            to_ascii("├──────→ [1.00 T] ↺ see #3 above"),
+            "+------> [1.00 T] ^ see #3 above"
+        );
+        assert_eq!(to_ascii("╔═╗ 🏦"), "+=+ ??");
+    }
+
+    fn graph(config: RenderConfig) -> TransferGraph {
+        let mut graph = TransferGraph::with_config(config);
+        graph.origin = Some("Origin".to_string());
+        graph.target = Some("Target".to_string());
+        for (from, to, amount) in [
+            ("Origin", "Hub", 100),
+            ("Hub", "Target", 60),
+            ("Hub", "Other", 40),
+        ] {
+            graph.add_transfer(Transfer {
+                from: from.to_string(),
+                to: to.to_string(),
+                amount: TokenAmount::from(amount),
+                token_symbol: "T".to_string(),
+                timestamp: None,
+                slot: None,
+                note: None,
+            });
+        }
+        graph.set_node_label("Hub", "Mixer".to_string());
+        graph
+    }
+
+    #[test]
+    fn test_ascii_charset() {
+        let output = graph(RenderConfig {
+            ascii: AsciiConfig {
+                charset: Charset::Ascii,
+                ..AsciiConfig::default()
+            },
+            ..RenderConfig::default()
+        })
+        .render_ascii();
+        assert!(output.is_ascii(), "{}", output);
+        assert!(output.contains("\nORIGIN Origin\n"));
+        assert!(output.contains("+------> [100.00 T] --------> Hub\n"));
+        assert!(output.contains("\n+-----"));
+        assert!(output.contains("| Total Nodes:"));
+    }
+
+    #[test]
+    fn test_colors() {
+        let plain = graph(RenderConfig::default()).render_ascii();
+        assert!(!plain.contains('\x1b'));
+
+        let output = graph(RenderConfig {
+            ascii: AsciiConfig {
+                color: ColorMode::Always,
+                ..AsciiConfig::default()
+            },
+            hubs: HubConfig {
+                count: 1,
+                ..HubConfig::default()
+            },
+            ..RenderConfig::default()
+        })
+        .render_ascii();
+        assert!(output.contains("\x1b[1;32m🏦 ORIGIN\x1b[0m Origin"));
+        assert!(output.contains("\x1b[1;31m🎯 TARGET\x1b[0m Target"));
+        assert!(output.contains("○ \x1b[36mMixer\x1b[0m Hub"));
+        // Only the largest of three transfers is large
+        assert!(output.contains("[\x1b[1;33m100.00 T\x1b[0m]"));
+        assert!(output.contains("[60.00 T]"));
+        // Colour does not shift the stats box
+        let row = output.lines().find(|l| l.contains("Top 1 Hubs")).unwrap();
+        assert_eq!(display_width(row), 75);
+    }
+
+    #[test]
+    fn test_narrow_frame() {
+        let output = graph(RenderConfig {
+            ascii: AsciiConfig {
+                max_width: Some(50),
+                ..AsciiConfig::default()
+            },
+            title: "🏦 A TITLE WITH AN EMOJI".to_string(),
+            ..RenderConfig::default()
+        })
+        .render_ascii();
+        let frame: Vec<&str> = output
+            .lines()
+            .filter(|l| l.starts_with(['╔', '║', '╚', '┌', '│', '└']))
+            .collect();
+        assert!(frame.len() > 6);
+        for line in &frame {
+            let width = display_width(line);
+            assert!(width == 50 || width == 49, "{}", line);
+        }
+        assert!(output.contains("║            🏦 A TITLE WITH AN EMOJI            ║"));
+    }
+}
@@ -0,0 +1,595 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{GraphNode, Transfer, TransferGraph};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Point in time as whole seconds since the Unix epoch, in UTC
+///
+/// Parses the formats seen in explorer exports and hand-written traces:
+///
+/// * `Dec 26, 2024 10:51:22 UTC` (the time, seconds and `UTC` are optional)
+/// * `2024-01-01`, `2024-01-01 12:00:00`, `2024-01-01T12:00:00Z`,
+///   `2024-01-01T12:00:00.000Z` (JavaScript's `toISOString()`)
+/// * a time followed by a numeric offset such as `+02:00`, `-0500` or `+02`
+/// * Unix seconds with at least five digits such as `1735210282`, or any
+///   number of digits after `@` such as `@0`
+///
+/// Times without a zone are taken to be UTC and times with an offset are
+/// converted to UTC. Fractional seconds are dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(pub i64);
+
+/// How timestamps are displayed by the renderers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TimestampFormat {
+    /// `Dec 26, 2024 10:51:22 UTC`
+    #[default]
+    Human,
+    /// `2024-12-26T10:51:22Z`
+    Iso8601,
+    /// `2024-12-26`
+    Date,
+    /// `1735210282`
+    Unix,
+}
+
+/// Error produced when a string is not a recognised [`Timestamp`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimestampError {
+    pub input: String,
+}
+
+impl fmt::Display for TimestampError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid timestamp `{}`", self.input)
+    }
+}
+
+impl std::error::Error for TimestampError {}
+
+impl Timestamp {
+    pub fn from_unix(seconds: i64) -> Self {
+        Timestamp(seconds)
+    }
+
+    pub fn unix(&self) -> i64 {
+        self.0
+    }
+
+    /// Timestamp of midnight UTC on the given calendar day, or `None` if the
+    /// date does not exist
+    pub fn from_date(year: i64, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        days_from_civil(year, month, day)
+            .checked_mul(SECONDS_PER_DAY)
+            .map(Timestamp)
+    }
+
+    /// Calendar date and time of day: (year, month, day, hour, minute, second)
+    pub fn to_utc(&self) -> (i64, u32, u32, u32, u32, u32) {
+        let days = self.0.div_euclid(SECONDS_PER_DAY);
+        let secs = self.0.rem_euclid(SECONDS_PER_DAY) as u32;
+        let (year, month, day) = civil_from_days(days);
+        (year, month, day, secs / 3600, secs / 60 % 60, secs % 60)
+    }
+
+    pub fn format(&self, format: TimestampFormat) -> String {
+        let (year, month, day, hour, minute, second) = self.to_utc();
+        match format {
+            TimestampFormat::Human => format!(
+                "{} {}, {} {:02}:{:02}:{:02} UTC",
+                &MONTHS[month as usize - 1][..3],
+                day,
+                year,
+                hour,
+                minute,
+                second
+            ),
+            TimestampFormat::Iso8601 => self.to_string(),
+            TimestampFormat::Date => format!("{:04}-{:02}-{:02}", year, month, day),
+            TimestampFormat::Unix => self.0.to_string(),
+        }
+    }
+}
+
+/// ISO-8601 in UTC, e.g. `2024-12-26T10:51:22Z`
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day, hour, minute, second) = self.to_utc();
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year, month, day, hour, minute, second
+        )
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = TimestampError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || TimestampError {
+            input: s.to_string(),
+        };
+
+        let trimmed = s.trim();
+        if let Some(seconds) = trimmed.strip_prefix('@') {
+            return number_i64(seconds).map(Timestamp).ok_or_else(err);
+        }
+        // Shorter numbers are more likely a mistyped year than a time in 1970
+        let digits = trimmed.strip_prefix('-').unwrap_or(trimmed);
+        if digits.len() >= 5 {
+            if let Some(seconds) = number_i64(trimmed) {
+                return Ok(Timestamp(seconds));
+            }
+        }
+
+        let text = trimmed
+            .strip_suffix("UTC")
+            .or_else(|| trimmed.strip_suffix('Z'))
+            .unwrap_or(trimmed)
+            .trim_end();
+
+        let (date, time) = if text.starts_with(|c: char| c.is_ascii_digit()) {
+            // 2024-01-01[( |T)12:00[:00]]
+            let (date, time) = match text.split_once(['T', ' ']) {
+                Some((date, time)) => (date, Some(time)),
+                None => (text, None),
+            };
+            let parts: Vec<&str> = date.split('-').collect();
+            let [year, month, day] = parts[..] else {
+                return Err(err());
+            };
+            if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+                return Err(err());
+            }
+            ((number(year), number(month), number(day)), time)
+        } else {
+            // Dec 26, 2024[ 10:51[:22]]
+            let (month, rest) = text.split_once(' ').ok_or_else(err)?;
+            let (day, rest) = rest.trim_start().split_once(',').ok_or_else(err)?;
+            let rest = rest.trim_start();
+            let (year, time) = match rest.split_once(' ') {
+                Some((year, time)) => (year, Some(time.trim())),
+                None => (rest, None),
+            };
+            if year.len() != 4 {
+                return Err(err());
+            }
+            ((number(year), month_number(month), number(day)), time)
+        };
+
+        let (Some(year), Some(month), Some(day)) = date else {
+            return Err(err());
+        };
+        let midnight = Timestamp::from_date(year as i64, month, day).ok_or_else(err)?;
+        let seconds = match time {
+            Some(time) => {
+                let (clock, offset) = split_offset(time).ok_or_else(err)?;
+                time_of_day(clock).ok_or_else(err)? - offset
+            }
+            None => 0,
+        };
+        Ok(Timestamp(midnight.0 + seconds))
+    }
+}
+
+/// Signed decimal such as `-42`, without separators
+fn number_i64(s: &str) -> Option<i64> {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// Unsigned decimal without sign or separators
+fn number(s: &str) -> Option<u32> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// Month number for an English month name or its three-letter abbreviation
+fn month_number(name: &str) -> Option<u32> {
+    MONTHS
+        .iter()
+        .position(|m| m.eq_ignore_ascii_case(name) || m[..3].eq_ignore_ascii_case(name))
+        .map(|idx| idx as u32 + 1)
+}
+
+/// Split a trailing UTC offset (`+HH:MM`, `-HHMM` or `+HH`, optionally after
+/// a space) off a time of day, returning the offset in seconds (0 without one)
+fn split_offset(time: &str) -> Option<(&str, i64)> {
+    let Some(idx) = time.find(['+', '-']) else {
+        return Some((time, 0));
+    };
+    let (clock, offset) = time.split_at(idx);
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let offset = &offset[1..];
+    let (hours, minutes) = match offset.split_once(':') {
+        Some(parts) => parts,
+        None if offset.len() == 4 => offset.split_at(2),
+        None => (offset, "00"),
+    };
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let (hours, minutes) = (number(hours)?, number(minutes)?);
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some((clock.trim_end(), sign * (hours * 3600 + minutes * 60) as i64))
+}
+
+/// Seconds since midnight for `HH:MM`, `HH:MM:SS` or `HH:MM:SS.fff`
+fn time_of_day(time: &str) -> Option<i64> {
+    let parts: Vec<&str> = time.split(':').collect();
+    let (seconds, fraction) = match parts.get(2) {
+        Some(seconds) => match seconds.split_once('.') {
+            Some((seconds, fraction)) => (Some(seconds), Some(fraction)),
+            None => (Some(*seconds), None),
+        },
+        None => (None, None),
+    };
+    if !(2..=3).contains(&parts.len())
+        || parts[..2].iter().chain(seconds.as_ref()).any(|p| p.len() != 2)
+    {
+        return None;
+    }
+    if let Some(fraction) = fraction {
+        number(fraction)?;
+    }
+    let hour = number(parts[0])?;
+    let minute = number(parts[1])?;
+    let second = seconds.map_or(Some(0), number)?;
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    Some((hour * 3600 + minute * 60 + second) as i64)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's
+/// `days_from_civil`)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of [`days_from_civil`]
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Timestamp {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Accepts any string format understood by `FromStr` (so free-form
+/// timestamps written by schema versions 1 and 2 still load) or a number of
+/// Unix seconds
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Timestamp {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TimestampVisitor;
+
+        impl serde::de::Visitor<'_> for TimestampVisitor {
+            type Value = Timestamp;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a date-time string or Unix seconds")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Timestamp, E> {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Timestamp, E> {
+                i64::try_from(v)
+                    .map(Timestamp)
+                    .map_err(|_| E::custom(format!("timestamp {} out of range", v)))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Timestamp, E> {
+                Ok(Timestamp(v))
+            }
+        }
+
+        deserializer.deserialize_any(TimestampVisitor)
+    }
+}
+
+/// Chronological order: by timestamp, then slot; transfers missing either
+/// sort after those that have it
+pub(crate) fn chronological(a: &Transfer, b: &Transfer) -> Ordering {
+    let key = |t: &Transfer| (t.timestamp.is_none(), t.timestamp, t.slot.is_none(), t.slot);
+    key(a).cmp(&key(b))
+}
+
+impl TransferGraph {
+    /// Every transfer in the graph in chronological order
+    ///
+    /// Ties (and untimed transfers, which come last) keep the graph's
+    /// address order.
+    pub fn transfers_by_time(&self) -> Vec<&Transfer> {
+        let mut transfers: Vec<&Transfer> = self
+            .sorted_nodes()
+            .into_iter()
+            .flat_map(|n| n.outgoing.iter())
+            .collect();
+        transfers.sort_by(|a, b| chronological(a, b));
+        transfers
+    }
+
+    /// Timed transfers with `start <= timestamp < end`, in chronological
+    /// order. An absent bound leaves that side of the window open.
+    pub fn transfers_between(
+        &self,
+        start: Option<Timestamp>,
+        end: Option<Timestamp>,
+    ) -> Vec<&Transfer> {
+        self.transfers_by_time()
+            .into_iter()
+            .filter(|t| t.timestamp.is_some_and(|ts| in_window(ts, start, end)))
+            .collect()
+    }
+
+    /// Earliest and latest transfer timestamps, if any transfer is timed
+    pub fn time_span(&self) -> Option<(Timestamp, Timestamp)> {
+        let mut times = self
+            .nodes
+            .values()
+            .flat_map(|n| n.outgoing.iter())
+            .filter_map(|t| t.timestamp);
+        let first = times.next()?;
+        Some(times.fold((first, first), |(lo, hi), ts| (lo.min(ts), hi.max(ts))))
+    }
+
+    /// Copy of the graph restricted to the transfers inside the window
+    /// `[start, end)`; untimed transfers are dropped
+    ///
+    /// Origin, target, token details, labels and render settings are kept.
+    /// Labelled nodes stay in the graph even if none of their transfers fall
+    /// inside the window.
+    pub fn time_window(&self, start: Option<Timestamp>, end: Option<Timestamp>) -> TransferGraph {
+        let mut graph = TransferGraph::with_config(self.render_config.clone());
+        graph.origin = self.origin.clone();
+        graph.target = self.target.clone();
+        graph.token_name = self.token_name.clone();
+        graph.token_mint = self.token_mint.clone();
+
+        for transfer in self.transfers_between(start, end) {
+            graph.add_transfer(transfer.clone());
+        }
+        for node in self.nodes.values().filter(|n| n.label.is_some()) {
+            graph
+                .nodes
+                .entry(node.address.clone())
+                .or_insert_with(|| GraphNode {
+                    address: node.address.clone(),
+                    label: None,
+                    incoming: Vec::new(),
+                    outgoing: Vec::new(),
+                })
+                .label = node.label.clone();
+        }
+        graph
+    }
+
+    /// Timestamp and slot of a transfer in the configured display format,
+    /// e.g. `Dec 26, 2024 10:51:22 UTC, slot 311245`
+    pub(crate) fn format_time(&self, transfer: &Transfer) -> Option<String> {
+        let time = transfer
+            .timestamp
+            .map(|ts| ts.format(self.render_config.timestamp_format));
+        let slot = transfer.slot.map(|slot| format!("slot {}", slot));
+        match (time, slot) {
+            (Some(time), Some(slot)) => Some(format!("{}, {}", time, slot)),
+            (time, slot) => time.or(slot),
+        }
+    }
+}
+
+fn in_window(ts: Timestamp, start: Option<Timestamp>, end: Option<Timestamp>) -> bool {
+    start.is_none_or(|start| ts >= start) && end.is_none_or(|end| ts < end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TokenAmount;
+
+    fn ts(s: &str) -> Timestamp {
+        s.parse().unwrap()
+    }
+
+    fn transfer(from: &str, to: &str, amount: u64, timestamp: Option<&str>) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: timestamp.map(ts),
+            slot: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        assert_eq!(ts("Dec 26, 2024 10:51:22 UTC"), Timestamp(1_735_210_282));
+        assert_eq!(ts("December 26, 2024 10:51:22"), Timestamp(1_735_210_282));
+        assert_eq!(ts("2024-12-26T10:51:22Z"), Timestamp(1_735_210_282));
+        assert_eq!(ts("2024-12-26 10:51:22"), Timestamp(1_735_210_282));
+        assert_eq!(ts("1735210282"), Timestamp(1_735_210_282));
+        assert_eq!(ts("Jan 1, 2025 01:05:19"), ts("2025-01-01 01:05:19"));
+        assert_eq!(ts("2024-01-01"), Timestamp(1_704_067_200));
+        assert_eq!(ts("Jan 1, 2024"), Timestamp(1_704_067_200));
+        assert_eq!(ts("2024-02-29 23:59"), Timestamp(1_709_251_140));
+        assert_eq!(ts("1969-12-31 23:59:59"), Timestamp(-1));
+
+        for bad in [
+            "",
+            "2024",
+            "-1",
+            "@",
+            "@1e3",
+            "2024-01-01T12:00:00.Z",
+            "2024-01-01T12:00:00.5x",
+            "2024-01-01T12:00:00+2:00",
+            "2024-01-01T12:00:00+24:00",
+            "yesterday",
+            "2023-02-29",
+            "2024-1-01",
+            "2024-01-01 24:00:00",
+            "Foo 1, 2024",
+            "Dec 32, 2024",
+            "Dec 26 2024",
+        ] {
+            assert!(bad.parse::<Timestamp>().is_err(), "{}", bad);
+        }
+        assert_eq!(
+            "nope".parse::<Timestamp>().unwrap_err().to_string(),
+            "invalid timestamp `nope`"
+        );
+    }
+
+    #[test]
+    fn test_parse_fractional_seconds() {
+        assert_eq!(ts("2024-01-01T12:00:00.000Z"), ts("2024-01-01T12:00:00Z"));
+        assert_eq!(ts("2024-01-01T12:00:00.999Z"), ts("2024-01-01T12:00:00Z"));
+        assert_eq!(ts("2024-01-01 12:00:00.5"), ts("2024-01-01 12:00:00"));
+    }
+
+    #[test]
+    fn test_parse_offsets() {
+        let noon = ts("2024-01-01T12:00:00Z");
+        assert_eq!(ts("2024-01-01T12:00:00+00:00"), noon);
+        assert_eq!(ts("2024-01-01T14:00:00+02:00"), noon);
+        assert_eq!(ts("2024-01-01T07:00:00-0500"), noon);
+        assert_eq!(ts("2024-01-01T13:00:00.000+01"), noon);
+        assert_eq!(ts("2024-01-01 14:30 +02:30"), noon);
+        assert_eq!(ts("Jan 1, 2024 14:00:00 +02:00"), noon);
+        assert_eq!(ts("2024-01-01T01:00:00+02:00"), ts("2023-12-31T23:00:00Z"));
+    }
+
+    #[test]
+    fn test_parse_unix_seconds() {
+        assert_eq!(ts("1735210282"), Timestamp(1_735_210_282));
+        assert_eq!(ts("86400"), Timestamp(86_400));
+        assert_eq!(ts("-86400"), Timestamp(-86_400));
+        assert_eq!(ts("@0"), Timestamp(0));
+        assert_eq!(ts("@2024"), Timestamp(2024));
+        assert_eq!(ts("@-1"), Timestamp(-1));
+    }
+
+    #[test]
+    fn test_format() {
+        let t = ts("Jan 1, 2025 01:05:19");
+        assert_eq!(t.format(TimestampFormat::Human), "Jan 1, 2025 01:05:19 UTC");
+        assert_eq!(t.format(TimestampFormat::Iso8601), "2025-01-01T01:05:19Z");
+        assert_eq!(t.format(TimestampFormat::Date), "2025-01-01");
+        assert_eq!(t.format(TimestampFormat::Unix), "1735693519");
+        assert_eq!(Timestamp(-1).to_string(), "1969-12-31T23:59:59Z");
+        assert_eq!(ts(&t.to_string()), t);
+    }
+
+    #[test]
+    fn test_time_ordering_and_windows() {
+        let mut graph = TransferGraph::new();
+        graph.add_transfer(transfer("A", "B", 1, Some("2024-01-03")));
+        graph.add_transfer(transfer("A", "C", 2, None));
+        graph.add_transfer(transfer("B", "C", 3, Some("2024-01-01")));
+        graph.add_transfer(Transfer {
+            slot: Some(7),
+            ..transfer("C", "D", 4, Some("2024-01-02"))
+        });
+        graph.set_node_label("A", "Mint".to_string());
+
+        let amounts = |transfers: Vec<&Transfer>| -> Vec<String> {
+            transfers.iter().map(|t| t.amount.to_string()).collect()
+        };
+        assert_eq!(amounts(graph.transfers_by_time()), ["3", "4", "1", "2"]);
+        assert_eq!(
+            amounts(graph.transfers_between(Some(ts("2024-01-02")), None)),
+            ["4", "1"]
+        );
+        assert_eq!(
+            amounts(graph.transfers_between(None, Some(ts("2024-01-03")))),
+            ["3", "4"]
+        );
+        assert_eq!(
+            graph.time_span(),
+            Some((ts("2024-01-01"), ts("2024-01-03")))
+        );
+
+        let window = graph.time_window(Some(ts("2024-01-02")), Some(ts("2024-01-03")));
+        assert_eq!(window.transfers_by_time().len(), 1);
+        assert_eq!(window.nodes.len(), 3);
+        assert_eq!(window.nodes["A"].label.as_deref(), Some("Mint"));
+        assert_eq!(
+            window.format_time(window.transfers_by_time()[0]).as_deref(),
+            Some("Jan 2, 2024 00:00:00 UTC, slot 7")
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+use crate::renderer::{EdgeVisit, NodeVisit, Renderer};
+use crate::{TraceDirection, TransferGraph};
+
+/// Numbers of the nodes of an ASCII tree, worked out with a walk of their
+/// own ahead of drawing so that every line can be written as it comes
+///
+/// Nodes are numbered from the top in the order they first appear. Only
+/// nodes that are referenced or drawn more than once show their number,
+/// and references read `↺ see #3 above` (or `below`).
+#[derive(Default)]
+pub(crate) struct TreeNumbers {
+    /// Node lines come after their funders instead of before their children
+    backward: bool,
+    numbers: HashMap<String, usize>,
+    /// Nodes referenced or drawn more than once
+    marked: HashSet<String>,
+    /// Nodes drawn so far
+    drawn: HashSet<String>,
+}
+
+impl TreeNumbers {
+    /// Number the tree `graph` draws in `direction`
+    pub(crate) fn new(graph: &TransferGraph, direction: TraceDirection) -> io::Result<Self> {
+        let mut numbers = TreeNumbers {
+            backward: direction == TraceDirection::Backward,
+            ..TreeNumbers::default()
+        };
+        let root = match direction {
+            TraceDirection::Backward => graph.target.as_deref(),
+            _ => graph.origin.as_deref(),
+        };
+        if let Some(root) = root {
+            graph.walk(&mut numbers, direction, root, None)?;
+        }
+        Ok(numbers)
+    }
+
+    /// What the line of `addr` ends with as it is drawn: ` #3` or nothing
+    pub(crate) fn node_suffix(&mut self, addr: &str) -> String {
+        self.drawn.insert(addr.to_string());
+        match self.numbers.get(addr) {
+            Some(number) if self.marked.contains(addr) => format!(" #{}", number),
+            _ => String::new(),
+        }
+    }
+
+    /// What an edge line to `addr`, drawn elsewhere, ends with
+    pub(crate) fn reference_suffix(&self, addr: &str) -> String {
+        match self.numbers.get(addr) {
+            Some(number) => {
+                let place = if self.drawn.contains(addr) {
+                    "above"
+                } else {
+                    "below"
+                };
+                format!("  ↺ see #{} {}", number, place)
+            }
+            None => String::new(),
+        }
+    }
+
+    fn line(&mut self, addr: &str) {
+        if self.numbers.contains_key(addr) {
+            self.marked.insert(addr.to_string());
+        } else {
+            let next = self.numbers.len() + 1;
+            self.numbers.insert(addr.to_string(), next);
+        }
+    }
+}
+
+impl Renderer for TreeNumbers {
+    fn node(&mut self, _graph: &TransferGraph, node: &NodeVisit<'_>) -> io::Result<()> {
+        if !self.backward {
+            self.line(node.address);
+        }
+        Ok(())
+    }
+
+    fn edge(&mut self, _graph: &TransferGraph, edge: &EdgeVisit<'_>) -> io::Result<()> {
+        if edge.reference {
+            let transfer = edge.transfer;
+            let addr = if self.backward {
+                &transfer.from
+            } else {
+                &transfer.to
+            };
+            self.marked.insert(addr.clone());
+        }
+        Ok(())
+    }
+
+    fn end_node(&mut self, _graph: &TransferGraph, node: &NodeVisit<'_>) -> io::Result<()> {
+        if self.backward {
+            self.line(node.address);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{TokenAmount, Transfer, TransferGraph};
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: TokenAmount::from(amount),
+            token_symbol: "SVMAI".to_string(),
+            timestamp: None,
+            slot: None,
+            note: None,
+        }
+    }
+
+    /// O splits into A and B, which both pay C, which pays D
+    fn diamond() -> TransferGraph {
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("O".to_string());
+        graph.render_config.show_header = false;
+        graph.render_config.show_paths_summary = false;
+        graph.render_config.show_stats_summary = false;
+        graph.add_transfer(transfer("O", "A", 10));
+        graph.add_transfer(transfer("O", "B", 20));
+        graph.add_transfer(transfer("A", "C", 10));
+        graph.add_transfer(transfer("B", "C", 20));
+        graph.add_transfer(transfer("C", "D", 30));
+        graph
+    }
+
+    fn tree(graph: &TransferGraph) -> String {
+        graph
+            .render_ascii()
+            .split_once("═\n\n")
+            .unwrap()
+            .1
+            .to_string()
+    }
+
+    #[test]
+    fn test_back_references() {
+        let expected = "\
+🏦 ORIGIN O
+      ├──────→ [10.00 SVMAI] ────────→ A
+      ○ A
+            └──────→ [10.00 SVMAI] ────────→ C
+            ○ C #3
+                  └──────→ [30.00 SVMAI] ────────→ D
+                  ○ D
+
+      └──────→ [20.00 SVMAI] ────────→ B
+      ○ B
+            └──────→ [20.00 SVMAI] ────────→ C  ↺ see #3 above
+";
+        assert_eq!(tree(&diamond()), expected);
+    }
+
+    #[test]
+    fn test_duplicate_shared_subtrees() {
+        let mut graph = diamond();
+        graph.render_config.tree.duplicate_shared_depth = 1;
+        let output = tree(&graph);
+        assert_eq!(output.matches("○ C #3\n").count(), 2);
+        assert!(output.contains("────────→ D  ↺ see #4 above\n"));
+        assert_eq!(output.matches("○ D #4\n").count(), 1);
+
+        graph.render_config.tree.duplicate_shared_depth = 2;
+        let output = tree(&graph);
+        assert_eq!(output.matches("○ D #4\n").count(), 2);
+        assert!(!output.contains("↺"));
+    }
+
+    #[test]
+    fn test_cycles_point_back() {
+        let mut graph = diamond();
+        graph.render_config.tree.duplicate_shared_depth = 5;
+        graph.add_transfer(transfer("D", "O", 1));
+        let output = tree(&graph);
+        assert!(output.starts_with("🏦 ORIGIN O #1\n"));
+        assert!(output.contains("────────→ O  ↺ see #1 above\n"));
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        // Numbering walks all 100,000 hops to find the one back to C0
+        let hops = 100_000;
+        let mut graph = TransferGraph::new();
+        graph.origin = Some("C0".to_string());
+        graph.render_config.show_header = false;
+        graph.render_config.show_paths_summary = false;
+        graph.render_config.show_cycles_summary = false;
+        graph.render_config.show_stats_summary = false;
+        graph.render_config.ascii.output_limits.max_bytes = Some(100_000);
+        for i in 0..hops {
+            graph.add_transfer(transfer(&format!("C{}", i), &format!("C{}", i + 1), 1));
+        }
+        graph.add_transfer(transfer(&format!("C{}", hops), "C0", 1));
+        let output = tree(&graph);
+        assert!(output.starts_with("🏦 ORIGIN C0 #1\n"));
+        assert!(output.contains("... output truncated after "));
+    }
+}